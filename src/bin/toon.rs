@@ -0,0 +1,478 @@
+//! `toon` - convert between JSON and TOON on the command line.
+//!
+//! Input is read from a file or stdin and the result is written to stdout.
+//!
+//! Exit codes:
+//! - `0` on success
+//! - `1` when `fmt --check` finds a file that is not formatted
+//! - `2` for invalid command-line usage
+//! - `65` when the input is not valid JSON or TOON
+//! - `74` when reading input or writing output fails
+
+use std::{
+    fs,
+    io::{
+        self,
+        Read,
+        Write,
+    },
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use clap::{
+    Args,
+    Parser,
+    Subcommand,
+    ValueEnum,
+};
+use rtoon::{
+    types::Indent,
+    DecodeOptions,
+    Delimiter,
+    DuplicateKeys,
+    EncodeOptions,
+    FloatFormat,
+    FormatOptions,
+    Limits,
+    ToonError,
+};
+
+const EXIT_UNFORMATTED: u8 = 1;
+const EXIT_DATA: u8 = 65;
+const EXIT_IO: u8 = 74;
+
+#[derive(Parser)]
+#[command(name = "toon", version, about = "Convert between JSON and TOON")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Convert JSON to TOON.
+    Encode(EncodeArgs),
+    /// Convert TOON to JSON.
+    Decode(DecodeArgs),
+    /// Rewrite TOON in canonical form.
+    Fmt(FmtArgs),
+    /// Compare the size of JSON and TOON encodings.
+    Stats(EncodeArgs),
+}
+
+#[derive(Args)]
+struct Input {
+    /// Input file. Reads from stdin if omitted or `-`.
+    file: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct Style {
+    /// Delimiter for array values and tabular rows.
+    #[arg(short, long, value_enum, default_value_t = DelimiterArg::Comma)]
+    delimiter: DelimiterArg,
+
+    /// Prefix for array lengths, e.g. `#` for `[#3]`.
+    #[arg(long, value_name = "CHAR")]
+    length_marker: Option<char>,
+
+    /// Number of spaces per indentation level.
+    #[arg(long, value_name = "N", default_value_t = 2, conflicts_with = "tabs")]
+    indent: usize,
+
+    /// Indent with tabs instead of spaces.
+    #[arg(long)]
+    tabs: bool,
+}
+
+#[derive(Args)]
+struct EncodeArgs {
+    #[command(flatten)]
+    input: Input,
+
+    #[command(flatten)]
+    style: Style,
+
+    /// Choose the delimiter for each array that needs the fewest quotes.
+    #[arg(long)]
+    auto_delimiter: bool,
+
+    /// Fold chains of single-key objects into dotted keys like `a.b.c`.
+    #[arg(long)]
+    key_folding: bool,
+
+    /// Write arrays of objects with differing keys or nested objects as
+    /// tables where possible.
+    #[arg(long)]
+    flatten_tabular: bool,
+
+    /// Round numbers that are not integers to N digits after the point.
+    #[arg(long, value_name = "N", conflicts_with = "significant_digits")]
+    fixed_decimals: Option<usize>,
+
+    /// Round numbers that are not integers to N significant digits.
+    #[arg(long, value_name = "N")]
+    significant_digits: Option<usize>,
+}
+
+#[derive(Args)]
+struct DecodeArgs {
+    #[command(flatten)]
+    input: Input,
+
+    /// Expected delimiter (auto-detected by default).
+    #[arg(short, long, value_enum)]
+    delimiter: Option<DelimiterArg>,
+
+    /// Disable strict validation of array lengths and row widths.
+    #[arg(long)]
+    no_strict: bool,
+
+    /// Disable type coercion of unquoted values.
+    #[arg(long)]
+    no_coerce: bool,
+
+    /// Expand unquoted dotted keys like `a.b.c` into nested objects.
+    #[arg(long)]
+    expand_paths: bool,
+
+    /// Read `~` cells and dotted columns written by `encode --flatten-tabular`.
+    #[arg(long)]
+    flatten_tabular: bool,
+
+    /// Fix common mistakes in model-written TOON instead of failing.
+    #[arg(long)]
+    repair: bool,
+
+    /// What to do when an object repeats a key [default: error, or
+    /// last-wins with --no-strict or --repair].
+    #[arg(long, value_enum, value_name = "POLICY")]
+    duplicate_keys: Option<DuplicateKeysArg>,
+
+    /// Deepest nesting of objects and arrays [default: 256].
+    #[arg(long, value_name = "N")]
+    max_depth: Option<usize>,
+
+    /// Largest input, in bytes.
+    #[arg(long, value_name = "BYTES")]
+    max_document_bytes: Option<usize>,
+
+    /// Longest key or string value, in bytes.
+    #[arg(long, value_name = "BYTES")]
+    max_string_length: Option<usize>,
+
+    /// Largest length an array header may declare.
+    #[arg(long, value_name = "N")]
+    max_array_length: Option<usize>,
+
+    /// Most keys a single object may have.
+    #[arg(long, value_name = "N")]
+    max_object_keys: Option<usize>,
+
+    /// Number of spaces per indentation level [default: 2].
+    #[arg(long, value_name = "N", conflicts_with_all = ["tabs", "auto_indent"])]
+    indent: Option<usize>,
+
+    /// The input is indented with tabs.
+    #[arg(long, conflicts_with = "auto_indent")]
+    tabs: bool,
+
+    /// Take the indentation unit from the first indented line.
+    #[arg(long)]
+    auto_indent: bool,
+
+    /// Pretty-print the JSON output.
+    #[arg(short, long)]
+    pretty: bool,
+}
+
+#[derive(Args)]
+struct FmtArgs {
+    /// Files to format. Reads from stdin if none are given.
+    files: Vec<PathBuf>,
+
+    /// Report files that are not formatted instead of printing the result.
+    #[arg(long)]
+    check: bool,
+
+    #[command(flatten)]
+    style: Style,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DelimiterArg {
+    Comma,
+    Tab,
+    Pipe,
+}
+
+impl From<DelimiterArg> for Delimiter {
+    fn from(arg: DelimiterArg) -> Self {
+        match arg {
+            DelimiterArg::Comma => Delimiter::Comma,
+            DelimiterArg::Tab => Delimiter::Tab,
+            DelimiterArg::Pipe => Delimiter::Pipe,
+        }
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DuplicateKeysArg {
+    Error,
+    FirstWins,
+    LastWins,
+    CollectArray,
+}
+
+impl From<DuplicateKeysArg> for DuplicateKeys {
+    fn from(arg: DuplicateKeysArg) -> Self {
+        match arg {
+            DuplicateKeysArg::Error => DuplicateKeys::Error,
+            DuplicateKeysArg::FirstWins => DuplicateKeys::FirstWins,
+            DuplicateKeysArg::LastWins => DuplicateKeys::LastWins,
+            DuplicateKeysArg::CollectArray => DuplicateKeys::CollectArray,
+        }
+    }
+}
+
+impl Style {
+    fn indent(&self) -> Indent {
+        if self.tabs {
+            Indent::Tabs
+        } else {
+            Indent::Spaces(self.indent)
+        }
+    }
+
+    fn encode_options(&self) -> EncodeOptions {
+        let mut options = EncodeOptions::new()
+            .with_delimiter(self.delimiter.into())
+            .with_indent(self.indent());
+        if let Some(marker) = self.length_marker {
+            options = options.with_length_marker(marker);
+        }
+        options
+    }
+
+    fn format_options(&self) -> FormatOptions {
+        let mut options = FormatOptions::new()
+            .with_delimiter(self.delimiter.into())
+            .with_indent(self.indent());
+        if let Some(marker) = self.length_marker {
+            options = options.with_length_marker(marker);
+        }
+        options
+    }
+}
+
+impl EncodeArgs {
+    fn options(&self) -> EncodeOptions {
+        let mut options = self
+            .style
+            .encode_options()
+            .with_auto_delimiter(self.auto_delimiter)
+            .with_key_folding(self.key_folding)
+            .with_flatten_tabular(self.flatten_tabular);
+        if let Some(decimals) = self.fixed_decimals {
+            options = options.with_float_format(FloatFormat::FixedDecimals(decimals));
+        }
+        if let Some(digits) = self.significant_digits {
+            options = options.with_float_format(FloatFormat::SignificantDigits(digits));
+        }
+        options
+    }
+}
+
+impl DecodeArgs {
+    fn options(&self) -> DecodeOptions {
+        let mut options = DecodeOptions::new()
+            .with_strict(!self.no_strict)
+            .with_coerce_types(!self.no_coerce)
+            .with_expand_paths(self.expand_paths)
+            .with_flatten_tabular(self.flatten_tabular);
+        if let Some(delimiter) = self.delimiter {
+            options = options.with_delimiter(delimiter.into());
+        }
+        if self.repair {
+            options = options.repair();
+        }
+        if let Some(policy) = self.duplicate_keys {
+            options = options.with_duplicate_keys(policy.into());
+        }
+        options
+            .with_indent(self.indent())
+            .with_limits(self.limits())
+    }
+
+    fn limits(&self) -> Limits {
+        let mut limits = Limits::new();
+        if let Some(depth) = self.max_depth {
+            limits = limits.with_max_depth(depth);
+        }
+        if let Some(bytes) = self.max_document_bytes {
+            limits = limits.with_max_document_bytes(bytes);
+        }
+        if let Some(length) = self.max_string_length {
+            limits = limits.with_max_string_length(length);
+        }
+        if let Some(length) = self.max_array_length {
+            limits = limits.with_max_array_length(length);
+        }
+        if let Some(keys) = self.max_object_keys {
+            limits = limits.with_max_object_keys(keys);
+        }
+        limits
+    }
+
+    fn indent(&self) -> Indent {
+        if self.tabs {
+            Indent::Tabs
+        } else if self.auto_indent {
+            Indent::Auto
+        } else {
+            self.indent.map_or_else(Indent::default, Indent::Spaces)
+        }
+    }
+}
+
+/// A failure, tagged with the exit code it maps to.
+enum Failure {
+    Unformatted,
+    Data(String),
+    Io(String),
+}
+
+impl From<ToonError> for Failure {
+    fn from(err: ToonError) -> Self {
+        let mut message = err.to_string();
+        if let Some(context) = err.context() {
+            message.push_str(&context.to_string());
+        }
+
+        match err {
+            ToonError::Io { .. } => Failure::Io(message),
+            _ => Failure::Data(message),
+        }
+    }
+}
+
+impl From<io::Error> for Failure {
+    fn from(err: io::Error) -> Self {
+        Failure::Io(err.to_string())
+    }
+}
+
+impl From<serde_json::Error> for Failure {
+    fn from(err: serde_json::Error) -> Self {
+        if err.is_io() {
+            Failure::Io(err.to_string())
+        } else {
+            Failure::Data(format!("Invalid JSON: {}", err))
+        }
+    }
+}
+
+fn read_input(input: &Input) -> Result<String, Failure> {
+    match &input.file {
+        Some(path) if path.as_os_str() != "-" => read_file(path),
+        _ => read_stdin(),
+    }
+}
+
+fn read_file(path: &PathBuf) -> Result<String, Failure> {
+    fs::read_to_string(path).map_err(|e| Failure::Io(format!("{}: {}", path.display(), e)))
+}
+
+fn read_stdin() -> Result<String, Failure> {
+    let mut buffer = String::new();
+    io::stdin().read_to_string(&mut buffer)?;
+    Ok(buffer)
+}
+
+fn write_output(output: &str) -> Result<(), Failure> {
+    let mut stdout = io::stdout().lock();
+    writeln!(stdout, "{}", output)?;
+    stdout.flush()?;
+    Ok(())
+}
+
+fn encode(args: &EncodeArgs) -> Result<(), Failure> {
+    let input = read_input(&args.input)?;
+    let value: serde_json::Value = serde_json::from_str(&input)?;
+    write_output(&rtoon::encode(&value, &args.options())?)
+}
+
+fn decode(args: &DecodeArgs) -> Result<(), Failure> {
+    let input = read_input(&args.input)?;
+    let value = rtoon::decode(&input, &args.options())?;
+    let output = if args.pretty {
+        serde_json::to_string_pretty(&value)?
+    } else {
+        serde_json::to_string(&value)?
+    };
+    write_output(&output)
+}
+
+fn stats(args: &EncodeArgs) -> Result<(), Failure> {
+    let input = read_input(&args.input)?;
+    let value: serde_json::Value = serde_json::from_str(&input)?;
+    let comparison = rtoon::stats::compare(&value, &args.options())?;
+    write_output(&comparison.to_string())
+}
+
+fn fmt(args: &FmtArgs) -> Result<(), Failure> {
+    let options = args.style.format_options();
+    let inputs = if args.files.is_empty() {
+        vec![(PathBuf::from("<stdin>"), read_stdin()?)]
+    } else {
+        args.files
+            .iter()
+            .map(|path| Ok((path.clone(), read_file(path)?)))
+            .collect::<Result<_, Failure>>()?
+    };
+
+    let mut unformatted = false;
+    for (path, input) in inputs {
+        let in_file = |err: ToonError| match Failure::from(err) {
+            Failure::Data(message) => Failure::Data(format!("{}: {}", path.display(), message)),
+            other => other,
+        };
+
+        if !args.check {
+            write_output(&rtoon::format(&input, &options).map_err(in_file)?)?;
+        } else if !rtoon::is_formatted(&input, &options).map_err(in_file)? {
+            eprintln!("{} is not formatted", path.display());
+            unformatted = true;
+        }
+    }
+
+    if unformatted {
+        return Err(Failure::Unformatted);
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match &cli.command {
+        Command::Encode(args) => encode(args),
+        Command::Decode(args) => decode(args),
+        Command::Fmt(args) => fmt(args),
+        Command::Stats(args) => stats(args),
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(Failure::Unformatted) => ExitCode::from(EXIT_UNFORMATTED),
+        Err(Failure::Data(message)) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(EXIT_DATA)
+        }
+        Err(Failure::Io(message)) => {
+            eprintln!("error: {}", message);
+            ExitCode::from(EXIT_IO)
+        }
+    }
+}
@@ -6,6 +6,10 @@ pub const STRUCTURAL_CHARS: &[char] = &['[', ']', '{', '}', ':', '-'];
 /// TOON keywords that must be quoted when used as strings.
 pub const KEYWORDS: &[&str] = &["null", "true", "false"];
 
+/// Cell marking a field that is absent from a row of a flattened tabular
+/// array.
+pub const ABSENT_MARKER: &str = "~";
+
 /// Default indentation size (2 spaces).
 pub const DEFAULT_INDENT: usize = 2;
 
@@ -0,0 +1,825 @@
+//! A lossless syntax tree for editing TOON documents in place.
+
+use std::{
+    fmt,
+    ops::Range,
+    str::FromStr,
+};
+
+use indexmap::IndexMap;
+
+use crate::{
+    constants::DEFAULT_INDENT,
+    decode::{
+        decode,
+        incremental::pointer,
+    },
+    encode::{
+        encode,
+        write_primitive_value,
+        writer::Writer,
+    },
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    types::{
+        DecodeOptions,
+        Delimiter,
+        EncodeOptions,
+        IntoJsonValue,
+        JsonValue,
+    },
+    utils::{
+        normalize,
+        string::{
+            split_by_delimiter,
+            unescape_string,
+        },
+    },
+};
+
+/// A TOON document that keeps its exact text.
+///
+/// Indentation, quoting, delimiters, blank lines and line endings are kept
+/// as written, so printing a document with [`Display`](fmt::Display) gives
+/// back the input byte for byte. Edits only touch the lines they change; new
+/// lines are written like [`encode`] would, indented to match their
+/// neighbours.
+///
+/// Paths are lists of object keys as they decode, so `["a.b"]` is the key
+/// `a.b` and not a nested key. Values are given as JSON.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::cst::Document;
+/// use serde_json::json;
+///
+/// let input = "name: \"My App\"\nserver:\n  host: localhost\n  port: 80\n\n\
+///              users[1|]{id|name}:\n  1|Ada\n";
+/// let mut doc: Document = input.parse()?;
+/// assert_eq!(doc.to_string(), input);
+///
+/// doc.set(&["server", "port"], json!(8080))?;
+/// doc.push_row(&["users"], json!({"id": 2, "name": "Bob | Jr."}))?;
+/// assert!(doc.remove(&["name"]));
+/// assert_eq!(
+///     doc.to_string(),
+///     "server:\n  host: localhost\n  port: 8080\n\n\
+///      users[2|]{id|name}:\n  1|Ada\n  2|\"Bob | Jr.\"\n"
+/// );
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    nodes: Vec<Node>,
+    /// Spaces per nesting level, used for new lines.
+    indent: usize,
+    /// Line ending used for new lines.
+    newline: String,
+    /// Whether the last line ends with a line ending.
+    final_newline: bool,
+}
+
+/// A line and the more indented lines that belong to it.
+#[derive(Debug, Clone, PartialEq)]
+struct Node {
+    /// The line without its line ending.
+    text: String,
+    /// `\n` or `\r\n`, or empty on a last line without one.
+    eol: String,
+    kind: Kind,
+    children: Vec<Node>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind {
+    Blank,
+    /// A `key: value`, `key:` or `key[N]...:` line.
+    Field {
+        key: String,
+        value: FieldValue,
+    },
+    /// Any other line, such as a tabular row or a list item.
+    Other,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FieldValue {
+    /// A primitive, at this byte range of the line.
+    Scalar(Range<usize>),
+    Object,
+    Array(Header),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Header {
+    /// Byte range of the `N` in `[N]`.
+    length: Range<usize>,
+    delimiter: Delimiter,
+    /// Field names of a tabular array.
+    fields: Option<Vec<String>>,
+}
+
+impl Document {
+    /// Parse a document. It must decode with the default (strict) options.
+    pub fn parse(input: &str) -> ToonResult<Self> {
+        decode(input, &DecodeOptions::default())?;
+
+        let lines = split_lines(input);
+        let nodes = parse_block(&lines, &mut 0, None, false);
+        let newline = lines
+            .iter()
+            .map(|&(_, eol)| eol)
+            .find(|eol| !eol.is_empty())
+            .unwrap_or("\n");
+        Ok(Self {
+            indent: detect_indent(&nodes).unwrap_or(DEFAULT_INDENT),
+            nodes,
+            newline: newline.to_string(),
+            final_newline: input.ends_with('\n'),
+        })
+    }
+
+    /// Set the value of a field, adding the field at the end of its object
+    /// if it is missing. Replacing one primitive with another keeps the rest
+    /// of the line; other values rewrite the field.
+    pub fn set<V: IntoJsonValue>(&mut self, path: &[&str], value: V) -> ToonResult<()> {
+        let (key, parents) = split_path(path)?;
+        let value = normalize(value.into_json_value());
+        let unit = self.indent;
+        let (nodes, indent) = self.object_mut(parents)?;
+
+        match find_field(nodes, key) {
+            Some(i) => {
+                let node = &mut nodes[i];
+                let scalar = match &node.kind {
+                    Kind::Field {
+                        value: FieldValue::Scalar(range),
+                        ..
+                    } if is_primitive(&value) => Some(range.clone()),
+                    _ => None,
+                };
+                if let Some(range) = scalar {
+                    node.text
+                        .replace_range(range, &scalar_text(&value, Delimiter::Comma)?);
+                    node.kind = classify(&node.text);
+                } else {
+                    let indent = indent_of(&node.text);
+                    nodes.splice(i..=i, field_nodes(key, value, indent, unit)?);
+                }
+            }
+            None => {
+                let at = content_end(nodes);
+                nodes.splice(at..at, field_nodes(key, value, indent, unit)?);
+            }
+        }
+
+        self.fix_line_ends();
+        Ok(())
+    }
+
+    /// Append a row to a tabular array and update its `[N]`. The row is an
+    /// object with a value for each field, or an array of values in field
+    /// order. Values are quoted for the array's delimiter.
+    pub fn push_row<V: IntoJsonValue>(&mut self, path: &[&str], row: V) -> ToonResult<()> {
+        let unit = self.indent;
+        let node = self.field_mut(path)?;
+        let (fields, delimiter) = match &node.kind {
+            Kind::Field {
+                value:
+                    FieldValue::Array(Header {
+                        fields: Some(fields),
+                        delimiter,
+                        ..
+                    }),
+                ..
+            } => (fields.clone(), *delimiter),
+            kind => {
+                return Err(ToonError::TypeMismatch {
+                    expected: "tabular array".to_string(),
+                    found: kind_name(kind).to_string(),
+                })
+            }
+        };
+
+        let cells = match normalize(row.into_json_value()) {
+            JsonValue::Object(mut object) => {
+                let cells = fields
+                    .iter()
+                    .map(|field| {
+                        object.shift_remove(field).ok_or_else(|| {
+                            ToonError::InvalidInput(format!("Row has no value for '{}'", field))
+                        })
+                    })
+                    .collect::<ToonResult<Vec<_>>>()?;
+                if let Some(extra) = object.keys().next() {
+                    return Err(ToonError::InvalidInput(format!(
+                        "Row field '{}' is not in the table header",
+                        extra
+                    )));
+                }
+                cells
+            }
+            JsonValue::Array(cells) if cells.len() == fields.len() => cells,
+            other => {
+                return Err(ToonError::TypeMismatch {
+                    expected: format!("object or array of {} values", fields.len()),
+                    found: other.type_name().to_string(),
+                })
+            }
+        };
+
+        let mut text = " ".repeat(child_indent(node, unit));
+        for (i, cell) in cells.iter().enumerate() {
+            if i > 0 {
+                text.push(delimiter.as_char());
+            }
+            text.push_str(&scalar_text(cell, delimiter)?);
+        }
+        let at = content_end(&node.children);
+        node.children.insert(
+            at,
+            Node {
+                text,
+                eol: String::new(),
+                kind: Kind::Other,
+                children: Vec::new(),
+            },
+        );
+        update_length(node);
+
+        self.fix_line_ends();
+        Ok(())
+    }
+
+    /// Remove a field, or a row of a tabular array given by its index.
+    /// Removing a row updates the array's `[N]`. Returns whether anything was
+    /// removed.
+    pub fn remove(&mut self, path: &[&str]) -> bool {
+        let Some((last, parents)) = path.split_last() else {
+            return false;
+        };
+
+        // A numeric segment is a row index only under an array; under an
+        // object it is a key like any other.
+        let row = last.parse::<usize>().ok().filter(|_| {
+            self.field_mut(parents).is_ok_and(|node| {
+                matches!(
+                    node.kind,
+                    Kind::Field {
+                        value: FieldValue::Array(_),
+                        ..
+                    }
+                )
+            })
+        });
+        let removed = if let Some(index) = row {
+            self.field_mut(parents)
+                .is_ok_and(|node| remove_row(node, index))
+        } else if let Ok((nodes, _)) = self.object_mut(parents) {
+            find_field(nodes, last).map(|i| nodes.remove(i)).is_some()
+        } else {
+            false
+        };
+
+        if removed {
+            self.fix_line_ends();
+        }
+        removed
+    }
+
+    /// The children of the object at `path`, and the indentation of its
+    /// fields.
+    fn object_mut(&mut self, path: &[&str]) -> ToonResult<(&mut Vec<Node>, usize)> {
+        let unit = self.indent;
+        let mut nodes = &mut self.nodes;
+        let mut indent = 0;
+
+        for depth in 0..path.len() {
+            let Some(i) = find_field(nodes, path[depth]) else {
+                return Err(not_found(&path[..=depth]));
+            };
+            let node = &mut nodes[i];
+            if !matches!(
+                node.kind,
+                Kind::Field {
+                    value: FieldValue::Object,
+                    ..
+                }
+            ) {
+                return Err(ToonError::TypeMismatch {
+                    expected: "object".to_string(),
+                    found: kind_name(&node.kind).to_string(),
+                });
+            }
+            indent = indent_of(&node.text) + unit;
+            nodes = &mut node.children;
+        }
+
+        if nodes.iter().any(|node| node.kind == Kind::Other) {
+            return Err(ToonError::TypeMismatch {
+                expected: "object".to_string(),
+                found: "root array or primitive".to_string(),
+            });
+        }
+        if let Some(first) = nodes.iter().find(|node| node.kind != Kind::Blank) {
+            indent = indent_of(&first.text);
+        }
+        Ok((nodes, indent))
+    }
+
+    /// The field at `path`.
+    fn field_mut(&mut self, path: &[&str]) -> ToonResult<&mut Node> {
+        let (key, parents) = split_path(path)?;
+        let (nodes, _) = self.object_mut(parents)?;
+        match find_field(nodes, key) {
+            Some(i) => Ok(&mut nodes[i]),
+            None => Err(not_found(path)),
+        }
+    }
+
+    /// Give every line but the last a line ending, and the last one a line
+    /// ending only if the input had one.
+    fn fix_line_ends(&mut self) {
+        fn collect<'n>(nodes: &'n mut [Node], eols: &mut Vec<&'n mut String>) {
+            for node in nodes {
+                eols.push(&mut node.eol);
+                collect(&mut node.children, eols);
+            }
+        }
+
+        let mut eols = Vec::new();
+        collect(&mut self.nodes, &mut eols);
+        let count = eols.len();
+        for (i, eol) in eols.into_iter().enumerate() {
+            if i + 1 == count && !self.final_newline {
+                eol.clear();
+            } else if eol.is_empty() {
+                eol.clone_from(&self.newline);
+            }
+        }
+    }
+}
+
+impl FromStr for Document {
+    type Err = ToonError;
+
+    fn from_str(s: &str) -> ToonResult<Self> {
+        Self::parse(s)
+    }
+}
+
+impl fmt::Display for Document {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn write(f: &mut fmt::Formatter<'_>, nodes: &[Node]) -> fmt::Result {
+            for node in nodes {
+                f.write_str(&node.text)?;
+                f.write_str(&node.eol)?;
+                write(f, &node.children)?;
+            }
+            Ok(())
+        }
+        write(f, &self.nodes)
+    }
+}
+
+/// Split input into lines and their line endings.
+fn split_lines(input: &str) -> Vec<(&str, &str)> {
+    input
+        .split_inclusive('\n')
+        .map(|line| {
+            let text = line.trim_end_matches('\n').trim_end_matches('\r');
+            (text, &line[text.len()..])
+        })
+        .collect()
+}
+
+/// Read the lines that belong to a block: those more indented than
+/// `parent`, or every line at the root. Blank lines belong to the block of
+/// the line that follows them.
+fn parse_block(
+    lines: &[(&str, &str)],
+    pos: &mut usize,
+    parent: Option<usize>,
+    rows: bool,
+) -> Vec<Node> {
+    let inside = |indent: usize| parent.is_none_or(|parent| indent > parent);
+    let mut nodes = Vec::new();
+
+    while let Some(&(text, eol)) = lines.get(*pos) {
+        let kind = if text.trim().is_empty() {
+            let next = lines[*pos..]
+                .iter()
+                .find(|(text, _)| !text.trim().is_empty());
+            if !next.is_some_and(|(text, _)| inside(indent_of(text))) && parent.is_some() {
+                break;
+            }
+            Kind::Blank
+        } else if !inside(indent_of(text)) {
+            break;
+        } else if rows {
+            Kind::Other
+        } else {
+            classify(text)
+        };
+        *pos += 1;
+
+        let children = if kind == Kind::Blank {
+            Vec::new()
+        } else {
+            let tabular = matches!(
+                &kind,
+                Kind::Field {
+                    value: FieldValue::Array(Header {
+                        fields: Some(_),
+                        ..
+                    }),
+                    ..
+                }
+            );
+            parse_block(lines, pos, Some(indent_of(text)), tabular)
+        };
+        nodes.push(Node {
+            text: text.to_string(),
+            eol: eol.to_string(),
+            kind,
+            children,
+        });
+    }
+    nodes
+}
+
+/// Work out what a non-blank line holds.
+fn classify(text: &str) -> Kind {
+    let start = indent_of(text);
+    let rest = &text[start..];
+    if rest == "-" || rest.starts_with("- ") {
+        return Kind::Other;
+    }
+
+    let (key, key_end) = if rest.starts_with('"') {
+        let mut escaped = false;
+        let close = rest.char_indices().skip(1).find(|&(_, ch)| {
+            let close = ch == '"' && !escaped;
+            escaped = ch == '\\' && !escaped;
+            close
+        });
+        match close {
+            Some((close, _)) => (unescape_string(&rest[1..close]), start + close + 1),
+            None => return Kind::Other,
+        }
+    } else {
+        match rest.find([':', '[']) {
+            Some(0) | None => return Kind::Other,
+            Some(end) => (rest[..end].to_string(), start + end),
+        }
+    };
+
+    let value = if text[key_end..].starts_with(':') {
+        let value_start = key_end + 1 + indent_of(&text[key_end + 1..]);
+        let value_end = text.trim_end().len();
+        if value_start < value_end {
+            FieldValue::Scalar(value_start..value_end)
+        } else {
+            FieldValue::Object
+        }
+    } else {
+        match parse_header(text, key_end) {
+            Some(header) => FieldValue::Array(header),
+            None => return Kind::Other,
+        }
+    };
+    Kind::Field { key, value }
+}
+
+/// Parse the `[N]{fields}:` of an array header that starts at byte `open`.
+fn parse_header(text: &str, open: usize) -> Option<Header> {
+    let close = open + text[open..].find(']')?;
+    let digits = open + 1 + usize::from(text[open + 1..].starts_with('#'));
+    let digits_end = digits
+        + text[digits..close]
+            .bytes()
+            .take_while(u8::is_ascii_digit)
+            .count();
+    let delimiter = text[digits_end..close]
+        .chars()
+        .next()
+        .and_then(Delimiter::from_char)
+        .unwrap_or(Delimiter::Comma);
+
+    let mut after = close + 1;
+    let fields = if text[after..].starts_with('{') {
+        let end = after + text[after..].find('}')?;
+        let fields = split_by_delimiter(&text[after + 1..end], delimiter)
+            .iter()
+            .map(|field| unquote(field))
+            .collect();
+        after = end + 1;
+        Some(fields)
+    } else {
+        None
+    };
+
+    text[after..].starts_with(':').then_some(Header {
+        length: digits..digits_end,
+        delimiter,
+        fields,
+    })
+}
+
+fn unquote(s: &str) -> String {
+    match s.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(inner) => unescape_string(inner),
+        None => s.to_string(),
+    }
+}
+
+fn indent_of(text: &str) -> usize {
+    text.len() - text.trim_start_matches(' ').len()
+}
+
+/// The indentation step between the first field and its children.
+fn detect_indent(nodes: &[Node]) -> Option<usize> {
+    nodes.iter().find_map(|node| {
+        match node.children.iter().find(|child| child.kind != Kind::Blank) {
+            Some(child) => Some(indent_of(&child.text) - indent_of(&node.text)),
+            None => detect_indent(&node.children),
+        }
+    })
+}
+
+/// Indentation for a new line under `node`.
+fn child_indent(node: &Node, unit: usize) -> usize {
+    node.children
+        .iter()
+        .find(|child| child.kind != Kind::Blank)
+        .map_or(indent_of(&node.text) + unit, |child| indent_of(&child.text))
+}
+
+/// The position after the last non-blank node.
+fn content_end(nodes: &[Node]) -> usize {
+    nodes
+        .iter()
+        .rposition(|node| node.kind != Kind::Blank)
+        .map_or(0, |i| i + 1)
+}
+
+/// The last field with the given key.
+fn find_field(nodes: &[Node], key: &str) -> Option<usize> {
+    nodes
+        .iter()
+        .rposition(|node| matches!(&node.kind, Kind::Field { key: k, .. } if k == key))
+}
+
+/// Remove row `index` of a tabular array.
+fn remove_row(node: &mut Node, index: usize) -> bool {
+    if !matches!(
+        node.kind,
+        Kind::Field {
+            value: FieldValue::Array(Header {
+                fields: Some(_),
+                ..
+            }),
+            ..
+        }
+    ) {
+        return false;
+    }
+    let row = node
+        .children
+        .iter()
+        .enumerate()
+        .filter(|(_, child)| child.kind != Kind::Blank)
+        .nth(index)
+        .map(|(i, _)| i);
+    match row {
+        Some(i) => {
+            node.children.remove(i);
+            update_length(node);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Rewrite the `[N]` of an array header to its number of rows.
+fn update_length(node: &mut Node) {
+    if let Kind::Field {
+        value: FieldValue::Array(header),
+        ..
+    } = &node.kind
+    {
+        let count = node
+            .children
+            .iter()
+            .filter(|child| child.kind != Kind::Blank)
+            .count();
+        node.text
+            .replace_range(header.length.clone(), &count.to_string());
+        node.kind = classify(&node.text);
+    }
+}
+
+/// Encode a field as lines indented by `indent` spaces.
+fn field_nodes(key: &str, value: JsonValue, indent: usize, unit: usize) -> ToonResult<Vec<Node>> {
+    let mut object = IndexMap::new();
+    object.insert(key.to_string(), value);
+    let toon = encode(
+        JsonValue::Object(object),
+        &EncodeOptions::new().with_spaces(unit),
+    )?;
+
+    let prefix = " ".repeat(indent);
+    let lines: Vec<String> = toon.lines().map(|line| format!("{prefix}{line}")).collect();
+    let lines: Vec<(&str, &str)> = lines.iter().map(|line| (line.as_str(), "")).collect();
+    Ok(parse_block(&lines, &mut 0, None, false))
+}
+
+/// A primitive as written in a document, quoted for `delimiter`.
+fn scalar_text(value: &JsonValue, delimiter: Delimiter) -> ToonResult<String> {
+    let mut writer = Writer::new(EncodeOptions::new().with_delimiter(delimiter));
+    write_primitive_value(&mut writer, value)?;
+    Ok(writer.finish())
+}
+
+fn is_primitive(value: &JsonValue) -> bool {
+    !matches!(value, JsonValue::Array(_) | JsonValue::Object(_))
+}
+
+fn kind_name(kind: &Kind) -> &'static str {
+    match kind {
+        Kind::Field {
+            value: FieldValue::Scalar(_),
+            ..
+        } => "primitive",
+        Kind::Field {
+            value: FieldValue::Object,
+            ..
+        } => "object",
+        Kind::Field {
+            value: FieldValue::Array(_),
+            ..
+        } => "array",
+        Kind::Blank | Kind::Other => "line",
+    }
+}
+
+fn split_path<'p>(path: &'p [&'p str]) -> ToonResult<(&'p str, &'p [&'p str])> {
+    match path.split_last() {
+        Some((key, parents)) => Ok((key, parents)),
+        None => Err(ToonError::InvalidInput(
+            "Path must not be empty".to_string(),
+        )),
+    }
+}
+
+fn not_found(path: &[&str]) -> ToonError {
+    let segments: Vec<String> = path.iter().map(|s| s.to_string()).collect();
+    ToonError::InvalidInput(format!("No field at '{}'", pointer(&segments)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn edit(input: &str, f: impl FnOnce(&mut Document)) -> String {
+        let mut doc = Document::parse(input).unwrap();
+        f(&mut doc);
+        let output = doc.to_string();
+        crate::decode_default(&output).unwrap();
+        output
+    }
+
+    #[test]
+    fn test_unchanged_documents_print_identically() {
+        for input in [
+            "",
+            "a: 1",
+            "a: 1\n",
+            "\"quoted key\":   \"x\"  \r\nb:\r\n  c: true\r\n",
+            "a:\n    deep:\n        x: 1\n\n\nb[2|]: x|\"y|z\"\nrows[#2]{\"id\",name}:\n  1,A\n  \
+             2,B\n\n",
+            "items[2]:\n  - [2]: 1,2\n  - x\n",
+            "[3]: a,b,c\n",
+            "hello",
+        ] {
+            let doc: Document = input.parse().unwrap();
+            assert_eq!(doc.to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_set_keeps_formatting() {
+        let input = "\"name\":   Ada  \nmeta:\n    tags[1]: x\n";
+        assert_eq!(
+            edit(input, |doc| doc.set(&["name"], json!("Ada L")).unwrap()),
+            "\"name\":   \"Ada L\"  \nmeta:\n    tags[1]: x\n"
+        );
+        assert_eq!(
+            edit(input, |doc| doc.set(&["meta", "size"], json!(3)).unwrap()),
+            "\"name\":   Ada  \nmeta:\n    tags[1]: x\n    size: 3\n"
+        );
+        assert_eq!(
+            edit(input, |doc| doc
+                .set(&["meta", "tags"], json!(["a", "b"]))
+                .unwrap()),
+            "\"name\":   Ada  \nmeta:\n    tags[2]: a,b\n"
+        );
+        assert_eq!(
+            edit(input, |doc| doc
+                .set(&["new"], json!({"x": {"y": 1}}))
+                .unwrap()),
+            "\"name\":   Ada  \nmeta:\n    tags[1]: x\nnew:\n    x:\n        y: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_set_line_endings() {
+        assert_eq!(
+            edit("a: 1", |doc| doc.set(&["b"], json!(2)).unwrap()),
+            "a: 1\nb: 2"
+        );
+        assert_eq!(
+            edit("a: 1\r\n\r\n", |doc| doc.set(&["b"], json!(null)).unwrap()),
+            "a: 1\r\nb: null\r\n\r\n"
+        );
+        assert_eq!(
+            edit("a:\n", |doc| doc.set(&["a", "b"], json!("x")).unwrap()),
+            "a:\n  b: x\n"
+        );
+        assert_eq!(
+            edit("", |doc| doc.set(&["a"], json!(true)).unwrap()),
+            "a: true"
+        );
+    }
+
+    #[test]
+    fn test_push_and_remove_rows() {
+        let input = "users[#2\t]{id\tname}:\n  1\tAda\n  2\tBob\nnext: 1\n";
+        assert_eq!(
+            edit(input, |doc| {
+                doc.push_row(&["users"], json!([3, "C\td"])).unwrap();
+            }),
+            "users[#3\t]{id\tname}:\n  1\tAda\n  2\tBob\n  3\t\"C\\td\"\nnext: 1\n"
+        );
+        assert_eq!(
+            edit(input, |doc| assert!(doc.remove(&["users", "0"]))),
+            "users[#1\t]{id\tname}:\n  2\tBob\nnext: 1\n"
+        );
+        assert_eq!(
+            edit("t[0]{a,b}:", |doc| {
+                doc.push_row(&["t"], json!({"b": "x,y", "a": 1})).unwrap();
+            }),
+            "t[1]{a,b}:\n  1,\"x,y\""
+        );
+
+        let mut doc = Document::parse(input).unwrap();
+        assert!(doc.push_row(&["users"], json!({"id": 3})).is_err());
+        assert!(doc
+            .push_row(&["users"], json!({"id": 3, "name": "C", "x": 1}))
+            .is_err());
+        assert!(doc.push_row(&["next"], json!([1])).is_err());
+        assert!(doc.push_row(&["missing"], json!([1])).is_err());
+        assert_eq!(doc.to_string(), input);
+    }
+
+    #[test]
+    fn test_remove_fields() {
+        let input = "a: 1\nb:\n  c: 2\n  d[2]: x,y\n\ne: 3\n";
+        assert_eq!(
+            edit(input, |doc| assert!(doc.remove(&["b"]))),
+            "a: 1\n\ne: 3\n"
+        );
+        assert_eq!(
+            edit(input, |doc| assert!(doc.remove(&["b", "d"]))),
+            "a: 1\nb:\n  c: 2\n\ne: 3\n"
+        );
+        assert_eq!(
+            edit("a: 1\nb: 2", |doc| assert!(doc.remove(&["b"]))),
+            "a: 1"
+        );
+
+        let mut doc = Document::parse(input).unwrap();
+        assert!(!doc.remove(&["x"]));
+        assert!(!doc.remove(&["a", "x"]));
+        assert!(!doc.remove(&["b", "d", "0"]));
+        assert!(!doc.remove(&[]));
+        assert_eq!(doc.to_string(), input);
+
+        // A numeric key of an object is removed like any other key.
+        assert_eq!(
+            edit("a:\n  \"0\": 1\n  b: 2", |doc| assert!(
+                doc.remove(&["a", "0"])
+            )),
+            "a:\n  b: 2"
+        );
+
+        let mut doc = Document::parse("[2]: a,b").unwrap();
+        assert!(doc.set(&["a"], json!(1)).is_err());
+        assert!(!doc.remove(&["0"]));
+    }
+}
@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+
+use serde::de::{
+    self,
+    DeserializeSeed,
+    IgnoredAny,
+    Visitor,
+};
+#[cfg(feature = "arbitrary_precision")]
+use serde::de::{
+    value::BorrowedStrDeserializer,
+    IntoDeserializer,
+};
+
+use crate::{
+    de::{
+        deserialize_str,
+        NodeDeserializer,
+    },
+    decode::parser::{
+        ArrayCursor,
+        Node,
+        ObjectCursor,
+        Parser,
+        RowCursor,
+    },
+    error::{
+        ToonError,
+        ToonResult,
+    },
+};
+
+/// Map access over the fields of an object.
+pub(crate) struct ObjectAccess<'p, 'de> {
+    parser: &'p mut Parser<'de>,
+    cursor: ObjectCursor<'de>,
+    /// The line of each key read so far, for the duplicate key check.
+    keys: HashMap<String, usize>,
+    count: usize,
+}
+
+impl<'p, 'de> ObjectAccess<'p, 'de> {
+    pub(crate) fn new(parser: &'p mut Parser<'de>, cursor: ObjectCursor<'de>) -> Self {
+        Self {
+            parser,
+            cursor,
+            keys: HashMap::new(),
+            count: 0,
+        }
+    }
+
+    /// Skip any fields the visitor did not consume.
+    pub(crate) fn finish(mut self) -> ToonResult<()> {
+        while let Some(key) = self.parser.next_key(&mut self.cursor)? {
+            self.count += 1;
+            self.parser.check_key_count(self.count)?;
+            self.parser.check_duplicate_key(&key, &mut self.keys)?;
+            let node = self.parser.parse_field(&self.cursor)?;
+            self.parser.build(node)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> de::MapAccess<'de> for ObjectAccess<'_, 'de> {
+    type Error = ToonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> ToonResult<Option<K::Value>> {
+        match self.parser.next_key(&mut self.cursor)? {
+            Some(key) => {
+                self.count += 1;
+                self.parser.check_key_count(self.count)?;
+                self.parser.check_duplicate_key(&key, &mut self.keys)?;
+                deserialize_str(seed, key).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> ToonResult<V::Value> {
+        let node = self.parser.parse_field(&self.cursor)?;
+        seed.deserialize(NodeDeserializer {
+            parser: self.parser,
+            node,
+        })
+    }
+}
+
+/// Sequence access over the elements of an array.
+pub(crate) struct ArrayAccess<'p, 'de> {
+    parser: &'p mut Parser<'de>,
+    cursor: ArrayCursor<'de>,
+    count: usize,
+}
+
+impl<'p, 'de> ArrayAccess<'p, 'de> {
+    pub(crate) fn new(parser: &'p mut Parser<'de>, cursor: ArrayCursor<'de>) -> Self {
+        Self {
+            parser,
+            cursor,
+            count: 0,
+        }
+    }
+
+    /// Skip any elements the visitor did not consume and validate the
+    /// declared length.
+    pub(crate) fn finish(mut self) -> ToonResult<()> {
+        while let Some(node) = self.parser.next_element(&mut self.cursor)? {
+            self.parser.build(node)?;
+            self.count += 1;
+        }
+        self.parser.finish_array(&self.cursor, self.count)
+    }
+}
+
+impl<'de> de::SeqAccess<'de> for ArrayAccess<'_, 'de> {
+    type Error = ToonError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> ToonResult<Option<T::Value>> {
+        match self.parser.next_element(&mut self.cursor)? {
+            Some(node) => {
+                self.count += 1;
+                seed.deserialize(NodeDeserializer {
+                    parser: self.parser,
+                    node,
+                })
+                .map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn size_hint(&self) -> Option<usize> {
+        Some(self.cursor.len() - self.count)
+    }
+}
+
+/// Map access over the cells of a tabular row, keyed by the header fields.
+pub(crate) struct RowAccess<'p, 'de> {
+    parser: &'p mut Parser<'de>,
+    cursor: RowCursor<'de>,
+}
+
+impl<'p, 'de> RowAccess<'p, 'de> {
+    pub(crate) fn new(parser: &'p mut Parser<'de>, cursor: RowCursor<'de>) -> Self {
+        Self { parser, cursor }
+    }
+
+    /// Skip any cells the visitor did not consume.
+    pub(crate) fn finish(mut self) -> ToonResult<()> {
+        while self.parser.next_cell(&mut self.cursor)?.is_some() {
+            self.parser.parse_scalar()?;
+        }
+        Ok(())
+    }
+}
+
+impl<'de> de::MapAccess<'de> for RowAccess<'_, 'de> {
+    type Error = ToonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> ToonResult<Option<K::Value>> {
+        match self.parser.next_cell(&mut self.cursor)? {
+            Some(index) => deserialize_str(seed, self.cursor.field(index).clone()).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> ToonResult<V::Value> {
+        let scalar = self.parser.parse_scalar()?;
+        seed.deserialize(NodeDeserializer {
+            parser: self.parser,
+            node: Node::Primitive(scalar),
+        })
+    }
+}
+
+/// Enum access for the externally tagged `{ Variant: value }` form.
+pub(crate) struct EnumAccess<'p, 'de> {
+    parser: &'p mut Parser<'de>,
+    cursor: ObjectCursor<'de>,
+}
+
+impl<'p, 'de> EnumAccess<'p, 'de> {
+    pub(crate) fn new(parser: &'p mut Parser<'de>, cursor: ObjectCursor<'de>) -> Self {
+        Self { parser, cursor }
+    }
+}
+
+impl<'p, 'de> de::EnumAccess<'de> for EnumAccess<'p, 'de> {
+    type Error = ToonError;
+    type Variant = VariantAccess<'p, 'de>;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(
+        mut self,
+        seed: V,
+    ) -> ToonResult<(V::Value, Self::Variant)> {
+        let variant = self.parser.next_key(&mut self.cursor)?.ok_or_else(|| {
+            ToonError::DeserializationError("Expected enum variant, found empty object".into())
+        })?;
+        let value = deserialize_str(seed, variant)?;
+        Ok((
+            value,
+            VariantAccess {
+                parser: self.parser,
+                cursor: self.cursor,
+            },
+        ))
+    }
+}
+
+/// Access to the content of an externally tagged enum variant.
+pub(crate) struct VariantAccess<'p, 'de> {
+    parser: &'p mut Parser<'de>,
+    cursor: ObjectCursor<'de>,
+}
+
+impl<'de> VariantAccess<'_, 'de> {
+    fn content<T>(
+        mut self,
+        f: impl FnOnce(NodeDeserializer<'_, 'de>) -> ToonResult<T>,
+    ) -> ToonResult<T> {
+        let node = self.parser.parse_field(&self.cursor)?;
+        let value = f(NodeDeserializer {
+            parser: &mut *self.parser,
+            node,
+        })?;
+
+        if self.parser.next_key(&mut self.cursor)?.is_some() {
+            return Err(ToonError::DeserializationError(
+                "Expected a single key for enum variant".to_string(),
+            ));
+        }
+        Ok(value)
+    }
+}
+
+impl<'de> de::VariantAccess<'de> for VariantAccess<'_, 'de> {
+    type Error = ToonError;
+
+    fn unit_variant(self) -> ToonResult<()> {
+        self.content(|de| de::Deserialize::deserialize(de).map(|IgnoredAny| ()))
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> ToonResult<T::Value> {
+        self.content(|de| seed.deserialize(de))
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> ToonResult<V::Value> {
+        self.content(|de| de::Deserializer::deserialize_seq(de, visitor))
+    }
+
+    fn struct_variant<V: Visitor<'de>>(
+        self,
+        _fields: &'static [&'static str],
+        visitor: V,
+    ) -> ToonResult<V::Value> {
+        self.content(|de| de::Deserializer::deserialize_map(de, visitor))
+    }
+}
+
+/// Map access for serde_json's arbitrary precision number form: a single
+/// entry whose value is the number's text.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) struct NumberAccess(pub(crate) Option<String>);
+
+#[cfg(feature = "arbitrary_precision")]
+impl<'de> de::MapAccess<'de> for NumberAccess {
+    type Error = ToonError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> ToonResult<Option<K::Value>> {
+        match self.0 {
+            Some(_) => seed
+                .deserialize(BorrowedStrDeserializer::new(crate::ser::NUMBER_TOKEN))
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> ToonResult<V::Value> {
+        let text = self.0.take().ok_or_else(|| {
+            ToonError::DeserializationError("Number value requested twice".to_string())
+        })?;
+        seed.deserialize(text.into_deserializer())
+    }
+}
@@ -0,0 +1,519 @@
+//! Native serde deserializer that reads TOON directly.
+//!
+//! Visitors are driven straight from the parser's tokens, so structs, enums,
+//! maps and sequences are deserialized without building a `Value` tree first.
+
+mod access;
+mod rows;
+
+use std::borrow::Cow;
+
+pub use rows::Rows;
+use serde::de::{
+    self,
+    value::BorrowedStrDeserializer,
+    DeserializeSeed,
+    IntoDeserializer,
+    Visitor,
+};
+
+use crate::{
+    decode::parser::{
+        Node,
+        Parser,
+        Scalar,
+    },
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    types::DecodeOptions,
+};
+
+/// Implement the numeric `deserialize_*` methods with `deserialize_number`,
+/// so a primitive target gets a number even where `deserialize_any` would
+/// give serde_json's number map.
+macro_rules! forward_to_deserialize_number {
+    ($($method:ident)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+                self.deserialize_number(visitor)
+            }
+        )*
+    };
+}
+
+/// A serde `Deserializer` that reads TOON.
+///
+/// Accepts the same documents as [`decode`](crate::decode) with the same
+/// options. Errors raised while visiting (such as a missing struct field) are
+/// reported as [`ToonError::ParseError`] with the line and column at which
+/// they were detected.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     de::Deserializer,
+///     DecodeOptions,
+/// };
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct User {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct Data {
+///     users: Vec<User>,
+/// }
+///
+/// let input = "users[2]{id,name}:\n  1,Alice\n  2,Bob";
+/// let mut deserializer = Deserializer::new(input, DecodeOptions::default());
+/// let data = Data::deserialize(&mut deserializer)?;
+/// assert_eq!(data.users[1].name, "Bob");
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub struct Deserializer<'de> {
+    parser: Parser<'de>,
+}
+
+impl<'de> Deserializer<'de> {
+    /// Create a new deserializer for the given input and options.
+    pub fn new(input: &'de str, options: DecodeOptions) -> Self {
+        Self {
+            parser: Parser::new(input, options),
+        }
+    }
+
+    fn deserialize_number<V: Visitor<'de>>(&mut self, visitor: V) -> ToonResult<V::Value> {
+        self.root()?.deserialize_number(visitor)
+    }
+
+    fn root(&mut self) -> ToonResult<NodeDeserializer<'_, 'de>> {
+        let node = self.parser.parse_root()?;
+        Ok(NodeDeserializer {
+            parser: &mut self.parser,
+            node,
+        })
+    }
+}
+
+impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
+    type Error = ToonError;
+
+    forward_to_deserialize_number! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+        self.root()?.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+        self.root()?.deserialize_option(visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        visitor: V,
+    ) -> ToonResult<V::Value> {
+        self.root()?.deserialize_newtype_struct(name, visitor)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> ToonResult<V::Value> {
+        self.root()?.deserialize_enum(name, variants, visitor)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+/// Deserializes a single node of the document.
+pub(crate) struct NodeDeserializer<'p, 'de> {
+    parser: &'p mut Parser<'de>,
+    node: Node<'de>,
+}
+
+/// Deserialize a string that may borrow from the input.
+pub(crate) fn deserialize_str<'de, S: DeserializeSeed<'de>>(
+    seed: S,
+    s: Cow<'de, str>,
+) -> ToonResult<S::Value> {
+    match s {
+        Cow::Borrowed(s) => seed.deserialize(BorrowedStrDeserializer::new(s)),
+        Cow::Owned(s) => seed.deserialize(s.into_deserializer()),
+    }
+}
+
+fn visit_scalar<'de, V: Visitor<'de>>(scalar: Scalar<'de>, visitor: V) -> ToonResult<V::Value> {
+    match scalar {
+        Scalar::Null => visitor.visit_unit(),
+        Scalar::Bool(b) => visitor.visit_bool(b),
+        // Numbers that are not integers are passed as serde_json's number
+        // map, which `Value` and `Number` read back without an `f64`.
+        #[cfg(feature = "arbitrary_precision")]
+        Scalar::Number(n) if n.as_u64().is_none() && n.as_i64().is_none() => {
+            visitor.visit_map(access::NumberAccess(Some(n.to_string())))
+        }
+        Scalar::Number(n) => visit_number(n, visitor),
+        Scalar::String(Cow::Borrowed(s)) => visitor.visit_borrowed_str(s),
+        Scalar::String(Cow::Owned(s)) => visitor.visit_string(s),
+    }
+}
+
+/// Visit a number as an integer when it is one, and as an `f64` otherwise.
+fn visit_number<'de, V: Visitor<'de>>(n: serde_json::Number, visitor: V) -> ToonResult<V::Value> {
+    if let Some(u) = n.as_u64() {
+        visitor.visit_u64(u)
+    } else if let Some(i) = n.as_i64() {
+        visitor.visit_i64(i)
+    } else {
+        match n.as_f64() {
+            Some(f) if f.is_finite() => visitor.visit_f64(f),
+            _ => Err(ToonError::DeserializationError(format!(
+                "Number {} is out of range for f64",
+                n
+            ))),
+        }
+    }
+}
+
+/// Deserializes a single primitive value that has already been read.
+pub(crate) struct ScalarDeserializer<'de>(Scalar<'de>);
+
+impl<'de> IntoDeserializer<'de, ToonError> for Scalar<'de> {
+    type Deserializer = ScalarDeserializer<'de>;
+
+    fn into_deserializer(self) -> ScalarDeserializer<'de> {
+        ScalarDeserializer(self)
+    }
+}
+
+impl<'de> ScalarDeserializer<'de> {
+    fn deserialize_number<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+        match self.0 {
+            Scalar::Number(n) => visit_number(n, visitor),
+            scalar => visit_scalar(scalar, visitor),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for ScalarDeserializer<'de> {
+    type Error = ToonError;
+
+    forward_to_deserialize_number! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+        visit_scalar(self.0, visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+        match self.0 {
+            Scalar::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> ToonResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> ToonResult<V::Value> {
+        match self.0 {
+            Scalar::String(Cow::Borrowed(variant)) => {
+                visitor.visit_enum(BorrowedStrDeserializer::new(variant))
+            }
+            Scalar::String(Cow::Owned(variant)) => visitor.visit_enum(variant.into_deserializer()),
+            _ => Err(ToonError::DeserializationError(
+                "Expected a string for enum".to_string(),
+            )),
+        }
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+impl<'de> NodeDeserializer<'_, 'de> {
+    fn deserialize_number<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+        match self.node {
+            Node::Primitive(Scalar::Number(n)) => {
+                visit_number(n, visitor).map_err(|e| self.parser.fix_position(e))
+            }
+            _ => de::Deserializer::deserialize_any(self, visitor),
+        }
+    }
+}
+
+impl<'de> de::Deserializer<'de> for NodeDeserializer<'_, 'de> {
+    type Error = ToonError;
+
+    forward_to_deserialize_number! {
+        deserialize_i8 deserialize_i16 deserialize_i32 deserialize_i64 deserialize_i128
+        deserialize_u8 deserialize_u16 deserialize_u32 deserialize_u64 deserialize_u128
+        deserialize_f32 deserialize_f64
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+        let parser = self.parser;
+        let result = match self.node {
+            Node::Primitive(scalar) => visit_scalar(scalar, visitor),
+            Node::Object(cursor) => {
+                let mut access = access::ObjectAccess::new(&mut *parser, cursor);
+                visitor
+                    .visit_map(&mut access)
+                    .and_then(|value| access.finish().map(|_| value))
+            }
+            Node::Array(cursor) => {
+                let mut access = access::ArrayAccess::new(&mut *parser, cursor);
+                visitor
+                    .visit_seq(&mut access)
+                    .and_then(|value| access.finish().map(|_| value))
+            }
+            Node::Row(cursor) => {
+                let mut access = access::RowAccess::new(&mut *parser, cursor);
+                visitor
+                    .visit_map(&mut access)
+                    .and_then(|value| access.finish().map(|_| value))
+            }
+        };
+        result.map_err(|e| parser.fix_position(e))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> ToonResult<V::Value> {
+        match self.node {
+            Node::Primitive(Scalar::Null) => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> ToonResult<V::Value> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> ToonResult<V::Value> {
+        let parser = self.parser;
+        let result = match self.node {
+            Node::Primitive(Scalar::String(Cow::Borrowed(variant))) => {
+                visitor.visit_enum(BorrowedStrDeserializer::new(variant))
+            }
+            Node::Primitive(Scalar::String(Cow::Owned(variant))) => {
+                visitor.visit_enum(variant.into_deserializer())
+            }
+            Node::Object(cursor) => {
+                visitor.visit_enum(access::EnumAccess::new(&mut *parser, cursor))
+            }
+            _ => Err(ToonError::DeserializationError(
+                "Expected a string or an object with a single key for enum".to_string(),
+            )),
+        };
+        result.map_err(|e| parser.fix_position(e))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool char str string bytes byte_buf unit unit_struct seq tuple
+        tuple_struct map struct identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Deserialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::decode;
+
+    fn from_str<'de, T: Deserialize<'de>>(input: &'de str) -> ToonResult<T> {
+        T::deserialize(&mut Deserializer::new(input, DecodeOptions::default()))
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Role {
+        Admin,
+        User,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct User {
+        id: u32,
+        name: String,
+        role: Role,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    enum Event {
+        Click { x: i32, y: i32 },
+        Key(String),
+        Scroll(f64, f64),
+    }
+
+    #[test]
+    fn test_deserialize_struct() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Doc {
+            title: String,
+            version: Option<u8>,
+            tags: Vec<String>,
+            users: Vec<User>,
+            meta: BTreeMap<String, i64>,
+        }
+
+        let input = "title: Report\nversion: null\ntags[2]: a,b\nusers[2]{id,name,role}:\n  \
+                     1,Alice,admin\n  2,Bob,user\nmeta:\n  x: 1\n  y: 2";
+        let doc: Doc = from_str(input).unwrap();
+
+        assert_eq!(doc.title, "Report");
+        assert_eq!(doc.version, None);
+        assert_eq!(doc.tags, vec!["a", "b"]);
+        assert_eq!(
+            doc.users[1],
+            User {
+                id: 2,
+                name: "Bob".to_string(),
+                role: Role::User,
+            }
+        );
+        assert_eq!(doc.meta["y"], 2);
+    }
+
+    #[test]
+    fn test_deserialize_enums() {
+        assert_eq!(
+            from_str::<Event>("Click:\n  x: 1\n  y: 2").unwrap(),
+            Event::Click { x: 1, y: 2 }
+        );
+        assert_eq!(
+            from_str::<Event>("Key: k").unwrap(),
+            Event::Key("k".to_string())
+        );
+        assert_eq!(
+            from_str::<Event>("Scroll[2]: 0.5,1.5").unwrap(),
+            Event::Scroll(0.5, 1.5)
+        );
+        assert_eq!(from_str::<Role>("admin").unwrap(), Role::Admin);
+    }
+
+    #[test]
+    fn test_ignores_unknown_fields() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Small {
+            b: i32,
+        }
+
+        let small: Small = from_str("a[2]{x,y}:\n  1,2\n  3,4\nb: 5\nc:\n  d: 6").unwrap();
+        assert_eq!(small, Small { b: 5 });
+    }
+
+    #[test]
+    fn test_matches_decode() {
+        let inputs = [
+            "users[2]{id,name}:\n  1,Alice\n  2,Bob",
+            "pairs[2]:\n  - [2]: 1,2\n  - [2]: 3,4",
+            "tags[3|]: a|b|c",
+            "a:\n  b:\n    c: 1",
+            "[3]: x,y,z",
+            "hello world",
+        ];
+
+        for input in inputs {
+            let value: serde_json::Value = from_str(input).unwrap();
+            assert_eq!(value, decode(input, &DecodeOptions::default()).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_borrows_from_input() {
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Row<'a> {
+            name: &'a str,
+            #[serde(borrow)]
+            note: Cow<'a, str>,
+        }
+
+        #[derive(Deserialize, Debug, PartialEq)]
+        struct Doc<'a> {
+            #[serde(borrow)]
+            rows: Vec<Row<'a>>,
+            #[serde(borrow)]
+            tags: BTreeMap<&'a str, &'a str>,
+        }
+
+        let input = "rows[2]{name,note}:\n  Alice,plain\n  \"Bob\",\"line\\nbreak\"\ntags:\n  a: x";
+        let doc: Doc = from_str(input).unwrap();
+
+        assert_eq!(doc.rows[0].name, "Alice");
+        assert!(matches!(doc.rows[0].note, Cow::Borrowed("plain")));
+        assert_eq!(doc.rows[1].name, "Bob");
+        assert!(matches!(&doc.rows[1].note, Cow::Owned(s) if s == "line\nbreak"));
+        assert_eq!(doc.tags["a"], "x");
+
+        // Escaped strings cannot be borrowed.
+        assert!(from_str::<Row>("name: \"a\\tb\"\nnote: x").is_err());
+    }
+
+    #[test]
+    fn test_error_has_position() {
+        let err = from_str::<User>("id: 1\nname: Alice").unwrap_err();
+        match err {
+            ToonError::ParseError { line, message, .. } => {
+                assert_eq!(line, 2);
+                assert!(message.contains("missing field `role`"));
+            }
+            other => panic!("Expected parse error, got {:?}", other),
+        }
+
+        let err = from_str::<User>("id: 1\nname: Alice\nrole: root").unwrap_err();
+        assert!(matches!(err, ToonError::ParseError { line: 3, .. }));
+
+        assert_eq!(
+            from_str::<serde_json::Value>("items[2]: a").unwrap_err(),
+            decode("items[2]: a", &DecodeOptions::default()).unwrap_err()
+        );
+        assert_eq!(from_str::<Vec<i32>>("[2]: 1,2").unwrap(), vec![1, 2]);
+        assert_eq!(
+            from_str::<serde_json::Value>("a: 1").unwrap(),
+            json!({"a": 1})
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use std::{
+    io::BufRead,
+    marker::PhantomData,
+};
+
+use serde::{
+    de::value::{
+        MapAccessDeserializer,
+        MapDeserializer,
+    },
+    Deserialize,
+};
+
+use crate::{
+    decode::{
+        parser::{
+            Node,
+            Parser,
+        },
+        scanner::ReaderSource,
+        validation::validate_array_length,
+    },
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    types::DecodeOptions,
+};
+
+/// Iterator over the rows of a tabular array, deserializing one row at a
+/// time.
+///
+/// Created by [`rows`](crate::rows) or [`Rows::new`]. Only the current row
+/// is held in memory, so arbitrarily large tables can be processed from a
+/// reader. Each row is mapped to `T` by the header field list. In strict mode
+/// every row must have exactly as many values as there are fields, and the
+/// number of rows must match the declared `[N]` once the table ends.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     de::Rows,
+///     DecodeOptions,
+/// };
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// let input = "users[3]{id,name}:\n  1,Alice\n  2,Bob\n  3,Carol,extra\n";
+/// let options = DecodeOptions::new().with_strict(false);
+/// let rows: Rows<_, User> = Rows::new(input.as_bytes(), "users", &options)?;
+///
+/// let names: Vec<String> = rows
+///     .map(|user| user.map(|u| u.name))
+///     .collect::<Result<_, _>>()?;
+/// assert_eq!(names, ["Alice", "Bob", "Carol"]);
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub struct Rows<R, T> {
+    parser: Parser<'static, ReaderSource<R>>,
+    fields: Vec<String>,
+    len: usize,
+    count: usize,
+    strict: bool,
+    done: bool,
+    _row: PhantomData<fn() -> T>,
+}
+
+impl<R: BufRead, T: for<'de> Deserialize<'de>> Rows<R, T> {
+    /// Find the tabular array stored under the root-level `key` and prepare
+    /// to read its rows.
+    ///
+    /// Fields that come before `key` are parsed and discarded.
+    pub fn new(reader: R, key: &str, options: &DecodeOptions) -> ToonResult<Self> {
+        let mut parser = Parser::with_source(ReaderSource::new(reader), options.clone())?;
+        let not_found = || ToonError::InvalidInput(format!("Key '{}' not found", key));
+
+        let mut root = match parser.parse_root()? {
+            Node::Object(cursor) => cursor,
+            _ => return Err(not_found()),
+        };
+
+        let node = loop {
+            match parser.next_key(&mut root)? {
+                Some(name) if name == key => break parser.parse_field(&root)?,
+                Some(_) => {
+                    let node = parser.parse_field(&root)?;
+                    parser.build(node)?;
+                }
+                None => return Err(not_found()),
+            }
+        };
+
+        let cursor = match node {
+            Node::Array(cursor) => cursor,
+            Node::Object(_) => return Err(ToonError::type_mismatch("tabular array", "object")),
+            _ => return Err(ToonError::type_mismatch("tabular array", "primitive")),
+        };
+
+        let fields = match cursor.fields() {
+            Some(fields) => fields.iter().map(|f| f.to_string()).collect(),
+            None if cursor.len() == 0 => Vec::new(),
+            None => return Err(ToonError::type_mismatch("tabular array", "array")),
+        };
+
+        Ok(Self {
+            parser,
+            fields,
+            len: cursor.len(),
+            count: 0,
+            strict: options.strict,
+            done: false,
+            _row: PhantomData,
+        })
+    }
+
+    /// The field names from the array header.
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// The declared `[N]` row count.
+    pub fn declared_len(&self) -> usize {
+        self.len
+    }
+
+    fn step(&mut self) -> ToonResult<Option<T>> {
+        if !self.parser.at_indented_line()? {
+            self.check_len(self.count)?;
+            return Ok(None);
+        }
+
+        if self.count == self.len {
+            if !self.strict {
+                return Ok(None);
+            }
+
+            let context = self.parser.create_error_context();
+            let mut found = self.count;
+            while self.parser.at_indented_line()? {
+                self.parser.read_row(found, self.fields.len())?;
+                found += 1;
+            }
+            return Err(ToonError::length_mismatch_with_context(
+                self.len, found, context,
+            ));
+        }
+
+        let (line, column) = self.parser.line_start();
+        let cells = self.parser.read_row(self.count, self.fields.len())?;
+        self.count += 1;
+
+        let mut map = MapDeserializer::new(self.fields.iter().map(String::as_str).zip(cells));
+        T::deserialize(MapAccessDeserializer::new(&mut map))
+            .and_then(|row| map.end().map(|_| row))
+            .map(Some)
+            .map_err(|e| match e {
+                ToonError::DeserializationError(message) => {
+                    ToonError::parse_error(line, column, message)
+                }
+                other => other,
+            })
+    }
+
+    fn check_len(&self, found: usize) -> ToonResult<()> {
+        validate_array_length(self.len, found, self.strict)
+            .map_err(|e| e.with_context(self.parser.create_error_context()))
+    }
+}
+
+impl<R: BufRead, T: for<'de> Deserialize<'de>> Iterator for Rows<R, T> {
+    type Item = ToonResult<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.step() {
+            Ok(Some(row)) => Some(Ok(row)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.done {
+            (0, Some(0))
+        } else {
+            // The remaining rows, plus a possible length mismatch error.
+            let remaining = self.len.saturating_sub(self.count);
+            (0, Some(remaining.saturating_add(1)))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    #[serde(rename_all = "lowercase")]
+    enum Role {
+        Admin,
+        User,
+    }
+
+    #[derive(Deserialize, Debug, PartialEq)]
+    struct User {
+        id: u32,
+        name: String,
+        role: Role,
+        note: Option<String>,
+    }
+
+    fn rows(input: &str, options: &DecodeOptions) -> ToonResult<Vec<User>> {
+        Rows::new(input.as_bytes(), "users", options)?.collect()
+    }
+
+    #[test]
+    fn test_rows() {
+        let input = "title: Team\nmeta:\n  tags[2]: a,b\nusers[3]{id,name,role,note}:\n  \
+                     1,Alice,admin,null\n\n  2,\"Bob, Jr.\",user,hi\n  3,Carol,user,\"\"\nafter: \
+                     1\n";
+        let users = rows(input, &DecodeOptions::default()).unwrap();
+
+        assert_eq!(users.len(), 3);
+        assert_eq!(
+            users[1],
+            User {
+                id: 2,
+                name: "Bob, Jr.".to_string(),
+                role: Role::User,
+                note: Some("hi".to_string()),
+            }
+        );
+        assert_eq!(users[0].note, None);
+        assert_eq!(users[2].note, Some(String::new()));
+
+        let options = DecodeOptions::new().with_delimiter(crate::Delimiter::Pipe);
+        let input = "users[1|]{id|name|role|note}:\n  7|Dan|admin|x";
+        assert_eq!(rows(input, &options).unwrap()[0].id, 7);
+
+        let empty: Rows<_, User> =
+            Rows::new("users[0]:\nx: 1".as_bytes(), "users", &Default::default()).unwrap();
+        assert_eq!(empty.count(), 0);
+    }
+
+    #[test]
+    fn test_row_width_is_checked() {
+        let input = "users[2]{id,name,role,note}:\n  1,Alice,admin,x\n  2,Bob,user\n";
+        let err = rows(input, &DecodeOptions::default()).unwrap_err();
+        assert!(matches!(err, ToonError::InvalidStructure(ref m) if m.contains("Row 1")));
+
+        let input = "users[1]{id,name,role,note}:\n  1,Alice,admin,x,y\n";
+        assert!(rows(input, &DecodeOptions::default()).is_err());
+        let lenient = rows(input, &DecodeOptions::new().with_strict(false)).unwrap();
+        assert_eq!(lenient[0].note, Some("x".to_string()));
+    }
+
+    #[test]
+    fn test_row_count_is_checked() {
+        let short = "users[3]{id,name,role,note}:\n  1,A,user,x\n  2,B,user,y\nnext: 1";
+        assert!(matches!(
+            rows(short, &DecodeOptions::default()).unwrap_err(),
+            ToonError::LengthMismatch {
+                expected: 3,
+                found: 2,
+                ..
+            }
+        ));
+
+        let long = "users[1]{id,name,role,note}:\n  1,A,user,x\n  2,B,user,y\n  3,C,user,z";
+        assert!(matches!(
+            rows(long, &DecodeOptions::default()).unwrap_err(),
+            ToonError::LengthMismatch {
+                expected: 1,
+                found: 3,
+                ..
+            }
+        ));
+
+        let lenient = DecodeOptions::new().with_strict(false);
+        assert_eq!(rows(short, &lenient).unwrap().len(), 2);
+        assert_eq!(rows(long, &lenient).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_declared_length_bounds() {
+        let options = DecodeOptions::default();
+        let negative = Rows::<_, User>::new("users[-1]{id}:\n  1".as_bytes(), "users", &options);
+        assert!(matches!(negative.err(), Some(ToonError::ParseError { .. })));
+
+        let input = "users[#18446744073709551615]{id,name,role,note}:\n  1,A,user,x";
+        let iter = Rows::<_, User>::new(input.as_bytes(), "users", &options).unwrap();
+        assert_eq!(iter.declared_len(), usize::MAX);
+        assert_eq!(iter.size_hint(), (0, Some(usize::MAX)));
+    }
+
+    #[test]
+    fn test_lookup_errors() {
+        let options = DecodeOptions::default();
+        let missing = Rows::<_, User>::new("a: 1".as_bytes(), "users", &options);
+        assert!(matches!(missing.err(), Some(ToonError::InvalidInput(_))));
+
+        let scalar = Rows::<_, User>::new("users: 1".as_bytes(), "users", &options);
+        assert!(matches!(scalar.err(), Some(ToonError::TypeMismatch { .. })));
+
+        let list = Rows::<_, User>::new("users[2]: 1,2".as_bytes(), "users", &options);
+        assert!(matches!(list.err(), Some(ToonError::TypeMismatch { .. })));
+    }
+
+    #[test]
+    fn test_row_error_has_position() {
+        let input = "users[2]{id,name,role,note}:\n  1,Alice,admin,x\n  2,Bob,root,y\n";
+        let mut iter =
+            Rows::<_, User>::new(input.as_bytes(), "users", &Default::default()).unwrap();
+        assert!(iter.next().unwrap().is_ok());
+        match iter.next().unwrap().unwrap_err() {
+            ToonError::ParseError { line, message, .. } => {
+                assert_eq!(line, 3);
+                assert!(message.contains("unknown variant"));
+            }
+            other => panic!("Expected parse error, got {:?}", other),
+        }
+        assert!(iter.next().is_none());
+    }
+}
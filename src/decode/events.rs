@@ -0,0 +1,423 @@
+use std::{
+    collections::HashMap,
+    io::BufRead,
+};
+
+use serde_json::Value;
+
+use crate::{
+    decode::{
+        parser::{
+            ArrayCursor,
+            Node,
+            ObjectCursor,
+            Parser,
+        },
+        scanner::ReaderSource,
+    },
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    types::{
+        DecodeOptions,
+        Delimiter,
+        DuplicateKeys,
+    },
+};
+
+/// An event produced while reading a TOON document.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    /// Start of an object. Followed by `Key`/value pairs and an `End`.
+    StartObject,
+    /// An object key. The next event starts its value.
+    Key(String),
+    /// Start of an array. Followed by its elements (one `Row` per row for
+    /// tabular arrays) and an `End`.
+    StartArray {
+        /// The declared `[N]` length.
+        len: usize,
+        /// Field names, for tabular arrays.
+        fields: Option<Vec<String>>,
+        /// The delimiter that separates values in the array.
+        delimiter: Delimiter,
+    },
+    /// A row of a tabular array, with one value per field.
+    Row(Vec<Value>),
+    /// A primitive value.
+    Primitive(Value),
+    /// End of the innermost open object or array.
+    End,
+}
+
+enum Frame {
+    Object {
+        cursor: ObjectCursor<'static>,
+        in_value: bool,
+        /// The line of each key read so far, for the duplicate key check.
+        keys: HashMap<String, usize>,
+        count: usize,
+    },
+    Array {
+        cursor: ArrayCursor<'static>,
+        count: usize,
+    },
+}
+
+/// Streaming pull decoder that yields [`Event`]s from a [`BufRead`].
+///
+/// Input is read one line at a time and nothing is accumulated, so memory use
+/// is bounded by nesting depth (and the longest line) rather than by document
+/// size. Events are checked as they are read with the same rules as
+/// [`decode`](crate::decode); the first error ends the iteration.
+///
+/// Events report the document as written, so options that rewrite it are
+/// not supported: path expansion, flattened tables, repair, and duplicate
+/// key policies other than [`DuplicateKeys::Error`]. Without a policy,
+/// lenient mode reports every repeated key.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     decode::{
+///         Event,
+///         Events,
+///     },
+///     DecodeOptions,
+/// };
+/// use serde_json::json;
+///
+/// let input = "users[2]{id,name}:\n  1,Alice\n  2,Bob\n";
+/// let events = Events::new(input.as_bytes(), &DecodeOptions::default())?;
+///
+/// let names: Vec<_> = events
+///     .filter_map(|event| match event {
+///         Ok(Event::Row(row)) => Some(row[1].clone()),
+///         _ => None,
+///     })
+///     .collect();
+/// assert_eq!(names, vec![json!("Alice"), json!("Bob")]);
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub struct Events<R> {
+    parser: Parser<'static, ReaderSource<R>>,
+    stack: Vec<Frame>,
+    started: bool,
+    done: bool,
+}
+
+impl<R: BufRead> Events<R> {
+    /// Create an event reader over the given input.
+    ///
+    /// Fails with [`ToonError::InvalidInput`] if `options` asks for a
+    /// rewrite that events cannot report.
+    pub fn new(reader: R, options: &DecodeOptions) -> ToonResult<Self> {
+        let unsupported = [
+            (options.expand_paths, "path expansion"),
+            (options.flatten_tabular, "flattened tables"),
+            (options.repair, "repair mode"),
+            (
+                options
+                    .duplicate_keys
+                    .is_some_and(|policy| policy != DuplicateKeys::Error),
+                "duplicate key policies",
+            ),
+        ];
+        if let Some((_, name)) = unsupported.iter().find(|(enabled, _)| *enabled) {
+            return Err(ToonError::InvalidInput(format!(
+                "Events does not support {}",
+                name
+            )));
+        }
+
+        Ok(Self {
+            parser: Parser::with_source(ReaderSource::new(reader), options.clone())?,
+            stack: Vec::new(),
+            started: false,
+            done: false,
+        })
+    }
+
+    /// Current nesting depth (number of open objects and arrays).
+    pub fn depth(&self) -> usize {
+        self.stack.len()
+    }
+
+    fn step(&mut self) -> ToonResult<Option<Event>> {
+        if !self.started {
+            self.started = true;
+            let node = self.parser.parse_root()?;
+            return self.enter(node).map(Some);
+        }
+
+        let node = match self.stack.last_mut() {
+            None => return Ok(None),
+            Some(Frame::Object {
+                cursor,
+                in_value,
+                keys,
+                count,
+            }) => {
+                if *in_value {
+                    *in_value = false;
+                    self.parser.parse_field(cursor)?
+                } else {
+                    return match self.parser.next_key(cursor)? {
+                        Some(key) => {
+                            *in_value = true;
+                            *count += 1;
+                            self.parser.check_key_count(*count)?;
+                            self.parser.check_duplicate_key(&key, keys)?;
+                            Ok(Some(Event::Key(key.into_owned())))
+                        }
+                        None => {
+                            self.stack.pop();
+                            Ok(Some(Event::End))
+                        }
+                    };
+                }
+            }
+            Some(Frame::Array { cursor, count }) => match self.parser.next_element(cursor)? {
+                Some(node) => {
+                    *count += 1;
+                    node
+                }
+                None => {
+                    self.parser.finish_array(cursor, *count)?;
+                    self.stack.pop();
+                    return Ok(Some(Event::End));
+                }
+            },
+        };
+
+        self.enter(node).map(Some)
+    }
+
+    fn enter(&mut self, node: Node<'static>) -> ToonResult<Event> {
+        match node {
+            Node::Primitive(scalar) => Ok(Event::Primitive(scalar.into_value())),
+            Node::Object(cursor) => {
+                self.stack.push(Frame::Object {
+                    cursor,
+                    in_value: false,
+                    keys: HashMap::new(),
+                    count: 0,
+                });
+                Ok(Event::StartObject)
+            }
+            Node::Array(cursor) => {
+                let event = Event::StartArray {
+                    len: cursor.len(),
+                    fields: cursor
+                        .fields()
+                        .map(|fields| fields.iter().map(|f| f.to_string()).collect()),
+                    delimiter: cursor.delimiter(),
+                };
+                self.stack.push(Frame::Array { cursor, count: 0 });
+                Ok(event)
+            }
+            Node::Row(mut cursor) => {
+                let mut row = Vec::new();
+                while self.parser.next_cell(&mut cursor)?.is_some() {
+                    row.push(self.parser.parse_scalar()?.into_value());
+                }
+                Ok(Event::Row(row))
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for Events<R> {
+    type Item = ToonResult<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        match self.step() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::{
+        json,
+        Map,
+    };
+
+    use super::*;
+    use crate::{
+        decode,
+        error::ToonError,
+    };
+
+    fn events(input: &str) -> ToonResult<Vec<Event>> {
+        Events::new(input.as_bytes(), &DecodeOptions::default())?.collect()
+    }
+
+    /// Rebuild a value from events, to compare against `decode`.
+    fn rebuild(events: &mut dyn Iterator<Item = Event>) -> Value {
+        match events.next().unwrap() {
+            Event::Primitive(value) => value,
+            Event::StartObject => {
+                let mut obj = Map::new();
+                while let Some(Event::Key(key)) = events.next() {
+                    obj.insert(key, rebuild(events));
+                }
+                Value::Object(obj)
+            }
+            Event::StartArray { len, fields, .. } => {
+                let items = (0..len)
+                    .map(|_| match (&fields, events.next()) {
+                        (Some(fields), Some(Event::Row(row))) => {
+                            Value::Object(fields.iter().cloned().zip(row).collect())
+                        }
+                        (None, Some(event)) => {
+                            let mut rest = std::iter::once(event).chain(&mut *events);
+                            rebuild(&mut rest)
+                        }
+                        other => panic!("unexpected {:?}", other),
+                    })
+                    .collect();
+                assert_eq!(events.next(), Some(Event::End));
+                Value::Array(items)
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_event_sequence() {
+        let input = "name: Alice\ntags[2|]: a|b\nmeta:\n  x: 1";
+        assert_eq!(
+            events(input).unwrap(),
+            vec![
+                Event::StartObject,
+                Event::Key("name".to_string()),
+                Event::Primitive(json!("Alice")),
+                Event::Key("tags".to_string()),
+                Event::StartArray {
+                    len: 2,
+                    fields: None,
+                    delimiter: Delimiter::Pipe,
+                },
+                Event::Primitive(json!("a")),
+                Event::Primitive(json!("b")),
+                Event::End,
+                Event::Key("meta".to_string()),
+                Event::StartObject,
+                Event::Key("x".to_string()),
+                Event::Primitive(json!(1)),
+                Event::End,
+                Event::End,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tabular_rows() {
+        let input = "users[2]{id,name}:\n  1,Alice\n  2,\"Bob Smith\"";
+        let events = events(input).unwrap();
+        assert_eq!(
+            events[2],
+            Event::StartArray {
+                len: 2,
+                fields: Some(vec!["id".to_string(), "name".to_string()]),
+                delimiter: Delimiter::Comma,
+            }
+        );
+        assert_eq!(events[4], Event::Row(vec![json!(2), json!("Bob Smith")]));
+    }
+
+    #[test]
+    fn test_matches_decode() {
+        let inputs = [
+            "users[2]{id,name}:\n  1,Alice\n  2,Bob",
+            "pairs[2]:\n  - [2]: 1,2\n  - [2]: 3,4",
+            "a:\n  b:\n    c: 1\nd: \"x\\ty\"",
+            "[3]: x,y,z",
+            "hello world",
+            "",
+        ];
+
+        for input in inputs {
+            let mut iter = events(input).unwrap().into_iter();
+            assert_eq!(
+                rebuild(&mut iter),
+                decode(input, &DecodeOptions::default()).unwrap()
+            );
+            assert_eq!(iter.next(), None);
+        }
+    }
+
+    #[test]
+    fn test_object_checks_match_decode() {
+        let err = events("a: 1\nb:\n  c: 1\n  c: 2").unwrap_err();
+        assert!(matches!(
+            err,
+            ToonError::ParseError { line: 4, column: 3, ref message, .. }
+                if message.contains("Duplicate key 'c'")
+        ));
+
+        let lenient = DecodeOptions::new().with_strict(false);
+        let keys = Events::new("a: 1\na: 2".as_bytes(), &lenient)
+            .unwrap()
+            .filter(|event| matches!(event, Ok(Event::Key(_))))
+            .count();
+        assert_eq!(keys, 2);
+
+        let limited =
+            DecodeOptions::new().with_limits(crate::Limits::new().with_max_object_keys(1));
+        let result: ToonResult<Vec<_>> = Events::new("a: 1\nb: 2".as_bytes(), &limited)
+            .unwrap()
+            .collect();
+        assert!(matches!(result, Err(ToonError::LimitExceeded { .. })));
+    }
+
+    #[test]
+    fn test_unsupported_options() {
+        let options = [
+            DecodeOptions::new().with_expand_paths(true),
+            DecodeOptions::new().with_flatten_tabular(true),
+            DecodeOptions::new().repair(),
+            DecodeOptions::new().with_duplicate_keys(crate::DuplicateKeys::LastWins),
+        ];
+        for options in options {
+            let err = Events::new("a: 1".as_bytes(), &options).err();
+            assert!(matches!(err, Some(ToonError::InvalidInput(_))), "{:?}", err);
+        }
+        let error = DecodeOptions::new().with_duplicate_keys(crate::DuplicateKeys::Error);
+        assert!(Events::new("a: 1".as_bytes(), &error).is_ok());
+    }
+
+    #[test]
+    fn test_errors_end_iteration() {
+        let mut iter = Events::new("items[3]: a,b".as_bytes(), &DecodeOptions::default()).unwrap();
+        let results: Vec<_> = iter.by_ref().collect();
+        assert!(results.last().unwrap().is_err());
+        assert!(iter.next().is_none());
+
+        let err = events("a:\n  b: 1\n  [x").unwrap_err();
+        assert!(matches!(err, ToonError::ParseError { line: 3, .. }));
+
+        let err = Events::new(Cursor::new(vec![0xff, b'\n']), &DecodeOptions::default())
+            .err()
+            .unwrap();
+        assert!(matches!(err, ToonError::Io { .. }));
+    }
+}
@@ -0,0 +1,82 @@
+//! Expansion of dotted keys into nested objects.
+
+use serde_json::{
+    Map,
+    Value,
+};
+
+use crate::utils::is_valid_unquoted_key;
+
+/// Split a key into the path it stands for. Only unquoted keys whose
+/// segments are all valid unquoted keys are split; anything else is a
+/// single literal segment.
+pub(crate) fn expand_key(key: &str, quoted: bool) -> Vec<&str> {
+    if !quoted && key.contains('.') && key.split('.').all(is_valid_unquoted_key) {
+        key.split('.').collect()
+    } else {
+        vec![key]
+    }
+}
+
+/// Assign `value` at `path` inside `obj`, creating intermediate objects and
+/// merging objects assigned to the same path. Returns `false` if the path
+/// was already assigned a value that cannot be merged.
+pub(crate) fn insert_path(obj: &mut Map<String, Value>, path: &[&str], value: Value) -> bool {
+    let Some((first, rest)) = path.split_first() else {
+        return false;
+    };
+
+    if !rest.is_empty() {
+        let entry = obj
+            .entry(*first)
+            .or_insert_with(|| Value::Object(Map::new()));
+        return match entry {
+            Value::Object(inner) => insert_path(inner, rest, value),
+            _ => false,
+        };
+    }
+
+    match (obj.get_mut(*first), value) {
+        (None, value) => {
+            obj.insert(first.to_string(), value);
+            true
+        }
+        (Some(Value::Object(existing)), Value::Object(fields)) => fields
+            .into_iter()
+            .all(|(key, value)| insert_path(existing, &[key.as_str()], value)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_expand_key() {
+        assert_eq!(expand_key("a.b.c", false), vec!["a", "b", "c"]);
+        assert_eq!(expand_key("a.b", true), vec!["a.b"]);
+        assert_eq!(expand_key("a..b", false), vec!["a..b"]);
+        assert_eq!(expand_key("1.5", false), vec!["1.5"]);
+        assert_eq!(expand_key("plain", false), vec!["plain"]);
+    }
+
+    #[test]
+    fn test_insert_path() {
+        let mut obj = Map::new();
+        assert!(insert_path(&mut obj, &["a", "b"], json!(1)));
+        assert!(insert_path(&mut obj, &["a"], json!({"c": 2})));
+        assert!(insert_path(&mut obj, &["a", "d", "e"], json!(3)));
+        assert_eq!(
+            Value::Object(obj.clone()),
+            json!({"a": {"b": 1, "c": 2, "d": {"e": 3}}})
+        );
+
+        assert!(!insert_path(&mut obj, &["a", "b"], json!(4)));
+        assert!(!insert_path(&mut obj, &["a", "b", "x"], json!(4)));
+        assert!(!insert_path(&mut obj, &["a"], json!(5)));
+        assert!(!insert_path(&mut obj, &["a"], json!({"c": 6})));
+    }
+}
@@ -0,0 +1,366 @@
+//! Decoding TOON that is still arriving, such as streamed model output.
+
+use serde_json::{
+    Map,
+    Value,
+};
+
+use crate::{
+    decode::{
+        decode,
+        parser::Parser,
+    },
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    types::{
+        DecodeOptions,
+        Indent,
+    },
+};
+
+/// A value that was cut off by the end of the input received so far.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incomplete {
+    /// JSON Pointer to the value, e.g. `/users` or `/users/1/name`.
+    pub path: String,
+    pub kind: IncompleteKind,
+}
+
+/// How a value was cut off.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IncompleteKind {
+    /// An array header declared `declared` elements but only `received` have
+    /// arrived.
+    Array { declared: usize, received: usize },
+    /// A quoted string has not been closed yet.
+    String,
+}
+
+/// Decoder for TOON that arrives in pieces.
+///
+/// Feed text as it arrives with [`feed`](Self::feed) and call
+/// [`snapshot`](Self::snapshot) at any point for the value received so far.
+/// Arrays that are still short of their declared `[N]` length and strings
+/// that are still open are kept as they are instead of failing; use
+/// [`snapshot_with_marks`](Self::snapshot_with_marks) to find out which
+/// values are incomplete. A line that has not been finished is dropped if it
+/// cannot be read yet, and otherwise its values may still change as more
+/// text arrives.
+///
+/// The top-level fields of a root object are decoded once, when the next
+/// top-level line starts, so a snapshot only re-reads the field still
+/// arriving and copies the ones before it. A single large value, such as a
+/// root array or one long table, is still re-read in full by every snapshot,
+/// which makes a snapshot per row quadratic in the number of rows. Path
+/// expansion, repair, [`Indent::Auto`] and the document size and object key
+/// limits need the whole input, so with those options every snapshot
+/// re-reads everything.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     DecodeOptions,
+///     IncompleteKind,
+///     IncrementalDecoder,
+/// };
+/// use serde_json::json;
+///
+/// let mut decoder = IncrementalDecoder::new(&DecodeOptions::default());
+/// decoder.feed("users[3]{id,name}:\n  1,Alice\n  2,");
+/// decoder.feed("\"Bo");
+///
+/// let (value, marks) = decoder.snapshot_with_marks();
+/// assert_eq!(
+///     value,
+///     json!({"users": [{"id": 1, "name": "Alice"}, {"id": 2, "name": "Bo"}]})
+/// );
+/// assert_eq!(
+///     marks[0].kind,
+///     IncompleteKind::Array {
+///         declared: 3,
+///         received: 2
+///     }
+/// );
+/// assert_eq!(marks[1].path, "/users/1/name");
+/// assert_eq!(marks[1].kind, IncompleteKind::String);
+///
+/// decoder.feed("b\"\n  3,Carol\n");
+/// assert_eq!(decoder.snapshot()["users"][2]["name"], json!("Carol"));
+/// assert_eq!(decoder.finish()?, decoder.snapshot());
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct IncrementalDecoder {
+    buffer: String,
+    options: DecodeOptions,
+    /// The leading top-level fields of a root object that are complete.
+    done: Map<String, Value>,
+    /// Length of the input `done` was decoded from.
+    done_len: usize,
+    /// How far the input has been searched for top-level lines.
+    scanned: usize,
+    /// Set once the input cannot be read a top-level field at a time, e.g.
+    /// for a root array; snapshots then read all of it.
+    whole: bool,
+}
+
+impl IncrementalDecoder {
+    /// Create a decoder with the given options.
+    pub fn new(options: &DecodeOptions) -> Self {
+        let limits = &options.limits;
+        Self {
+            buffer: String::new(),
+            options: options.clone(),
+            done: Map::new(),
+            done_len: 0,
+            scanned: 0,
+            whole: options.expand_paths
+                || options.repair
+                || options.indent == Indent::Auto
+                || limits.max_document_bytes != usize::MAX
+                || limits.max_object_keys != usize::MAX,
+        }
+    }
+
+    /// Append the next piece of input.
+    pub fn feed(&mut self, chunk: &str) {
+        self.buffer.push_str(chunk);
+        if !self.whole {
+            self.decode_complete_fields();
+        }
+    }
+
+    /// Decode the top-level fields that end before the last top-level line
+    /// started so far.
+    fn decode_complete_fields(&mut self) {
+        let bytes = self.buffer.as_bytes();
+        let start = (self.scanned.max(self.done_len + 1)..bytes.len())
+            .rev()
+            .find(|&i| bytes[i - 1] == b'\n' && !bytes[i].is_ascii_whitespace());
+        self.scanned = bytes.len();
+        let Some(start) = start else {
+            return;
+        };
+
+        match decode(&self.buffer[self.done_len..start], &self.options) {
+            Ok(Value::Object(fields)) if !overlaps(&self.done, &fields) => {
+                self.done.extend(fields);
+                self.done_len = start;
+            }
+            _ => self.whole = true,
+        }
+    }
+
+    /// The input received so far.
+    pub fn buffer(&self) -> &str {
+        &self.buffer
+    }
+
+    /// The value described by the input received so far.
+    pub fn snapshot(&self) -> Value {
+        self.snapshot_with_marks().0
+    }
+
+    /// The value described by the input received so far, and the values in
+    /// it that are incomplete, outermost first.
+    ///
+    /// If the input cannot be read even allowing for it being cut off, it is
+    /// cut back to before the line with the error, and further lines are
+    /// dropped from the end until it can be read; an input with no readable
+    /// lines gives `null`.
+    pub fn snapshot_with_marks(&self) -> (Value, Vec<Incomplete>) {
+        if self.whole || self.done_len == 0 {
+            return partial(&self.buffer, &self.options);
+        }
+
+        let (rest, marks) = partial(&self.buffer[self.done_len..], &self.options);
+        let rest = match rest {
+            Value::Null => Map::new(),
+            Value::Object(fields) if !overlaps(&self.done, &fields) => fields,
+            _ => return partial(&self.buffer, &self.options),
+        };
+        let mut fields = self.done.clone();
+        fields.extend(rest);
+        (Value::Object(fields), marks)
+    }
+
+    /// Decode the complete input with the usual checks, once all of it has
+    /// arrived.
+    pub fn finish(&self) -> ToonResult<Value> {
+        decode(&self.buffer, &self.options)
+    }
+}
+
+/// Decode `text` allowing for it being cut off, cutting it back further
+/// until it can be read.
+fn partial(mut text: &str, options: &DecodeOptions) -> (Value, Vec<Incomplete>) {
+    loop {
+        let mut parser = Parser::partial(text, options.clone());
+        match parser.parse() {
+            Ok(value) => {
+                let mut marks = parser.take_incomplete();
+                marks.reverse();
+                return (value, marks);
+            }
+            Err(err) if !text.is_empty() => text = &text[..cut_point(text, &err)],
+            Err(_) => return (Value::Null, Vec::new()),
+        }
+    }
+}
+
+/// Whether `fields` repeats a key of `done`, which needs the whole input to
+/// handle.
+fn overlaps(done: &Map<String, Value>, fields: &Map<String, Value>) -> bool {
+    fields.keys().any(|key| done.contains_key(key))
+}
+
+/// Where to cut `text` after `err`: before the line the error was found on,
+/// or, for errors without a line, before the last line.
+fn cut_point(text: &str, err: &ToonError) -> usize {
+    let line = match err {
+        ToonError::ParseError { line, .. } | ToonError::LimitExceeded { line, .. } => *line,
+        _ => 0,
+    };
+    let error_line_start = match line {
+        0 => None,
+        1 => Some(0),
+        line => text.match_indices('\n').nth(line - 2).map(|(i, _)| i),
+    };
+
+    match error_line_start {
+        Some(end) if end < text.trim_end_matches(['\n', '\r']).len() => end,
+        _ => text.trim_end_matches(['\n', '\r']).rfind('\n').unwrap_or(0),
+    }
+}
+
+/// Format path segments as a JSON Pointer (RFC 6901).
+pub(crate) fn pointer(path: &[String]) -> String {
+    path.iter().fold(String::new(), |mut pointer, segment| {
+        pointer.push('/');
+        pointer.push_str(&segment.replace('~', "~0").replace('/', "~1"));
+        pointer
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn snapshot(input: &str) -> (Value, Vec<Incomplete>) {
+        let mut decoder = IncrementalDecoder::new(&DecodeOptions::default());
+        decoder.feed(input);
+        decoder.snapshot_with_marks()
+    }
+
+    fn array(path: &str, declared: usize, received: usize) -> Incomplete {
+        Incomplete {
+            path: path.to_string(),
+            kind: IncompleteKind::Array { declared, received },
+        }
+    }
+
+    #[test]
+    fn test_every_prefix_decodes() {
+        let input = "name: \"Ada L\"\ntags[3]: a,b,c\nusers[2]{id,name}:\n  1,Alice\n  \
+                     2,Bob\nmeta:\n  nested[2]:\n    - x\n    - y\n";
+        let full = crate::decode_default(input).unwrap();
+
+        let mut decoder = IncrementalDecoder::new(&DecodeOptions::default());
+        for ch in input.chars() {
+            let mut buf = [0; 4];
+            decoder.feed(ch.encode_utf8(&mut buf));
+            decoder.snapshot();
+        }
+        assert_eq!(decoder.snapshot(), full);
+        assert_eq!(decoder.finish().unwrap(), full);
+    }
+
+    #[test]
+    fn test_incomplete_arrays() {
+        let (value, marks) = snapshot("tags[3]: a,b,");
+        assert_eq!(value, json!({"tags": ["a", "b"]}));
+        assert_eq!(marks, vec![array("/tags", 3, 2)]);
+
+        let (value, marks) = snapshot("a:\n  rows[3]{x}:\n    1\n");
+        assert_eq!(value, json!({"a": {"rows": [{"x": 1}]}}));
+        assert_eq!(marks, vec![array("/a/rows", 3, 1)]);
+
+        let (value, marks) = snapshot("items[2]:\n  - [2]: 1");
+        assert_eq!(value, json!({"items": [[1]]}));
+        assert_eq!(marks, vec![array("/items", 2, 1), array("/items/0", 2, 1)]);
+    }
+
+    #[test]
+    fn test_unterminated_strings() {
+        let (value, marks) = snapshot("a: 1\nb: \"hello wor");
+        assert_eq!(value, json!({"a": 1, "b": "hello wor"}));
+        assert_eq!(
+            marks,
+            vec![Incomplete {
+                path: "/b".to_string(),
+                kind: IncompleteKind::String
+            }]
+        );
+
+        let (value, marks) = snapshot("\"a/b~c\": \"x\\");
+        assert_eq!(value, json!({"a/b~c": "x"}));
+        assert_eq!(marks[0].path, "/a~1b~0c");
+    }
+
+    #[test]
+    fn test_unfinished_lines_are_dropped() {
+        assert_eq!(snapshot("a: 1\nusers[2]{id,na").0, json!({"a": 1}));
+        assert_eq!(snapshot("a: 1\nb").0, json!({"a": 1}));
+        assert_eq!(snapshot("").0, json!(null));
+    }
+
+    #[test]
+    fn test_complete_fields_are_decoded_once() {
+        let input = "name: Ada\ntags[2]: a,b\nusers[2]{id,name}:\n  1,Alice\n  2,Bob\nmeta:\n  x: \
+                     \"y\"\nname: again\nlast: 1";
+        let options = DecodeOptions::default();
+
+        let mut decoder = IncrementalDecoder::new(&options);
+        for (i, ch) in input.char_indices() {
+            let mut buf = [0; 4];
+            decoder.feed(ch.encode_utf8(&mut buf));
+            let fed = &input[..i + ch.len_utf8()];
+            assert_eq!(
+                decoder.snapshot_with_marks(),
+                partial(fed, &options),
+                "{fed:?}"
+            );
+        }
+        // The repeated `name` stops the field-by-field reading.
+        assert!(decoder.whole);
+
+        let mut decoder = IncrementalDecoder::new(&options);
+        decoder.feed("a: 1\nb:\n  c: 2\nd[3]: x");
+        assert_eq!(decoder.done_len, "a: 1\nb:\n  c: 2\n".len());
+        decoder.feed(",y");
+        let (value, marks) = decoder.snapshot_with_marks();
+        assert_eq!(value, json!({"a": 1, "b": {"c": 2}, "d": ["x", "y"]}));
+        assert_eq!(marks, vec![array("/d", 3, 2)]);
+
+        let mut decoder = IncrementalDecoder::new(&options);
+        decoder.feed("[2]:\n  - a\n");
+        decoder.feed("  - b\nc");
+        assert_eq!(decoder.done_len, 0);
+    }
+
+    #[test]
+    fn test_cuts_back_to_error_line() {
+        let input = "a: 1\na: 2\nb: 3\nc: 4\n";
+        assert_eq!(snapshot(input).0, json!({"a": 1}));
+
+        let err = ToonError::parse_error(2, 1, "Duplicate key");
+        assert_eq!(cut_point(input, &err), 4);
+        let err = ToonError::InvalidStructure("Row 1".to_string());
+        assert_eq!(cut_point(input, &err), 14);
+    }
+}
@@ -1,8 +1,31 @@
+pub mod events;
+mod expand;
+pub mod incremental;
 pub mod parser;
+pub mod repair;
 pub mod scanner;
+pub mod spans;
 pub mod validation;
 
+pub use events::{
+    Event,
+    Events,
+};
+pub use incremental::{
+    Incomplete,
+    IncompleteKind,
+    IncrementalDecoder,
+};
+pub use repair::{
+    decode_with_repairs,
+    Repair,
+    RepairKind,
+};
 use serde_json::Value;
+pub use spans::{
+    decode_with_spans,
+    Location,
+};
 
 use crate::{
     error::ToonResult,
@@ -28,6 +51,10 @@ use crate::{
 /// # Ok::<(), rtoon::ToonError>(())
 /// ```
 pub fn decode(input: &str, options: &DecodeOptions) -> ToonResult<Value> {
+    if options.repair {
+        return decode_with_repairs(input, options).map(|(value, _)| value);
+    }
+
     let mut parser = parser::Parser::new(input, options.clone());
     parser.parse()
 }
@@ -1,3 +1,13 @@
+use std::{
+    borrow::Cow,
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    fmt,
+    rc::Rc,
+};
+
 use serde_json::{
     Map,
     Number,
@@ -6,60 +16,302 @@ use serde_json::{
 
 use crate::{
     constants::{
+        ABSENT_MARKER,
         KEYWORDS,
-        MAX_DEPTH,
     },
     decode::{
+        expand::{
+            expand_key,
+            insert_path,
+        },
+        incremental::{
+            pointer,
+            Incomplete,
+            IncompleteKind,
+        },
+        repair::{
+            Repair,
+            RepairKind,
+        },
         scanner::{
             Scanner,
+            Source,
             Token,
         },
+        spans::Location,
         validation,
     },
+    diagnostic::Span,
     error::{
         ErrorContext,
+        Limit,
         ToonError,
         ToonResult,
     },
     types::{
         DecodeOptions,
         Delimiter,
+        DuplicateKeys,
     },
-    utils::validation::validate_depth,
 };
 
+/// A value whose opening tokens have been read and which is ready to be
+/// consumed, either into a `Value` or by a serde visitor.
+pub(crate) enum Node<'a> {
+    Primitive(Scalar<'a>),
+    Object(ObjectCursor<'a>),
+    Array(ArrayCursor<'a>),
+    Row(RowCursor<'a>),
+}
+
+/// A primitive value. Strings borrow from the input unless they had to be
+/// unescaped or joined.
+pub(crate) enum Scalar<'a> {
+    Null,
+    Bool(bool),
+    Number(Number),
+    String(Cow<'a, str>),
+}
+
+impl Scalar<'_> {
+    pub(crate) fn into_value(self) -> Value {
+        match self {
+            Scalar::Null => Value::Null,
+            Scalar::Bool(b) => Value::Bool(b),
+            Scalar::Number(n) => Value::Number(n),
+            Scalar::String(s) => Value::String(s.into_owned()),
+        }
+    }
+}
+
+type FieldList<'a> = (Vec<Cow<'a, str>>, Vec<bool>, Vec<Span>);
+
+/// Iteration state for an object's fields.
+pub(crate) struct ObjectCursor<'a> {
+    depth: usize,
+    /// Root objects end at the first line that is not a key; nested objects
+    /// end when indentation returns to that of the line holding their key.
+    root: bool,
+    /// Indentation of the line holding the object's key.
+    indent: usize,
+    pending_key: Option<Cow<'a, str>>,
+    /// Whether the key last returned by `next_key` was quoted.
+    key_quoted: bool,
+}
+
+/// Iteration state for an array's elements.
+pub(crate) struct ArrayCursor<'a> {
+    length: usize,
+    form: ArrayForm<'a>,
+    delimiter: Delimiter,
+    /// Delimiter of the enclosing scope, restored once the array ends.
+    outer: Option<Delimiter>,
+    depth: usize,
+    /// Indentation of the line holding the header.
+    indent: usize,
+    /// Line holding the header.
+    line: usize,
+    /// The header, from `[` to `:`.
+    header: Span,
+    index: usize,
+}
+
+#[derive(Clone)]
+enum ArrayForm<'a> {
+    Inline,
+    List,
+    /// Field names, whether each was quoted, and where each was written.
+    Tabular(Rc<[Cow<'a, str>]>, Rc<[bool]>, Rc<[Span]>),
+}
+
+/// Iteration state for the cells of a single tabular row.
+pub(crate) struct RowCursor<'a> {
+    fields: Rc<[Cow<'a, str>]>,
+    quoted: Rc<[bool]>,
+    field_spans: Rc<[Span]>,
+    row: usize,
+    line: usize,
+    cell: usize,
+    /// Set in repair mode once the row has run out of cells.
+    missing: bool,
+}
+
+impl<'a> ArrayCursor<'a> {
+    /// The declared `[N]` length.
+    pub(crate) fn len(&self) -> usize {
+        self.length
+    }
+
+    /// The field names of a tabular array.
+    pub(crate) fn fields(&self) -> Option<&[Cow<'a, str>]> {
+        match &self.form {
+            ArrayForm::Tabular(fields, ..) => Some(fields),
+            _ => None,
+        }
+    }
+
+    /// The delimiter that separates values in this array.
+    pub(crate) fn delimiter(&self) -> Delimiter {
+        self.delimiter
+    }
+}
+
+impl<'a> RowCursor<'a> {
+    /// Name of the field at the given cell index.
+    pub(crate) fn field(&self, index: usize) -> &Cow<'a, str> {
+        &self.fields[index]
+    }
+}
+
 /// Parser that builds JSON values from a sequence of tokens.
-pub struct Parser<'a> {
-    scanner: Scanner,
-    current_token: Token,
+pub struct Parser<'a, S = &'a str> {
+    scanner: Scanner<'a, S>,
+    current_token: Token<'a>,
     options: DecodeOptions,
+    /// Delimiter of the innermost open array, if any.
     delimiter: Option<Delimiter>,
-    input: &'a str,
+    /// Determine array lengths from the content instead of the `[N]` header.
+    count_lengths: bool,
+    /// Fixes made in repair mode.
+    repairs: Vec<Repair>,
+    /// Treat the end of input as a cut-off point rather than the end of the
+    /// document.
+    partial: bool,
+    /// Keys and indexes leading to the value being built, in partial mode.
+    path: Vec<String>,
+    /// Values cut off by the end of input, in partial mode.
+    incomplete: Vec<Incomplete>,
+    /// Where each key and value was written, by JSON Pointer, in spans mode.
+    spans: Option<HashMap<String, Location>>,
+    /// The token before `current_token`.
+    last_span: Span,
+    /// The first token of the primitive value being read.
+    value_start: Span,
+    /// The range covered by the value last built.
+    extent: Span,
+    /// An error scanning the first token, reported once parsing starts.
+    start_error: Option<ToonError>,
 }
 
 impl<'a> Parser<'a> {
     /// Create a new parser with the given input and options.
     pub fn new(input: &'a str, options: DecodeOptions) -> Self {
-        let mut scanner = Scanner::new(input);
+        let mut parser = Self::init(input, options);
+        parser.scan_first_token();
+        parser
+    }
+
+    /// Create a parser for a document that may be cut off at any point.
+    /// Arrays with fewer elements than declared and unterminated strings at
+    /// the end of input are accepted and recorded as incomplete.
+    pub(crate) fn partial(input: &'a str, options: DecodeOptions) -> Self {
+        let mut parser = Self::init(input, options);
+        parser.partial = true;
+        parser.scanner.set_partial();
+        parser.scan_first_token();
+        parser
+    }
+
+    fn scan_first_token(&mut self) {
+        match self.scanner.scan_token() {
+            Ok(token) => self.current_token = token,
+            Err(err) => self.start_error = Some(err),
+        }
+    }
+
+    /// Create a parser that records where each key and value was written.
+    pub(crate) fn with_spans(input: &'a str, options: DecodeOptions) -> Self {
+        let mut parser = Self::new(input, options);
+        parser.spans = Some(HashMap::new());
+        parser
+    }
+}
+
+impl<'a, S: Source<'a>> Parser<'a, S> {
+    /// Create a parser that reads from the given source.
+    pub fn with_source(source: S, options: DecodeOptions) -> ToonResult<Self> {
+        let mut parser = Self::init(source, options);
+        parser.current_token = parser.scanner.scan_token()?;
+        Ok(parser)
+    }
+
+    fn init(source: S, options: DecodeOptions) -> Self {
+        let mut scanner = Scanner::with_source(source);
         let chosen_delim = options.delimiter;
         scanner.set_active_delimiter(chosen_delim);
-        let current_token = scanner.scan_token().unwrap_or(Token::Eof);
+        scanner.set_indent(options.indent, options.strict);
+        scanner.set_limits(options.limits);
 
         Self {
             scanner,
-            current_token,
+            current_token: Token::Eof,
             delimiter: chosen_delim,
             options,
-            input,
+            count_lengths: false,
+            repairs: Vec::new(),
+            partial: false,
+            path: Vec::new(),
+            incomplete: Vec::new(),
+            spans: None,
+            last_span: Span::default(),
+            value_start: Span::default(),
+            extent: Span::default(),
+            start_error: None,
         }
     }
 
+    /// Ignore declared `[N]` lengths and read each array until its content
+    /// ends.
+    pub(crate) fn count_lengths(mut self) -> Self {
+        self.count_lengths = true;
+        self
+    }
+
+    /// The fixes made so far in repair mode.
+    pub(crate) fn take_repairs(&mut self) -> Vec<Repair> {
+        std::mem::take(&mut self.repairs)
+    }
+
+    /// The values found to be incomplete so far in partial mode.
+    pub(crate) fn take_incomplete(&mut self) -> Vec<Incomplete> {
+        std::mem::take(&mut self.incomplete)
+    }
+
+    /// The locations recorded in spans mode.
+    pub(crate) fn take_spans(&mut self) -> HashMap<String, Location> {
+        self.spans.take().unwrap_or_default()
+    }
+
+    /// Check that the whole input has been consumed.
+    pub(crate) fn finish(&mut self) -> ToonResult<()> {
+        self.skip_newlines()?;
+        if !matches!(self.current_token, Token::Eof) {
+            return Err(self
+                .parse_error_with_context(format!("Unexpected {:?}", self.current_token))
+                .with_suggestion("Check the indentation of this line"));
+        }
+        Ok(())
+    }
+
     /// Parse the input into a JSON value.
     pub fn parse(&mut self) -> ToonResult<Value> {
-        self.parse_value()
+        let node = self.parse_root()?;
+        let value = self.build(node)?;
+        let extent = self.extent;
+        if let Some(spans) = &mut self.spans {
+            spans.insert(
+                String::new(),
+                Location {
+                    key: None,
+                    value: extent,
+                },
+            );
+        }
+        Ok(value)
     }
 
     fn advance(&mut self) -> ToonResult<()> {
+        self.last_span = self.scanner.token_span();
         self.current_token = self.scanner.scan_token()?;
         Ok(())
     }
@@ -71,171 +323,315 @@ impl<'a> Parser<'a> {
         Ok(())
     }
 
-    fn parse_value(&mut self) -> ToonResult<Value> {
-        self.parse_value_with_depth(0)
+    /// Consume a node completely, building the JSON value it describes.
+    pub(crate) fn build(&mut self, node: Node<'a>) -> ToonResult<Value> {
+        match node {
+            Node::Primitive(scalar) => {
+                self.check_unterminated();
+                self.extent = self.value_start.to(self.last_span);
+                Ok(scalar.into_value())
+            }
+            Node::Object(mut cursor) => {
+                let mut obj = Map::new();
+                let mut extent = self.last_span.end();
+                let mut start = None;
+                let policy = self.options.duplicate_key_policy();
+                let mut key_lines = HashMap::new();
+                let mut collected = HashSet::new();
+                let mut count = 0;
+                while let Some(key) = self.next_key(&mut cursor)? {
+                    let key_span = self.last_span;
+                    let (line, column) = self.line_start();
+                    count += 1;
+                    self.check_key_count(count)?;
+                    self.check_duplicate_key(&key, &mut key_lines)?;
+                    let node = self.parse_field(&cursor)?;
+                    let expand = self.options.expand_paths;
+                    let value =
+                        self.build_field(node, &key, cursor.key_quoted, key_span, expand)?;
+                    extent = start.get_or_insert(key_span).to(self.extent);
+
+                    if !self.options.expand_paths {
+                        insert_field(&mut obj, key.into_owned(), value, policy, &mut collected);
+                    } else if !insert_path(&mut obj, &expand_key(&key, cursor.key_quoted), value) {
+                        return Err(ToonError::parse_error(
+                            line,
+                            column,
+                            format!("Path '{}' conflicts with an earlier key", key),
+                        ));
+                    }
+                }
+                self.extent = extent;
+                Ok(Value::Object(obj))
+            }
+            Node::Array(mut cursor) => {
+                let mut items = Vec::new();
+                let mut extent = cursor.header;
+                while let Some(node) = self.next_element(&mut cursor)? {
+                    items.push(self.build_at(node, items.len(), None)?);
+                    extent = cursor.header.to(self.extent);
+                }
+                self.finish_array(&cursor, items.len())?;
+                self.extent = extent;
+                Ok(Value::Array(items))
+            }
+            Node::Row(mut cursor) => {
+                let mut row = Map::new();
+                let mut extent = self.last_span.end();
+                let mut start = None;
+                while let Some(index) = self.next_cell(&mut cursor)? {
+                    let value = if cursor.missing {
+                        Value::Null
+                    } else if self.options.flatten_tabular
+                        && matches!(&self.current_token, Token::String(s, false) if s == ABSENT_MARKER)
+                    {
+                        self.advance()?;
+                        continue;
+                    } else {
+                        let node = Node::Primitive(self.parse_scalar()?);
+                        let (key, quoted) = (cursor.field(index), cursor.quoted[index]);
+                        let expand = self.options.flatten_tabular;
+                        let value =
+                            self.build_field(node, key, quoted, cursor.field_spans[index], expand)?;
+                        extent = start.get_or_insert(self.value_start).to(self.extent);
+                        value
+                    };
+
+                    if !self.options.flatten_tabular {
+                        row.insert(cursor.field(index).to_string(), value);
+                        continue;
+                    }
+                    let path = expand_key(cursor.field(index), cursor.quoted[index]);
+                    if !insert_path(&mut row, &path, value) {
+                        return Err(self.parse_error_with_context(format!(
+                            "Column '{}' conflicts with another column",
+                            cursor.field(index)
+                        )));
+                    }
+                }
+                self.extent = extent;
+                Ok(Value::Object(row))
+            }
+        }
     }
 
-    fn parse_value_with_depth(&mut self, depth: usize) -> ToonResult<Value> {
-        validate_depth(depth, MAX_DEPTH)?;
+    /// Whether the path to the value being built is tracked.
+    fn tracking(&self) -> bool {
+        self.partial || self.spans.is_some()
+    }
+
+    /// Build a child value, tracking its path in partial and spans mode. In
+    /// spans mode, `key` is where the key of an object field was written.
+    fn build_at(
+        &mut self,
+        node: Node<'a>,
+        segment: impl fmt::Display,
+        key: Option<Span>,
+    ) -> ToonResult<Value> {
+        if !self.tracking() {
+            return self.build(node);
+        }
+        self.path.push(segment.to_string());
+        let value = self.build(node);
+        if let (Some(spans), Ok(_)) = (&mut self.spans, &value) {
+            spans.insert(
+                pointer(&self.path),
+                Location {
+                    key,
+                    value: self.extent,
+                },
+            );
+        }
+        self.path.pop();
+        value
+    }
+
+    /// Build the value of an object field or tabular cell. When the key is
+    /// `expand`ed into a dotted path, the value is tracked at the end of it.
+    fn build_field(
+        &mut self,
+        node: Node<'a>,
+        key: &str,
+        quoted: bool,
+        key_span: Span,
+        expand: bool,
+    ) -> ToonResult<Value> {
+        if !expand || !self.tracking() {
+            return self.build_at(node, key, Some(key_span));
+        }
+        let depth = self.path.len();
+        let mut segments = expand_key(key, quoted);
+        let last = segments.pop().unwrap_or(key);
+        self.path.extend(segments.into_iter().map(str::to_string));
+        let value = self.build_at(node, last, Some(key_span));
+        self.path.truncate(depth);
+        value
+    }
+
+    /// In partial mode, record the value at the current path as incomplete.
+    fn mark_incomplete(&mut self, kind: IncompleteKind) {
+        self.incomplete.push(Incomplete {
+            path: pointer(&self.path),
+            kind,
+        });
+    }
+
+    /// After a primitive has been read, record it as incomplete if the input
+    /// ended inside it. Only the last token can be an unterminated string.
+    fn check_unterminated(&mut self) {
+        if self.partial && matches!(self.current_token, Token::Eof) && self.scanner.unterminated() {
+            self.mark_incomplete(IncompleteKind::String);
+        }
+    }
+
+    /// In partial mode, end an array early because the input ran out after
+    /// `received` elements.
+    fn cut_off(&mut self, cursor: &mut ArrayCursor<'a>, received: usize) {
+        self.mark_incomplete(IncompleteKind::Array {
+            declared: cursor.length,
+            received,
+        });
+        cursor.length = received;
+    }
+
+    /// Read the start of the document and determine its root form.
+    pub(crate) fn parse_root(&mut self) -> ToonResult<Node<'a>> {
+        if let Some(err) = self.start_error.take() {
+            return Err(err);
+        }
+        let depth = 0;
+        self.check_depth(depth)?;
 
         self.skip_newlines()?;
+        self.value_start = self.scanner.token_span();
 
         match &self.current_token {
             Token::Null => {
                 // "null:" indicates "null" is a key, not a value
                 let next_char_is_colon = matches!(self.scanner.peek(), Some(':'));
                 if next_char_is_colon {
-                    let key = KEYWORDS[0].to_string();
+                    let key = Cow::Borrowed(KEYWORDS[0]);
                     self.advance()?;
-                    self.parse_object_with_initial_key(key, depth)
+                    Ok(self.root_object(key, depth))
                 } else {
                     self.advance()?;
-                    Ok(Value::Null)
+                    Ok(Node::Primitive(Scalar::Null))
                 }
             }
             Token::Bool(b) => {
                 let next_char_is_colon = matches!(self.scanner.peek(), Some(':'));
                 if next_char_is_colon {
                     let key = if *b {
-                        KEYWORDS[1].to_string()
+                        Cow::Borrowed(KEYWORDS[1])
                     } else {
-                        KEYWORDS[2].to_string()
+                        Cow::Borrowed(KEYWORDS[2])
                     };
                     self.advance()?;
-                    self.parse_object_with_initial_key(key, depth)
+                    Ok(self.root_object(key, depth))
                 } else {
                     let val = *b;
                     self.advance()?;
-                    Ok(Value::Bool(val))
+                    Ok(Node::Primitive(Scalar::Bool(val)))
                 }
             }
             Token::Integer(i) => {
-                let next_char_is_colon = matches!(self.scanner.peek(), Some(':'));
-                if next_char_is_colon {
-                    let key = i.to_string();
-                    self.advance()?;
-                    self.parse_object_with_initial_key(key, depth)
-                } else {
-                    let val = *i;
-                    self.advance()?;
-                    Ok(serde_json::Number::from(val).into())
-                }
+                let number = (*i).into();
+                self.parse_root_number(number, depth)
+            }
+            Token::Unsigned(u) => {
+                let number = (*u).into();
+                self.parse_root_number(number, depth)
+            }
+            #[cfg(feature = "arbitrary_precision")]
+            Token::Decimal(n) => {
+                let number = n.clone();
+                self.parse_root_number(number, depth)
             }
             Token::Number(n) => {
                 let next_char_is_colon = matches!(self.scanner.peek(), Some(':'));
                 if next_char_is_colon {
-                    let key = n.to_string();
+                    let key = Cow::Owned(n.to_string());
                     self.advance()?;
-                    self.parse_object_with_initial_key(key, depth)
+                    Ok(self.root_object(key, depth))
                 } else {
                     let val = *n;
                     self.advance()?;
-                    Ok(serde_json::Number::from_f64(val)
-                        .ok_or_else(|| ToonError::InvalidInput(format!("Invalid number: {}", val)))?
-                        .into())
+                    Ok(Node::Primitive(Scalar::Number(
+                        Number::from_f64(val).ok_or_else(|| {
+                            ToonError::InvalidInput(format!("Invalid number: {}", val))
+                        })?,
+                    )))
                 }
             }
-            Token::String(s, _) => {
+            Token::String(s, quoted) => {
                 let first = s.clone();
+                let quoted = *quoted;
                 self.advance()?;
 
                 match &self.current_token {
                     Token::Colon | Token::LeftBracket => {
-                        self.parse_object_with_initial_key(first, depth)
+                        let mut root = self.root_object(first, depth);
+                        if let Node::Object(cursor) = &mut root {
+                            cursor.key_quoted = quoted;
+                        }
+                        Ok(root)
                     }
                     _ => {
                         // Accumulate consecutive strings with spaces (e.g., "hello" "world" ->
                         // "hello world")
                         let mut accumulated = first;
-                        loop {
-                            match &self.current_token {
-                                Token::String(next, _) => {
-                                    if !accumulated.is_empty() {
-                                        accumulated.push(' ');
-                                    }
-                                    accumulated.push_str(next);
-                                    self.advance()?;
-                                }
-                                _ => break,
+                        while let Token::String(next, _) = &self.current_token {
+                            let joined = accumulated.to_mut();
+                            if !joined.is_empty() {
+                                joined.push(' ');
                             }
+                            joined.push_str(next);
+                            self.advance()?;
                         }
-                        Ok(Value::String(accumulated))
+                        let max = self.options.limits.max_string_length;
+                        self.check_limit(Limit::StringLength, accumulated.len(), max)?;
+                        Ok(Node::Primitive(Scalar::String(accumulated)))
                     }
                 }
             }
-            Token::LeftBracket => self.parse_root_array(depth),
-            Token::Eof => Ok(Value::Null),
-            _ => self.parse_object(depth),
+            Token::LeftBracket => Ok(Node::Array(self.begin_array(depth)?)),
+            Token::Eof => Ok(Node::Primitive(Scalar::Null)),
+            _ => Err(self
+                .parse_error_with_context(format!("Expected key, found {:?}", self.current_token))
+                .with_suggestion("Object keys must be strings")),
         }
     }
 
-    fn parse_object(&mut self, depth: usize) -> ToonResult<Value> {
-        validate_depth(depth, MAX_DEPTH)?;
-
-        let mut obj = Map::new();
-        let mut base_indent: Option<usize> = None;
-
-        loop {
-            while matches!(self.current_token, Token::Newline) {
-                self.advance()?;
-            }
-
-            if matches!(self.current_token, Token::Eof) {
-                break;
-            }
-
-            let current_indent = self.scanner.get_last_line_indent();
-            if let Some(expected) = base_indent {
-                if current_indent != expected {
-                    break;
-                }
-            } else {
-                base_indent = Some(current_indent);
-            }
-
-            let key = match &self.current_token {
-                Token::String(s, _) => s.clone(),
-                _ => {
-                    return Err(self
-                        .parse_error_with_context(format!(
-                            "Expected key, found {:?}",
-                            self.current_token
-                        ))
-                        .with_suggestion("Object keys must be strings"));
-                }
-            };
+    /// A number at the root, or the key of a root object such as `1: x`.
+    fn parse_root_number(&mut self, number: Number, depth: usize) -> ToonResult<Node<'a>> {
+        if matches!(self.scanner.peek(), Some(':')) {
+            let key = Cow::Owned(number.to_string());
             self.advance()?;
-
-            let value = if matches!(self.current_token, Token::LeftBracket) {
-                self.parse_array(depth)?
-            } else {
-                if !matches!(self.current_token, Token::Colon) {
-                    return Err(self
-                        .parse_error_with_context(format!(
-                            "Expected ':' or '[', found {:?}",
-                            self.current_token
-                        ))
-                        .with_suggestion("Use ':' for object values or '[' for arrays"));
-                }
-                self.advance()?;
-                self.parse_field_value(depth)?
-            };
-
-            obj.insert(key, value);
+            Ok(self.root_object(key, depth))
+        } else {
+            self.advance()?;
+            Ok(Node::Primitive(Scalar::Number(number)))
         }
-
-        Ok(Value::Object(obj))
     }
 
-    fn parse_object_with_initial_key(&mut self, key: String, depth: usize) -> ToonResult<Value> {
-        validate_depth(depth, MAX_DEPTH)?;
-
-        let mut obj = Map::new();
+    fn root_object(&self, key: Cow<'a, str>, depth: usize) -> Node<'a> {
+        Node::Object(ObjectCursor {
+            depth,
+            root: true,
+            indent: 0,
+            pending_key: Some(key),
+            key_quoted: false,
+        })
+    }
 
-        let value = if matches!(self.current_token, Token::LeftBracket) {
-            self.parse_array(depth)?
-        } else {
-            if !matches!(self.current_token, Token::Colon) {
+    /// Advance to the next key of an object, leaving the parser on the `:` or
+    /// `[` that introduces its value. Returns `None` at the end of the object.
+    pub(crate) fn next_key(
+        &mut self,
+        cursor: &mut ObjectCursor<'a>,
+    ) -> ToonResult<Option<Cow<'a, str>>> {
+        if let Some(key) = cursor.pending_key.take() {
+            if !matches!(self.current_token, Token::LeftBracket | Token::Colon) {
                 return Err(self
                     .parse_error_with_context(format!(
                         "Expected ':' or '[', found {:?}",
@@ -243,135 +639,142 @@ impl<'a> Parser<'a> {
                     ))
                     .with_suggestion("Use ':' for object values or '[' for arrays"));
             }
-            self.advance()?;
-            self.parse_field_value(depth)?
-        };
-
-        obj.insert(key, value);
+            return Ok(Some(key));
+        }
 
         self.skip_newlines()?;
 
-        loop {
+        if cursor.root {
             if matches!(self.current_token, Token::Eof) {
-                break;
+                return Ok(None);
             }
 
-            let next_key = match &self.current_token {
-                Token::String(s, _) => s.clone(),
-                _ => break,
-            };
-            self.advance()?;
-
-            let next_value = if matches!(self.current_token, Token::LeftBracket) {
-                self.parse_array(depth)?
-            } else {
-                if !matches!(self.current_token, Token::Colon) {
-                    break;
+            let key = match &self.current_token {
+                Token::String(s, quoted) => {
+                    cursor.key_quoted = *quoted;
+                    s.clone()
                 }
-                self.advance()?;
-                self.parse_field_value(depth)?
+                _ => return Ok(None),
             };
+            self.advance()?;
 
-            obj.insert(next_key, next_value);
-            self.skip_newlines()?;
+            if !matches!(self.current_token, Token::LeftBracket | Token::Colon) {
+                return Ok(None);
+            }
+            return Ok(Some(key));
         }
 
-        Ok(Value::Object(obj))
-    }
-
-    fn parse_field_value(&mut self, depth: usize) -> ToonResult<Value> {
-        match &self.current_token {
-            Token::Newline => self.parse_indented_object(depth + 1),
-            _ => self.parse_primitive(),
+        if self.scanner.get_last_line_indent() <= cursor.indent
+            || matches!(self.current_token, Token::Eof)
+        {
+            return Ok(None);
         }
-    }
+        let cut_off = |parser: &Self| parser.partial && matches!(parser.current_token, Token::Eof);
 
-    fn parse_indented_object(&mut self, depth: usize) -> ToonResult<Value> {
-        validate_depth(depth, MAX_DEPTH)?;
+        let key = match &self.current_token {
+            Token::String(s, quoted) => {
+                cursor.key_quoted = *quoted;
+                s.clone()
+            }
+            _ => {
+                return Err(self
+                    .parse_error_with_context(format!(
+                        "Expected key, found {:?}",
+                        self.current_token
+                    ))
+                    .with_suggestion("Object keys must be strings"));
+            }
+        };
 
-        let mut obj = Map::new();
+        self.advance()?;
 
-        loop {
-            while matches!(self.current_token, Token::Newline) {
-                self.advance()?;
-            }
+        if cut_off(self) {
+            return Ok(None);
+        }
+        if !matches!(self.current_token, Token::LeftBracket | Token::Colon) {
+            return Err(self
+                .parse_error_with_context(format!(
+                    "Expected ':' or '[', found {:?}",
+                    self.current_token
+                ))
+                .with_suggestion("Use ':' after object keys"));
+        }
 
-            if self.scanner.get_last_line_indent() == 0 || matches!(self.current_token, Token::Eof)
-            {
-                break;
-            }
+        Ok(Some(key))
+    }
 
-            let key = match &self.current_token {
-                Token::String(s, _) => s.clone(),
-                _ => {
-                    return Err(self
-                        .parse_error_with_context(format!(
-                            "Expected key, found {:?}",
-                            self.current_token
-                        ))
-                        .with_suggestion("Object keys must be strings"));
-                }
-            };
+    /// Read the value of the field whose key was just returned by `next_key`.
+    pub(crate) fn parse_field(&mut self, cursor: &ObjectCursor<'a>) -> ToonResult<Node<'a>> {
+        let depth = cursor.depth;
+        let indent = self.scanner.get_last_line_indent();
 
-            self.advance()?;
+        if matches!(self.current_token, Token::LeftBracket) {
+            return Ok(Node::Array(self.begin_array(depth)?));
+        }
 
-            let value = if matches!(self.current_token, Token::LeftBracket) {
-                self.parse_array(depth)?
-            } else {
-                if !matches!(self.current_token, Token::Colon) {
-                    return Err(self
-                        .parse_error_with_context(format!(
-                            "Expected ':' or '[', found {:?}",
-                            self.current_token
-                        ))
-                        .with_suggestion("Use ':' after object keys"));
-                }
-                self.advance()?;
-                self.parse_field_value(depth)?
-            };
+        self.advance()?;
 
-            obj.insert(key, value);
-            while matches!(self.current_token, Token::Newline) {
-                self.advance()?;
+        match &self.current_token {
+            Token::Newline | Token::Eof => {
+                self.check_depth(depth + 1)?;
+                Ok(Node::Object(ObjectCursor {
+                    depth: depth + 1,
+                    root: false,
+                    indent,
+                    pending_key: None,
+                    key_quoted: false,
+                }))
             }
+            _ => Ok(Node::Primitive(self.parse_scalar()?)),
         }
-
-        Ok(Value::Object(obj))
     }
 
-    fn parse_primitive(&mut self) -> ToonResult<Value> {
+    /// Parse a single primitive value (used for row cells and inline values).
+    pub(crate) fn parse_scalar(&mut self) -> ToonResult<Scalar<'a>> {
+        self.value_start = self.scanner.token_span();
         match &self.current_token {
             Token::String(s, is_quoted) => {
                 let value = if *is_quoted {
-                    Value::String(s.clone())
+                    Scalar::String(s.clone())
                 } else if self.options.coerce_types {
                     self.coerce_string_to_type(s)
                 } else {
-                    Value::String(s.clone())
+                    Scalar::String(s.clone())
                 };
                 self.advance()?;
                 Ok(value)
             }
             Token::Integer(i) => {
-                let value = Value::Number((*i).into());
+                let value = Scalar::Number((*i).into());
+                self.advance()?;
+                Ok(value)
+            }
+            Token::Unsigned(u) => {
+                let value = Scalar::Number((*u).into());
+                self.advance()?;
+                Ok(value)
+            }
+            #[cfg(feature = "arbitrary_precision")]
+            Token::Decimal(n) => {
+                let value = Scalar::Number(n.clone());
                 self.advance()?;
                 Ok(value)
             }
             Token::Number(f) => {
                 let value = Number::from_f64(*f)
-                    .map(Value::Number)
-                    .unwrap_or_else(|| Value::String(f.to_string()));
+                    .map(Scalar::Number)
+                    .unwrap_or_else(|| Scalar::String(Cow::Owned(f.to_string())));
                 self.advance()?;
                 Ok(value)
             }
             Token::Bool(b) => {
                 let value = *b;
                 self.advance()?;
-                Ok(Value::Bool(value))
+                Ok(Scalar::Bool(value))
             }
             Token::Null => {
                 self.advance()?;
-                Ok(Value::Null)
+                Ok(Scalar::Null)
             }
             _ => Err(self
                 .parse_error_with_context(format!(
@@ -382,14 +785,90 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn create_error_context(&self) -> ErrorContext {
-        let line = self.scanner.get_line();
+    pub(crate) fn create_error_context(&self) -> ErrorContext {
         let column = self.scanner.get_column();
 
-        ErrorContext::from_input(self.input, line, column, 2)
+        self.scanner
+            .error_context()
             .unwrap_or_else(|| ErrorContext::new("").with_indicator(column))
     }
 
+    /// Fail with [`ToonError::LimitExceeded`] if `found` is over `max`.
+    fn check_limit(&self, limit: Limit, found: usize, max: usize) -> ToonResult<()> {
+        if found > max {
+            return Err(ToonError::limit_exceeded(
+                limit,
+                max,
+                found,
+                self.scanner.get_line(),
+                Some(self.create_error_context()),
+            ));
+        }
+        Ok(())
+    }
+
+    fn check_depth(&self, depth: usize) -> ToonResult<()> {
+        self.check_limit(Limit::Depth, depth, self.options.limits.max_depth)
+    }
+
+    /// Fail if an object has read more keys than `max_object_keys`.
+    pub(crate) fn check_key_count(&self, count: usize) -> ToonResult<()> {
+        self.check_limit(
+            Limit::ObjectKeys,
+            count,
+            self.options.limits.max_object_keys,
+        )
+    }
+
+    /// With the `Error` duplicate key policy, fail if the object already had
+    /// `key`; in repair mode, record the repeat as a repair. `seen` holds the
+    /// line of each key read so far.
+    pub(crate) fn check_duplicate_key(
+        &mut self,
+        key: &str,
+        seen: &mut HashMap<String, usize>,
+    ) -> ToonResult<()> {
+        let policy = self.options.duplicate_key_policy();
+        if self.options.expand_paths || (policy != DuplicateKeys::Error && !self.options.repair) {
+            return Ok(());
+        }
+        match seen.get(key) {
+            Some(&first) if policy == DuplicateKeys::Error => {
+                Err(self.duplicate_key_error(key, first))
+            }
+            Some(&first) => {
+                self.repairs.push(Repair {
+                    line: self.scanner.get_line(),
+                    kind: RepairKind::DuplicateKey {
+                        key: key.to_string(),
+                        first,
+                    },
+                });
+                Ok(())
+            }
+            None => {
+                seen.insert(key.to_string(), self.scanner.get_line());
+                Ok(())
+            }
+        }
+    }
+
+    /// The error for a key, just read, that repeats the key on line `first`.
+    fn duplicate_key_error(&self, key: &str, first: usize) -> ToonError {
+        let column = self.last_span.column;
+        let context = self
+            .create_error_context()
+            .with_indicator(column - 1)
+            .with_related_line(first)
+            .with_suggestion("Remove one of the keys, or set a DuplicateKeys policy");
+        ToonError::parse_error_with_context(
+            self.scanner.get_line(),
+            column,
+            format!("Duplicate key '{}' (first on line {})", key, first),
+            context,
+        )
+    }
+
     fn parse_error_with_context(&self, message: impl Into<String>) -> ToonError {
         let context = self.create_error_context();
         ToonError::parse_error_with_context(
@@ -400,33 +879,53 @@ impl<'a> Parser<'a> {
         )
     }
 
-    fn coerce_string_to_type(&self, s: &str) -> Value {
+    /// Attach the current line and column to an error raised by a serde
+    /// visitor.
+    pub(crate) fn fix_position(&self, err: ToonError) -> ToonError {
+        match err {
+            ToonError::DeserializationError(message) => self.parse_error_with_context(message),
+            other => other,
+        }
+    }
+
+    fn coerce_string_to_type(&self, s: &Cow<'a, str>) -> Scalar<'a> {
         if s == "null" {
-            return Value::Null;
+            return Scalar::Null;
         }
 
         if s == "true" {
-            return Value::Bool(true);
+            return Scalar::Bool(true);
         }
         if s == "false" {
-            return Value::Bool(false);
+            return Scalar::Bool(false);
         }
 
         if let Ok(i) = s.parse::<i64>() {
-            return Value::Number(i.into());
+            return Scalar::Number(i.into());
+        }
+        if let Ok(u) = s.parse::<u64>() {
+            return Scalar::Number(u.into());
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        if let Ok(n) = s.parse::<Number>() {
+            return Scalar::Number(n);
         }
 
         if let Ok(f) = s.parse::<f64>() {
             if let Some(num) = Number::from_f64(f) {
-                return Value::Number(num);
+                return Scalar::Number(num);
             }
         }
 
-        Value::String(s.to_string())
+        Scalar::String(s.clone())
     }
 
-    fn parse_array(&mut self, depth: usize) -> ToonResult<Value> {
-        validate_depth(depth, MAX_DEPTH)?;
+    /// Parse an array header and position the parser on its first element.
+    fn begin_array(&mut self, depth: usize) -> ToonResult<ArrayCursor<'a>> {
+        self.check_depth(depth)?;
+        let indent = self.scanner.get_last_line_indent();
+        let line = self.scanner.get_line();
+        let start = self.scanner.token_span();
 
         if !matches!(self.current_token, Token::LeftBracket) {
             return Err(self
@@ -436,8 +935,11 @@ impl<'a> Parser<'a> {
         self.advance()?;
 
         let length = self.parse_array_length()?;
+        let max = self.options.limits.max_array_length;
+        self.check_limit(Limit::ArrayLength, length, max)?;
 
-        self.detect_or_consume_delimiter()?;
+        let outer = self.delimiter;
+        self.consume_header_delimiter()?;
 
         if !matches!(self.current_token, Token::RightBracket) {
             return Err(self
@@ -446,13 +948,10 @@ impl<'a> Parser<'a> {
         }
         self.advance()?;
 
-        if self.delimiter.is_none() {
-            self.delimiter = Some(Delimiter::Comma);
-        }
-        self.scanner.set_active_delimiter(self.delimiter);
-
         let fields = if matches!(self.current_token, Token::LeftBrace) {
-            Some(self.parse_field_list()?)
+            let fields = self.parse_field_list()?;
+            self.check_key_count(fields.0.len())?;
+            Some(fields)
         } else {
             None
         };
@@ -463,22 +962,303 @@ impl<'a> Parser<'a> {
                 .with_suggestion("Array header must end with ':'"));
         }
         self.advance()?;
+        let header = start.to(self.last_span);
 
-        if length == 0 {
-            return Ok(Value::Array(vec![]));
+        let form = if length == 0 && !self.count_lengths {
+            ArrayForm::Inline
+        } else if let Some((fields, quoted, spans)) = fields {
+            validation::validate_field_list(&fields)?;
+            self.skip_newlines()?;
+            ArrayForm::Tabular(fields.into(), quoted.into(), spans.into())
+        } else if matches!(self.current_token, Token::Newline) {
+            self.skip_newlines()?;
+            ArrayForm::List
+        } else {
+            ArrayForm::Inline
+        };
+
+        Ok(ArrayCursor {
+            length,
+            form,
+            delimiter: self.delimiter.unwrap_or_default(),
+            outer,
+            depth,
+            indent,
+            line,
+            header,
+            index: 0,
+        })
+    }
+
+    /// Read the next element of an array, or `None` once `[N]` elements have
+    /// been read.
+    pub(crate) fn next_element(
+        &mut self,
+        cursor: &mut ArrayCursor<'a>,
+    ) -> ToonResult<Option<Node<'a>>> {
+        if self.partial && cursor.index < cursor.length && self.input_ended(cursor)? {
+            self.cut_off(cursor, cursor.index);
         }
 
-        if let Some(fields) = fields {
-            validation::validate_field_list(&fields)?;
-            self.parse_tabular_array(length, fields, depth)
+        let done = if self.count_lengths {
+            !self.has_element(cursor)?
         } else {
-            self.parse_regular_array(length, depth)
+            cursor.index >= cursor.length
+        };
+        if done {
+            if self.options.repair && cursor.index != cursor.length {
+                self.repairs.push(Repair {
+                    line: cursor.line,
+                    kind: RepairKind::ArrayLength {
+                        declared: cursor.length,
+                        found: cursor.index,
+                    },
+                });
+            }
+            self.set_delimiter(cursor.outer);
+            return Ok(None);
+        }
+        self.set_delimiter(Some(cursor.delimiter));
+
+        let i = cursor.index;
+        let length = cursor.length;
+        cursor.index += 1;
+
+        match cursor.form.clone() {
+            ArrayForm::Tabular(fields, quoted, field_spans) => {
+                if i > 0 {
+                    self.skip_newlines()?;
+                }
+                Ok(Some(Node::Row(RowCursor {
+                    fields,
+                    quoted,
+                    field_spans,
+                    row: i,
+                    line: self.scanner.get_line(),
+                    cell: 0,
+                    missing: false,
+                })))
+            }
+            ArrayForm::List => {
+                if i > 0 {
+                    self.skip_newlines()?;
+                }
+
+                if !matches!(self.current_token, Token::Dash) {
+                    return Err(self
+                        .parse_error_with_context(format!(
+                            "Expected '-' for list item, found {:?}",
+                            self.current_token
+                        ))
+                        .with_suggestion(format!(
+                            "List arrays need '-' prefix for each item (item {} of {})",
+                            i + 1,
+                            length
+                        )));
+                }
+                self.advance()?;
+
+                if self.partial && matches!(self.current_token, Token::Eof) {
+                    self.cut_off(cursor, i);
+                    self.set_delimiter(cursor.outer);
+                    return Ok(None);
+                }
+                self.parse_element(cursor.depth).map(Some)
+            }
+            ArrayForm::Inline => {
+                if i > 0 {
+                    match &self.current_token {
+                        Token::Delimiter(_) => {
+                            self.advance()?;
+                        }
+                        Token::String(s, _) if s == "," || s == "|" || s == "\t" => {
+                            self.advance()?;
+                        }
+                        _ => {
+                            return Err(self
+                                .parse_error_with_context(format!(
+                                    "Expected delimiter, found {:?}",
+                                    self.current_token
+                                ))
+                                .with_suggestion(format!(
+                                    "Expected delimiter between items (item {} of {})",
+                                    i + 1,
+                                    length
+                                )));
+                        }
+                    }
+                    if self.partial && matches!(self.current_token, Token::Eof) {
+                        self.cut_off(cursor, i);
+                        self.set_delimiter(cursor.outer);
+                        return Ok(None);
+                    }
+                }
+
+                self.parse_element(cursor.depth).map(Some)
+            }
         }
     }
 
-    fn parse_root_array(&mut self, depth: usize) -> ToonResult<Value> {
-        validate_depth(depth, MAX_DEPTH)?;
-        self.parse_array(depth)
+    /// Whether the input ends where the next element of the array should
+    /// start.
+    fn input_ended(&mut self, cursor: &ArrayCursor<'a>) -> ToonResult<bool> {
+        if !matches!(cursor.form, ArrayForm::Inline) {
+            self.skip_newlines()?;
+        }
+        Ok(matches!(self.current_token, Token::Eof))
+    }
+
+    /// Whether another element of the array follows, judged by the content
+    /// alone.
+    fn has_element(&mut self, cursor: &ArrayCursor<'a>) -> ToonResult<bool> {
+        let block_continues = |parser: &mut Self| -> ToonResult<bool> {
+            parser.skip_newlines()?;
+            Ok(!matches!(parser.current_token, Token::Eof)
+                && parser.scanner.get_last_line_indent() > cursor.indent)
+        };
+
+        Ok(match cursor.form {
+            ArrayForm::Tabular(..) => block_continues(self)?,
+            ArrayForm::List => block_continues(self)? && matches!(self.current_token, Token::Dash),
+            ArrayForm::Inline if cursor.index == 0 => {
+                !matches!(self.current_token, Token::Newline | Token::Eof)
+            }
+            ArrayForm::Inline => match &self.current_token {
+                Token::Delimiter(_) => true,
+                Token::String(s, _) => s == "," || s == "|" || s == "\t",
+                _ => false,
+            },
+        })
+    }
+
+    fn parse_element(&mut self, depth: usize) -> ToonResult<Node<'a>> {
+        if matches!(self.current_token, Token::LeftBracket) {
+            Ok(Node::Array(self.begin_array(depth + 1)?))
+        } else {
+            Ok(Node::Primitive(self.parse_scalar()?))
+        }
+    }
+
+    /// Validate the number of elements read against the declared length.
+    pub(crate) fn finish_array(&self, cursor: &ArrayCursor, count: usize) -> ToonResult<()> {
+        if self.count_lengths {
+            return Ok(());
+        }
+        validation::validate_array_length(cursor.length, count, self.options.strict)
+    }
+
+    /// Advance to the next cell of a tabular row, returning its field index.
+    /// The cell value is read with `parse_scalar`.
+    pub(crate) fn next_cell(&mut self, cursor: &mut RowCursor<'a>) -> ToonResult<Option<usize>> {
+        let i = cursor.cell;
+        if i >= cursor.fields.len() {
+            if self.options.repair && self.at_delimiter() {
+                let mut found = i;
+                while self.at_delimiter() {
+                    self.advance()?;
+                    self.parse_scalar()?;
+                    found += 1;
+                }
+                self.repairs.push(Repair {
+                    line: cursor.line,
+                    kind: RepairKind::RowTruncated { expected: i, found },
+                });
+            }
+            return Ok(None);
+        }
+        cursor.cell += 1;
+
+        let cut_off = |parser: &Self| parser.partial && matches!(parser.current_token, Token::Eof);
+        if i > 0 && !cursor.missing {
+            if cut_off(self) {
+                return Ok(None);
+            }
+            match &self.current_token {
+                Token::Delimiter(_) => {
+                    self.advance()?;
+                }
+                Token::String(s, _) if s == "," || s == "|" || s == "\t" => {
+                    self.advance()?;
+                }
+                _ if self.options.repair => {
+                    cursor.missing = true;
+                    self.repairs.push(Repair {
+                        line: cursor.line,
+                        kind: RepairKind::RowPadded {
+                            expected: cursor.fields.len(),
+                            found: i,
+                        },
+                    });
+                }
+                _ => {
+                    return Err(self
+                        .parse_error_with_context(format!(
+                            "Expected delimiter in tabular row {}, got {:?}",
+                            cursor.row, self.current_token
+                        ))
+                        .with_suggestion(format!(
+                            "Expected delimiter between fields in row {}",
+                            cursor.row + 1
+                        )));
+                }
+            }
+            if cut_off(self) {
+                return Ok(None);
+            }
+        }
+
+        Ok(Some(i))
+    }
+
+    fn at_delimiter(&self) -> bool {
+        match &self.current_token {
+            Token::Delimiter(_) => true,
+            Token::String(s, _) => s == "," || s == "|" || s == "\t",
+            _ => false,
+        }
+    }
+
+    /// Read every cell of tabular row `row`, keeping the first `width`. In
+    /// strict mode the row must have exactly `width` cells.
+    pub(crate) fn read_row(&mut self, row: usize, width: usize) -> ToonResult<Vec<Scalar<'a>>> {
+        let mut cells = Vec::with_capacity(width);
+        let mut count = 0;
+
+        loop {
+            if count > 0 {
+                match &self.current_token {
+                    Token::Delimiter(_) => self.advance()?,
+                    Token::String(s, _) if s == "," || s == "|" || s == "\t" => self.advance()?,
+                    _ => break,
+                }
+            }
+
+            let cell = self.parse_scalar()?;
+            if count < width {
+                cells.push(cell);
+            }
+            count += 1;
+        }
+
+        if self.options.strict {
+            validation::validate_row_length(row, width, count)?;
+        }
+        Ok(cells)
+    }
+
+    /// Skip blank lines and report whether the next line is indented, i.e.
+    /// still belongs to the block opened by a root-level key.
+    pub(crate) fn at_indented_line(&mut self) -> ToonResult<bool> {
+        self.skip_newlines()?;
+        Ok(!matches!(self.current_token, Token::Eof) && self.scanner.get_last_line_indent() > 0)
+    }
+
+    /// Line and column of the first token on the current line.
+    pub(crate) fn line_start(&self) -> (usize, usize) {
+        (
+            self.scanner.get_line(),
+            self.scanner.get_last_line_indent() + 1,
+        )
     }
 
     fn parse_array_length(&mut self) -> ToonResult<usize> {
@@ -495,7 +1275,10 @@ impl<'a> Parser<'a> {
 
         match &self.current_token {
             Token::Integer(i) => {
-                let len = *i as usize;
+                let len = usize::try_from(*i).map_err(|_| {
+                    self.parse_error_with_context(format!("Invalid array length: {}", i))
+                        .with_suggestion("Length must be a positive number")
+                })?;
                 self.advance()?;
                 Ok(len)
             }
@@ -508,34 +1291,39 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn detect_or_consume_delimiter(&mut self) -> ToonResult<()> {
-        match &self.current_token {
-            Token::Delimiter(delim) => {
-                if self.delimiter.is_none() {
-                    self.delimiter = Some(*delim);
-                }
-                self.advance()?;
-            }
-            Token::String(s, _) if s == "," || s == "|" || s == "\t" => {
-                let delim = if s == "," {
-                    Delimiter::Comma
-                } else if s == "|" {
-                    Delimiter::Pipe
-                } else {
-                    Delimiter::Tab
-                };
-                if self.delimiter.is_none() {
-                    self.delimiter = Some(delim);
-                }
-                self.advance()?;
-            }
-            _ => {}
+    /// Consume the optional delimiter symbol of an array header and make the
+    /// array's delimiter active. Without a symbol the array uses the
+    /// configured delimiter, or comma. A configured delimiter overrides the
+    /// header.
+    fn consume_header_delimiter(&mut self) -> ToonResult<()> {
+        let declared = match &self.current_token {
+            Token::Delimiter(delim) => Some(*delim),
+            Token::String(s, _) if s == "," => Some(Delimiter::Comma),
+            Token::String(s, _) if s == "|" => Some(Delimiter::Pipe),
+            Token::String(s, _) if s == "\t" => Some(Delimiter::Tab),
+            _ => None,
+        };
+        if declared.is_some() {
+            self.advance()?;
         }
-        self.scanner.set_active_delimiter(self.delimiter);
+
+        let delimiter = self
+            .options
+            .delimiter
+            .or(declared)
+            .unwrap_or(Delimiter::Comma);
+        self.set_delimiter(Some(delimiter));
         Ok(())
     }
 
-    fn parse_field_list(&mut self) -> ToonResult<Vec<String>> {
+    fn set_delimiter(&mut self, delimiter: Option<Delimiter>) {
+        self.delimiter = delimiter;
+        self.scanner.set_active_delimiter(delimiter);
+    }
+
+    /// Parse a `{a,b}` field list into the field names, whether each was
+    /// quoted, and where each was written.
+    fn parse_field_list(&mut self) -> ToonResult<FieldList<'a>> {
         if !matches!(self.current_token, Token::LeftBrace) {
             return Err(self
                 .parse_error_with_context("Expected '{'")
@@ -544,12 +1332,16 @@ impl<'a> Parser<'a> {
         self.advance()?;
 
         let mut fields = Vec::new();
+        let mut quoted = Vec::new();
+        let mut spans = Vec::new();
 
         loop {
             match &self.current_token {
-                Token::String(s, _) => {
+                Token::String(s, is_quoted) => {
                     fields.push(s.clone());
+                    quoted.push(*is_quoted);
                     self.advance()?;
+                    spans.push(self.last_span);
 
                     if matches!(self.current_token, Token::Delimiter(_)) {
                         self.advance()?;
@@ -576,139 +1368,34 @@ impl<'a> Parser<'a> {
         }
         self.advance()?;
 
-        Ok(fields)
-    }
-
-    fn parse_tabular_array(
-        &mut self,
-        length: usize,
-        fields: Vec<String>,
-        depth: usize,
-    ) -> ToonResult<Value> {
-        validate_depth(depth, MAX_DEPTH)?;
-
-        let mut rows = Vec::new();
-
-        self.skip_newlines()?;
-
-        self.scanner.set_active_delimiter(self.delimiter);
-
-        for row_index in 0..length {
-            let mut row = Map::new();
-
-            for (i, field) in fields.iter().enumerate() {
-                if i > 0 {
-                    match &self.current_token {
-                        Token::Delimiter(_) => {
-                            self.advance()?;
-                        }
-                        Token::String(s, _) if s == "," || s == "|" || s == "\t" => {
-                            self.advance()?;
-                        }
-                        _ => {
-                            return Err(self
-                                .parse_error_with_context(format!(
-                                    "Expected delimiter in tabular row {}, got {:?}",
-                                    row_index, self.current_token
-                                ))
-                                .with_suggestion(&format!(
-                                    "Expected delimiter between fields in row {}",
-                                    row_index + 1
-                                )));
-                        }
-                    }
-                }
-
-                let value = self.parse_primitive()?;
-                row.insert(field.clone(), value);
-            }
-
-            rows.push(Value::Object(row));
-
-            if row_index < length - 1 {
-                self.skip_newlines()?;
-            }
-        }
-
-        validation::validate_array_length(length, rows.len(), self.options.strict)?;
-
-        Ok(Value::Array(rows))
+        Ok((fields, quoted, spans))
     }
+}
 
-    fn parse_regular_array(&mut self, length: usize, depth: usize) -> ToonResult<Value> {
-        let mut items = Vec::new();
-
-        match &self.current_token {
-            Token::Newline => {
-                self.skip_newlines()?;
-
-                for i in 0..length {
-                    if !matches!(self.current_token, Token::Dash) {
-                        return Err(self
-                            .parse_error_with_context(format!(
-                                "Expected '-' for list item, found {:?}",
-                                self.current_token
-                            ))
-                            .with_suggestion(&format!(
-                                "List arrays need '-' prefix for each item (item {} of {})",
-                                i + 1,
-                                length
-                            )));
-                    }
-                    self.advance()?;
-
-                    let value = if matches!(self.current_token, Token::LeftBracket) {
-                        self.parse_array(depth + 1)?
-                    } else {
-                        self.parse_primitive()?
-                    };
-
-                    items.push(value);
-
-                    if items.len() < length {
-                        self.skip_newlines()?;
-                    }
-                }
-            }
+/// Insert an object field whose key may already be present, following the
+/// duplicate key policy. `collected` holds the keys whose values have been
+/// gathered into an array.
+fn insert_field(
+    obj: &mut Map<String, Value>,
+    key: String,
+    value: Value,
+    policy: DuplicateKeys,
+    collected: &mut HashSet<String>,
+) {
+    let Some(existing) = obj.get_mut(&key) else {
+        obj.insert(key, value);
+        return;
+    };
+    match policy {
+        DuplicateKeys::Error | DuplicateKeys::LastWins => *existing = value,
+        DuplicateKeys::FirstWins => {}
+        DuplicateKeys::CollectArray => match existing {
+            Value::Array(items) if collected.contains(&key) => items.push(value),
             _ => {
-                for i in 0..length {
-                    if i > 0 {
-                        match &self.current_token {
-                            Token::Delimiter(_) => {
-                                self.advance()?;
-                            }
-                            Token::String(s, _) if s == "," || s == "|" || s == "\t" => {
-                                self.advance()?;
-                            }
-                            _ => {
-                                return Err(self
-                                    .parse_error_with_context(format!(
-                                        "Expected delimiter, found {:?}",
-                                        self.current_token
-                                    ))
-                                    .with_suggestion(&format!(
-                                        "Expected delimiter between items (item {} of {})",
-                                        i + 1,
-                                        length
-                                    )));
-                            }
-                        }
-                    }
-
-                    let value = if matches!(self.current_token, Token::LeftBracket) {
-                        self.parse_array(depth + 1)?
-                    } else {
-                        self.parse_primitive()?
-                    };
-
-                    items.push(value);
-                }
+                *existing = Value::Array(vec![existing.take(), value]);
+                collected.insert(key);
             }
-        }
-
-        validation::validate_array_length(length, items.len(), self.options.strict)?;
-
-        Ok(Value::Array(items))
+        },
     }
 }
 
@@ -0,0 +1,428 @@
+//! Lenient decoding of model-generated TOON.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{
+    decode::parser::Parser,
+    error::ToonResult,
+    types::{
+        DecodeOptions,
+        Indent,
+    },
+};
+
+/// A fix made while decoding in repair mode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    /// 1-based line of the input the fix applies to.
+    pub line: usize,
+    pub kind: RepairKind,
+}
+
+/// What was wrong with the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RepairKind {
+    /// The document was wrapped in a markdown code fence, which was removed
+    /// along with any text outside it.
+    CodeFence,
+    /// A line was indented by `found` columns, counting a tab as two, and
+    /// re-indented with `fixed` spaces, or `fixed` tabs if `tabs` is set.
+    Indentation {
+        found: usize,
+        fixed: usize,
+        tabs: bool,
+    },
+    /// An array header declared `declared` elements but `found` were present.
+    ArrayLength { declared: usize, found: usize },
+    /// A tabular row had `found` cells instead of `expected`; the missing
+    /// cells were filled with `null`.
+    RowPadded { expected: usize, found: usize },
+    /// A tabular row had `found` cells instead of `expected`; the extra cells
+    /// were dropped.
+    RowTruncated { expected: usize, found: usize },
+    /// An object repeated `key`, first seen on line `first`. The values were
+    /// merged by the duplicate key policy, which keeps the last by default.
+    DuplicateKey { key: String, first: usize },
+}
+
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            RepairKind::CodeFence => write!(f, "removed markdown code fence"),
+            RepairKind::Indentation { found, fixed, tabs } => {
+                let unit = match (tabs, fixed) {
+                    (true, 1) => "tab",
+                    (true, _) => "tabs",
+                    (false, 1) => "space",
+                    (false, _) => "spaces",
+                };
+                write!(
+                    f,
+                    "re-indented from {} columns to {} {}",
+                    found, fixed, unit
+                )
+            }
+            RepairKind::ArrayLength { declared, found } => {
+                write!(f, "array declared {} items but has {}", declared, found)
+            }
+            RepairKind::RowPadded { expected, found } => write!(
+                f,
+                "row has {} of {} cells, filled the rest with null",
+                found, expected
+            ),
+            RepairKind::RowTruncated { expected, found } => write!(
+                f,
+                "row has {} cells, dropped all but the first {}",
+                found, expected
+            ),
+            RepairKind::DuplicateKey { key, first } => {
+                write!(f, "key '{}' repeats the key on line {}", key, first)
+            }
+        }
+    }
+}
+
+/// Decode TOON written by a language model, fixing common mistakes, and
+/// report each fix that was made.
+///
+/// This ignores the repair setting of `options` and always repairs; see
+/// [`DecodeOptions::repair`] for what is fixed.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     decode_with_repairs,
+///     DecodeOptions,
+///     RepairKind,
+/// };
+/// use serde_json::json;
+///
+/// let input = "```toon\nusers[3]{id,name}:\n  1,Alice\n  2\n```";
+/// let (value, repairs) = decode_with_repairs(input, &DecodeOptions::new())?;
+///
+/// assert_eq!(
+///     value,
+///     json!({"users": [{"id": 1, "name": "Alice"}, {"id": 2, "name": null}]})
+/// );
+/// assert_eq!(repairs[0].kind, RepairKind::CodeFence);
+/// assert_eq!(
+///     repairs[1].kind,
+///     RepairKind::ArrayLength {
+///         declared: 3,
+///         found: 2
+///     }
+/// );
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn decode_with_repairs(
+    input: &str,
+    options: &DecodeOptions,
+) -> ToonResult<(Value, Vec<Repair>)> {
+    let (text, mut repairs) = normalize(input, options.indent);
+
+    let mut parser = Parser::new(&text, options.clone().repair()).count_lengths();
+    let value = parser.parse()?;
+
+    repairs.extend(parser.take_repairs());
+    repairs.sort_by_key(|repair| repair.line);
+    Ok((value, repairs))
+}
+
+/// Strip code fences and normalize indentation to `indent`. Removed lines
+/// are left blank so that line numbers still match the input.
+fn normalize(input: &str, indent: Indent) -> (String, Vec<Repair>) {
+    let mut repairs = Vec::new();
+    let mut lines: Vec<&str> = input.lines().collect();
+
+    let is_fence = |line: &str| line.trim_start().starts_with("```");
+    if let Some(open) = lines.iter().position(|line| is_fence(line)) {
+        let close = lines[open + 1..]
+            .iter()
+            .position(|line| is_fence(line))
+            .map_or(lines.len(), |i| open + 1 + i);
+        for (i, line) in lines.iter_mut().enumerate() {
+            if i <= open || i >= close {
+                *line = "";
+            }
+        }
+        repairs.push(Repair {
+            line: open + 1,
+            kind: RepairKind::CodeFence,
+        });
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut levels = vec![0];
+    let mut unit = 0;
+
+    for (i, line) in lines.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+
+        let content = line.trim_start_matches([' ', '\t']);
+        if content.is_empty() {
+            continue;
+        }
+
+        let prefix = &line[..line.len() - content.len()];
+        let found: usize = prefix
+            .chars()
+            .map(|ch| if ch == '\t' { 2 } else { 1 })
+            .sum();
+        if unit == 0 && found > 0 {
+            unit = found;
+        }
+
+        // An indent less than one unit past the current level is drift, not
+        // a new level.
+        while found < *levels.last().unwrap_or(&0) {
+            levels.pop();
+        }
+        let top = *levels.last().unwrap_or(&0);
+        if found >= top + unit.max(1) {
+            levels.push(found);
+        }
+
+        // Keep the document's own unit if the configured indentation allows
+        // it, and re-indent in the configured unit otherwise. Tabs count as
+        // two columns, as above.
+        let depth = levels.len() - 1;
+        let (fixed, indentation) = match indent {
+            Indent::Tabs => (depth, "\t".repeat(depth)),
+            Indent::Spaces(n) if n > 0 && unit % n != 0 => (depth * n, " ".repeat(depth * n)),
+            _ => (depth * unit, " ".repeat(depth * unit)),
+        };
+        if prefix != indentation {
+            repairs.push(Repair {
+                line: i + 1,
+                kind: RepairKind::Indentation {
+                    found,
+                    fixed,
+                    tabs: indent == Indent::Tabs,
+                },
+            });
+        }
+        output.push_str(&indentation);
+        output.push_str(content);
+    }
+
+    (output, repairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::DuplicateKeys;
+
+    fn repair(input: &str) -> (Value, Vec<Repair>) {
+        decode_with_repairs(input, &DecodeOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_strips_code_fence() {
+        let (value, repairs) = repair("Here you go:\n\n```toon\na: 1\nb[2]: x,y\n```\nDone.");
+        assert_eq!(value, json!({"a": 1, "b": ["x", "y"]}));
+        assert_eq!(
+            repairs,
+            vec![Repair {
+                line: 3,
+                kind: RepairKind::CodeFence
+            }]
+        );
+    }
+
+    #[test]
+    fn test_fixes_lengths_and_rows() {
+        let input = "tags[2]: a,b,c\nrows[1]{id,name}:\n  1,Alice,extra\n  2\nlist[3]:\n  - x";
+        let (value, repairs) = repair(input);
+        assert_eq!(
+            value,
+            json!({
+                "tags": ["a", "b", "c"],
+                "rows": [{"id": 1, "name": "Alice"}, {"id": 2, "name": null}],
+                "list": ["x"]
+            })
+        );
+
+        let kinds: Vec<(usize, RepairKind)> =
+            repairs.into_iter().map(|r| (r.line, r.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (
+                    1,
+                    RepairKind::ArrayLength {
+                        declared: 2,
+                        found: 3
+                    }
+                ),
+                (
+                    2,
+                    RepairKind::ArrayLength {
+                        declared: 1,
+                        found: 2
+                    }
+                ),
+                (
+                    3,
+                    RepairKind::RowTruncated {
+                        expected: 2,
+                        found: 3
+                    }
+                ),
+                (
+                    4,
+                    RepairKind::RowPadded {
+                        expected: 2,
+                        found: 1
+                    }
+                ),
+                (
+                    5,
+                    RepairKind::ArrayLength {
+                        declared: 3,
+                        found: 1
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_normalizes_indentation() {
+        let input = "a:\n  b: 1\n   c: 2\nrows[2]{id}:\n\t1\n   2";
+        let (value, repairs) = repair(input);
+        assert_eq!(
+            value,
+            json!({"a": {"b": 1, "c": 2}, "rows": [{"id": 1}, {"id": 2}]})
+        );
+        assert_eq!(
+            repairs,
+            vec![
+                Repair {
+                    line: 3,
+                    kind: RepairKind::Indentation {
+                        found: 3,
+                        fixed: 2,
+                        tabs: false
+                    }
+                },
+                Repair {
+                    line: 5,
+                    kind: RepairKind::Indentation {
+                        found: 2,
+                        fixed: 2,
+                        tabs: false
+                    }
+                },
+                Repair {
+                    line: 6,
+                    kind: RepairKind::Indentation {
+                        found: 3,
+                        fixed: 2,
+                        tabs: false
+                    }
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reindents_to_configured_unit() {
+        let input = "a:\n   b: 1\n   c: 2";
+        let (value, repairs) = repair(input);
+        assert_eq!(value, json!({"a": {"b": 1, "c": 2}}));
+        assert_eq!(
+            repairs[0].kind,
+            RepairKind::Indentation {
+                found: 3,
+                fixed: 2,
+                tabs: false
+            }
+        );
+
+        assert_eq!(
+            repairs[0].to_string(),
+            "line 2: re-indented from 3 columns to 2 spaces"
+        );
+
+        let auto = DecodeOptions::new().with_indent(Indent::Auto);
+        let (value, repairs) = decode_with_repairs(input, &auto).unwrap();
+        assert_eq!(value, json!({"a": {"b": 1, "c": 2}}));
+        assert!(repairs.is_empty());
+
+        let tabs = DecodeOptions::new().with_indent(Indent::Tabs);
+        let (value, repairs) = decode_with_repairs("a:\n  b: 1\n  c:\n      d: 2", &tabs).unwrap();
+        assert_eq!(value, json!({"a": {"b": 1, "c": {"d": 2}}}));
+        assert_eq!(
+            repairs[0].to_string(),
+            "line 2: re-indented from 2 columns to 1 tab"
+        );
+        assert_eq!(
+            repairs[2].to_string(),
+            "line 4: re-indented from 6 columns to 2 tabs"
+        );
+    }
+
+    #[test]
+    fn test_repeated_keys() {
+        let input = "a: 1\nb:\n  c: 2\n  c: 3\na: 4";
+        let (value, repairs) = repair(input);
+        assert_eq!(value, json!({"a": 4, "b": {"c": 3}}));
+        assert_eq!(
+            repairs,
+            vec![
+                Repair {
+                    line: 4,
+                    kind: RepairKind::DuplicateKey {
+                        key: "c".to_string(),
+                        first: 3
+                    }
+                },
+                Repair {
+                    line: 5,
+                    kind: RepairKind::DuplicateKey {
+                        key: "a".to_string(),
+                        first: 1
+                    }
+                },
+            ]
+        );
+
+        let first = DecodeOptions::new().with_duplicate_keys(DuplicateKeys::FirstWins);
+        let (value, _) = decode_with_repairs(input, &first).unwrap();
+        assert_eq!(value, json!({"a": 1, "b": {"c": 2}}));
+
+        let error = DecodeOptions::new().with_duplicate_keys(DuplicateKeys::Error);
+        assert!(decode_with_repairs(input, &error).is_err());
+    }
+
+    #[test]
+    fn test_repair_option() {
+        let input = "```\nitems[3]: a,b\n```";
+        let options = DecodeOptions::new().repair();
+        assert_eq!(
+            crate::decode(input, &options).unwrap(),
+            json!({"items": ["a", "b"]})
+        );
+
+        let items: std::collections::HashMap<String, Vec<String>> =
+            crate::from_toon(input, Some(&options)).unwrap();
+        assert_eq!(items["items"], vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_valid_input_needs_no_repairs() {
+        let input = "users[2]{id,name}:\n    1,Alice\n    2,Bob\nmeta:\n    count: 2";
+        let (value, repairs) = repair(input);
+        assert_eq!(value, crate::decode_default(input).unwrap());
+        assert!(repairs.is_empty());
+    }
+}
@@ -1,14 +1,35 @@
+use std::{
+    borrow::Cow,
+    io::BufRead,
+    marker::PhantomData,
+};
+
+#[cfg(feature = "arbitrary_precision")]
+use serde_json::Number;
+
 use crate::{
+    diagnostic::Span,
     error::{
+        ErrorContext,
+        Limit,
         ToonError,
         ToonResult,
     },
-    types::Delimiter,
+    types::{
+        Delimiter,
+        Indent,
+        Limits,
+    },
 };
 
 /// Tokens produced by the scanner during lexical analysis.
+///
+/// Strings borrow from the input unless they contain escape sequences.
+/// Integers that fit in `i64` or `u64` are read exactly. With the
+/// `arbitrary_precision` feature, every other number is a [`Token::Decimal`]
+/// that keeps its text; otherwise it is read as an `f64`.
 #[derive(Debug, Clone, PartialEq)]
-pub enum Token {
+pub enum Token<'a> {
     LeftBracket,
     RightBracket,
     LeftBrace,
@@ -16,38 +37,245 @@ pub enum Token {
     Colon,
     Dash,
     Newline,
-    String(String, bool),
+    String(Cow<'a, str>, bool),
     Number(f64),
     Integer(i64),
+    Unsigned(u64),
+    #[cfg(feature = "arbitrary_precision")]
+    Decimal(Number),
     Bool(bool),
     Null,
     Delimiter(Delimiter),
     Eof,
 }
 
+/// Text that the scanner reads from.
+///
+/// A `&str` exposes the whole document, so tokens borrow from it. A
+/// [`ReaderSource`] only buffers the lines around the current position.
+pub trait Source<'a> {
+    /// The buffered text. Scanner positions are byte offsets into it.
+    fn text(&self) -> &str;
+
+    /// Return the buffered text in `start..end` as a token value.
+    fn slice(&self, start: usize, end: usize) -> Cow<'a, str>;
+
+    /// Called before each token. May discard text before the line containing
+    /// `position` and buffer text ahead of it. Returns the adjusted position.
+    fn refill(&mut self, position: usize) -> ToonResult<usize>;
+
+    /// Buffer more text once the scanner reaches the end of the buffer.
+    /// Returns `false` when there is no more input.
+    fn extend(&mut self) -> bool;
+
+    /// Build error context for the given position.
+    fn error_context(&self, position: usize, line: usize, column: usize) -> Option<ErrorContext>;
+}
+
+impl<'a> Source<'a> for &'a str {
+    fn text(&self) -> &str {
+        self
+    }
+
+    fn slice(&self, start: usize, end: usize) -> Cow<'a, str> {
+        Cow::Borrowed(&self[start..end])
+    }
+
+    fn refill(&mut self, position: usize) -> ToonResult<usize> {
+        Ok(position)
+    }
+
+    fn extend(&mut self) -> bool {
+        false
+    }
+
+    fn error_context(&self, _position: usize, line: usize, column: usize) -> Option<ErrorContext> {
+        ErrorContext::from_input(self, line, column, 2)
+    }
+}
+
+/// Source that reads lines from a [`BufRead`] on demand.
+///
+/// Only the current line and the one after it are kept in memory, so tokens
+/// are always owned.
+pub struct ReaderSource<R> {
+    reader: R,
+    buffer: String,
+    eof: bool,
+    error: Option<std::io::Error>,
+}
+
+impl<R: BufRead> ReaderSource<R> {
+    /// Create a source that reads from the given reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: String::new(),
+            eof: false,
+            error: None,
+        }
+    }
+
+    fn read_line(&mut self) -> bool {
+        if self.eof {
+            return false;
+        }
+        match self.reader.read_line(&mut self.buffer) {
+            Ok(0) => {
+                self.eof = true;
+                false
+            }
+            Ok(_) => true,
+            Err(err) => {
+                self.error = Some(err);
+                self.eof = true;
+                false
+            }
+        }
+    }
+}
+
+impl<'a, R: BufRead> Source<'a> for ReaderSource<R> {
+    fn text(&self) -> &str {
+        &self.buffer
+    }
+
+    fn slice(&self, start: usize, end: usize) -> Cow<'a, str> {
+        Cow::Owned(self.buffer[start..end].to_string())
+    }
+
+    fn refill(&mut self, position: usize) -> ToonResult<usize> {
+        let line_start = self.buffer[..position].rfind('\n').map_or(0, |i| i + 1);
+        self.buffer.drain(..line_start);
+        let position = position - line_start;
+
+        // Keep the rest of the current line and the whole next line buffered,
+        // which covers all of the scanner's lookahead.
+        while self.buffer[position..].matches('\n').take(2).count() < 2 && self.read_line() {}
+
+        match self.error.take() {
+            Some(err) => Err(err.into()),
+            None => Ok(position),
+        }
+    }
+
+    fn extend(&mut self) -> bool {
+        self.read_line()
+    }
+
+    fn error_context(&self, position: usize, _line: usize, column: usize) -> Option<ErrorContext> {
+        let position = position.min(self.buffer.len());
+        let start = self.buffer[..position].rfind('\n').map_or(0, |i| i + 1);
+        let end = self.buffer[start..]
+            .find('\n')
+            .map_or(self.buffer.len(), |i| start + i);
+        Some(ErrorContext::new(&self.buffer[start..end]).with_indicator(column.saturating_sub(1)))
+    }
+}
+
 /// Scanner that tokenizes TOON input into a sequence of tokens.
-pub struct Scanner {
-    input: Vec<char>,
+pub struct Scanner<'a, S = &'a str> {
+    source: S,
     position: usize,
     line: usize,
     column: usize,
     active_delimiter: Option<Delimiter>,
     last_line_indent: usize,
+    /// Return unterminated quoted strings instead of failing.
+    partial: bool,
+    /// Set once an unterminated quoted string has been returned.
+    unterminated: bool,
+    /// Line and column at which the last token started.
+    token_start: (usize, usize),
+    /// How lines are indented. `Indent::Auto` is replaced by the style of
+    /// the first indented line.
+    indent: Indent,
+    /// Line whose indentation `Indent::Auto` was replaced by.
+    indent_line: Option<usize>,
+    /// Reject indentation that does not follow `indent`.
+    check_indent: bool,
+    /// Bounds on the document size and string length.
+    limits: Limits,
+    /// Bytes the source has discarded before the buffered text.
+    discarded: usize,
+    _input: PhantomData<&'a str>,
 }
 
-impl Scanner {
+impl<'a> Scanner<'a> {
     /// Create a new scanner for the given input string.
-    pub fn new(input: &str) -> Self {
+    pub fn new(input: &'a str) -> Self {
+        Self::with_source(input)
+    }
+}
+
+impl<'a, S: Source<'a>> Scanner<'a, S> {
+    /// Create a new scanner that reads from the given source.
+    pub fn with_source(source: S) -> Self {
         Self {
-            input: input.chars().collect(),
+            source,
             position: 0,
             line: 1,
             column: 1,
             active_delimiter: None,
             last_line_indent: 0,
+            partial: false,
+            unterminated: false,
+            token_start: (1, 1),
+            indent: Indent::default(),
+            indent_line: None,
+            check_indent: false,
+            limits: Limits::default(),
+            discarded: 0,
+            _input: PhantomData,
         }
     }
 
+    /// Build error context for the current position.
+    pub fn error_context(&self) -> Option<ErrorContext> {
+        self.source
+            .error_context(self.position, self.line, self.column)
+    }
+
+    /// Accept input that may be cut off: a quoted string that reaches the
+    /// end of input is returned as if it were closed there.
+    pub(crate) fn set_partial(&mut self) {
+        self.partial = true;
+    }
+
+    /// Whether an unterminated quoted string has been returned.
+    pub(crate) fn unterminated(&self) -> bool {
+        self.unterminated
+    }
+
+    /// Set how lines are indented, and whether indentation that does not
+    /// follow it is an error.
+    pub(crate) fn set_indent(&mut self, indent: Indent, check: bool) {
+        self.indent = indent;
+        self.check_indent = check;
+    }
+
+    /// Set the limits on document size and string length.
+    pub(crate) fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Fail once more of the document has been read than
+    /// `max_document_bytes` allows.
+    fn check_document_size(&self) -> ToonResult<()> {
+        let read = self.discarded + self.source.text().len();
+        let max = self.limits.max_document_bytes;
+        if read > max {
+            return Err(ToonError::limit_exceeded(
+                Limit::DocumentBytes,
+                max,
+                read,
+                self.line,
+                self.error_context(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Set the active delimiter for tokenizing array elements.
     pub fn set_active_delimiter(&mut self, delimiter: Option<Delimiter>) {
         self.active_delimiter = delimiter;
@@ -58,6 +286,16 @@ impl Scanner {
         (self.line, self.column)
     }
 
+    /// The range of the input covered by the last token scanned.
+    pub(crate) fn token_span(&self) -> Span {
+        Span {
+            line: self.token_start.0,
+            column: self.token_start.1,
+            end_line: self.line,
+            end_column: self.column,
+        }
+    }
+
     pub fn get_line(&self) -> usize {
         self.line
     }
@@ -67,58 +305,44 @@ impl Scanner {
     }
 
     pub fn peek(&self) -> Option<char> {
-        self.input.get(self.position).copied()
+        self.source.text()[self.position..].chars().next()
+    }
+
+    fn spaces_from(&self, idx: usize) -> usize {
+        self.source.text().as_bytes()[idx..]
+            .iter()
+            .take_while(|&&b| b == b' ')
+            .count()
     }
 
     pub fn count_leading_spaces(&self) -> usize {
-        let mut idx = self.position;
-        let mut count = 0;
-        while let Some(&ch) = self.input.get(idx) {
-            if ch == ' ' {
-                count += 1;
-                idx += 1;
-            } else {
-                break;
-            }
-        }
-        count
+        self.spaces_from(self.position)
     }
 
     pub fn count_spaces_after_newline(&self) -> usize {
-        let mut idx = self.position;
-        if self.input.get(idx) != Some(&'\n') {
+        if self.peek() != Some('\n') {
             return 0;
         }
-        idx += 1;
-        let mut count = 0;
-        while let Some(&ch) = self.input.get(idx) {
-            if ch == ' ' {
-                count += 1;
-                idx += 1;
-            } else {
-                break;
-            }
-        }
-        count
+        self.spaces_from(self.position + 1)
     }
 
     pub fn peek_ahead(&self, offset: usize) -> Option<char> {
-        self.input.get(self.position + offset).copied()
+        self.source.text()[self.position..].chars().nth(offset)
     }
 
     pub fn advance(&mut self) -> Option<char> {
-        if let Some(ch) = self.input.get(self.position) {
-            self.position += 1;
-            if *ch == '\n' {
-                self.line += 1;
-                self.column = 1;
-            } else {
-                self.column += 1;
-            }
-            Some(*ch)
+        if self.position == self.source.text().len() && !self.source.extend() {
+            return None;
+        }
+        let ch = self.peek()?;
+        self.position += ch.len_utf8();
+        if ch == '\n' {
+            self.line += 1;
+            self.column = 1;
         } else {
-            None
+            self.column += 1;
         }
+        Some(ch)
     }
 
     pub fn skip_whitespace(&mut self) {
@@ -132,22 +356,18 @@ impl Scanner {
     }
 
     /// Scan the next token from the input.
-    pub fn scan_token(&mut self) -> ToonResult<Token> {
+    pub fn scan_token(&mut self) -> ToonResult<Token<'a>> {
+        let position = self.source.refill(self.position)?;
+        self.discarded += self.position - position;
+        self.position = position;
+        self.check_document_size()?;
+
         if self.column == 1 {
-            let mut count = 0;
-            let mut idx = self.position;
-            while let Some(&ch) = self.input.get(idx) {
-                if ch == ' ' {
-                    count += 1;
-                    idx += 1;
-                } else {
-                    break;
-                }
-            }
-            self.last_line_indent = count;
+            self.start_line()?;
         }
 
         self.skip_whitespace();
+        self.token_start = (self.line, self.column);
 
         match self.peek() {
             None => Ok(Token::Eof),
@@ -176,12 +396,13 @@ impl Scanner {
                 Ok(Token::Colon)
             }
             Some('-') => {
+                let start = self.position;
                 self.advance();
                 // Check if '-' is part of a negative number
                 if let Some(ch) = self.peek() {
                     if ch.is_ascii_digit() {
-                        let num_str = self.scan_number_string(true)?;
-                        return self.parse_number(&num_str);
+                        self.scan_number_string();
+                        return self.finish_number(start);
                     }
                 }
                 Ok(Token::Dash)
@@ -212,21 +433,26 @@ impl Scanner {
             }
             Some('"') => self.scan_quoted_string(),
             Some(ch) if ch.is_ascii_digit() => {
-                let num_str = self.scan_number_string(false)?;
-                self.parse_number(&num_str)
+                let start = self.position;
+                self.scan_number_string();
+                self.finish_number(start)
             }
             Some(_) => self.scan_unquoted_string(),
         }
     }
 
-    fn scan_quoted_string(&mut self) -> ToonResult<Token> {
+    /// Scan a quoted string. The value borrows from the input unless it
+    /// contains escape sequences.
+    fn scan_quoted_string(&mut self) -> ToonResult<Token<'a>> {
         self.advance();
 
-        let mut value = String::new();
+        let start = self.position;
+        let mut owned: Option<String> = None;
         let mut escaped = false;
 
         while let Some(ch) = self.advance() {
             if escaped {
+                let value = owned.get_or_insert_with(String::new);
                 match ch {
                     'n' => value.push('\n'),
                     'r' => value.push('\r'),
@@ -241,92 +467,217 @@ impl Scanner {
                 escaped = false;
             } else if ch == '\\' {
                 escaped = true;
+                if owned.is_none() {
+                    owned = Some(self.source.text()[start..self.position - 1].to_string());
+                }
             } else if ch == '"' {
-                return Ok(Token::String(value, true));
-            } else {
+                let value = match owned {
+                    Some(value) => Cow::Owned(value),
+                    None => self.source.slice(start, self.position - 1),
+                };
+                return self.string_token(value, true);
+            } else if let Some(value) = owned.as_mut() {
                 value.push(ch);
             }
         }
 
+        if self.partial {
+            self.unterminated = true;
+            let value = match owned {
+                Some(value) => Cow::Owned(value),
+                None => self.source.slice(start, self.position),
+            };
+            return self.string_token(value, true);
+        }
         Err(ToonError::UnexpectedEof)
     }
 
-    fn scan_unquoted_string(&mut self) -> ToonResult<Token> {
-        let mut value = String::new();
+    fn scan_unquoted_string(&mut self) -> ToonResult<Token<'a>> {
+        self.scan_unquoted_from(self.position)
+    }
 
+    /// Scan an unquoted string whose first characters, from byte offset
+    /// `start`, have already been consumed.
+    fn scan_unquoted_from(&mut self, start: usize) -> ToonResult<Token<'a>> {
         while let Some(ch) = self.peek() {
-            if ch == '\n'
-                || ch == ' '
-                || ch == ':'
-                || ch == '['
-                || ch == ']'
-                || ch == '{'
-                || ch == '}'
-            {
+            if self.ends_unquoted(ch) {
                 break;
             }
-
-            if let Some(active) = self.active_delimiter {
-                if (active == Delimiter::Comma && ch == ',')
-                    || (active == Delimiter::Pipe && ch == '|')
-                    || (active == Delimiter::Tab && ch == '\t')
-                {
-                    break;
-                }
-            }
-            value.push(ch);
             self.advance();
         }
 
-        let value = if value.len() == 1 && (value == "," || value == "|" || value == "\t") {
+        let value = &self.source.text()[start..self.position];
+        let value = if value == "," || value == "|" || value == "\t" {
             value
         } else {
-            value.trim_end().to_string()
+            value.trim_end()
         };
 
-        match value.as_str() {
+        match value {
             "null" => Ok(Token::Null),
             "true" => Ok(Token::Bool(true)),
             "false" => Ok(Token::Bool(false)),
-            _ => Ok(Token::String(value, false)),
+            _ => self.string_token(self.source.slice(start, start + value.len()), false),
+        }
+    }
+
+    /// A string token, unless it is longer than `max_string_length`.
+    fn string_token(&self, value: Cow<'a, str>, quoted: bool) -> ToonResult<Token<'a>> {
+        let max = self.limits.max_string_length;
+        if value.len() > max {
+            let context = self
+                .error_context()
+                .map(|context| context.with_indicator(self.token_start.1 - 1));
+            return Err(ToonError::limit_exceeded(
+                Limit::StringLength,
+                max,
+                value.len(),
+                self.line,
+                context,
+            ));
+        }
+        Ok(Token::String(value, quoted))
+    }
+
+    /// Measure the indentation of the line that starts at the current
+    /// position, checking it if enabled. Indentation tabs are consumed.
+    fn start_line(&mut self) -> ToonResult<()> {
+        let text = &self.source.text()[self.position..];
+        let width = text
+            .find(|ch| ch != ' ' && ch != '\t')
+            .unwrap_or(text.len());
+        let prefix = &text[..width];
+        let blank = matches!(text[width..].chars().next(), None | Some('\n' | '\r'));
+        let spaces = prefix.len() - prefix.trim_start_matches(' ').len();
+        let tabs = prefix.len() - prefix.trim_start_matches('\t').len();
+
+        if self.indent == Indent::Auto && width > 0 && !blank {
+            self.indent = if tabs > 0 {
+                Indent::Tabs
+            } else {
+                Indent::Spaces(spaces)
+            };
+            self.indent_line = Some(self.line);
+        }
+
+        let problem = match self.indent {
+            _ if blank || !self.check_indent => None,
+            Indent::Tabs if prefix.contains(' ') => Some((
+                "Indentation must use tabs only".to_string(),
+                "Indent with one tab per level".to_string(),
+            )),
+            Indent::Spaces(size) if prefix.contains('\t') => Some((
+                "Tabs are not allowed in indentation".to_string(),
+                format!("Indent with {} spaces per level", size),
+            )),
+            Indent::Spaces(size) if size > 0 && !spaces.is_multiple_of(size) => {
+                let origin = match self.indent_line {
+                    Some(line) => format!(", as on line {}", line),
+                    None => String::new(),
+                };
+                Some((
+                    format!(
+                        "Indentation must be an exact multiple of {} spaces{}",
+                        size, origin
+                    ),
+                    format!(
+                        "Indent by {} or {} spaces",
+                        spaces / size * size,
+                        (spaces / size + 1) * size
+                    ),
+                ))
+            }
+            _ => None,
+        };
+        if let Some((message, suggestion)) = problem {
+            let context = self
+                .error_context()
+                .unwrap_or_else(|| ErrorContext::new(""));
+            return Err(
+                ToonError::parse_error_with_context(self.line, 1, message, context)
+                    .with_suggestion(suggestion),
+            );
         }
+
+        if self.indent == Indent::Tabs {
+            self.last_line_indent = tabs;
+            for _ in 0..tabs {
+                self.advance();
+            }
+        } else {
+            self.last_line_indent = spaces;
+        }
+        Ok(())
     }
 
     pub fn get_last_line_indent(&self) -> usize {
         self.last_line_indent
     }
 
-    fn scan_number_string(&mut self, negative: bool) -> ToonResult<String> {
-        let mut num_str = if negative {
-            String::from("-")
-        } else {
-            String::new()
+    /// Whether `ch` terminates an unquoted value.
+    fn ends_unquoted(&self, ch: char) -> bool {
+        matches!(ch, '\n' | ' ' | ':' | '[' | ']' | '{' | '}')
+            || self
+                .active_delimiter
+                .is_some_and(|active| active.as_char() == ch)
+    }
+
+    /// Finish a token that began like a number. Text such as `1,99` or
+    /// `12abc` that continues past the number is an unquoted string, except
+    /// for the delimiter symbol of an array header like `[3|]`.
+    fn finish_number(&mut self, start: usize) -> ToonResult<Token<'a>> {
+        let continues = match self.peek() {
+            Some(',' | '|' | '\t') if self.peek_ahead(1) == Some(']') => false,
+            Some(ch) => !ch.is_whitespace() && !self.ends_unquoted(ch),
+            None => false,
         };
+        if continues {
+            self.scan_unquoted_from(start)
+        } else {
+            self.parse_number(start)
+        }
+    }
 
+    /// Scan the rest of a number.
+    fn scan_number_string(&mut self) {
         while let Some(ch) = self.peek() {
             if ch.is_ascii_digit() || ch == '.' || ch == 'e' || ch == 'E' || ch == '+' || ch == '-'
             {
-                num_str.push(ch);
                 self.advance();
             } else {
                 break;
             }
         }
-
-        Ok(num_str)
     }
 
-    fn parse_number(&self, s: &str) -> ToonResult<Token> {
+    /// Parse the number that starts at byte offset `start`.
+    fn parse_number(&self, start: usize) -> ToonResult<Token<'a>> {
+        let s = &self.source.text()[start..self.position];
+        if let Ok(i) = s.parse::<i64>() {
+            return Ok(Token::Integer(i));
+        }
+        if let Ok(u) = s.parse::<u64>() {
+            return Ok(Token::Unsigned(u));
+        }
+        #[cfg(feature = "arbitrary_precision")]
+        if let Ok(n) = s.parse::<Number>() {
+            return Ok(Token::Decimal(n));
+        }
+
         if s.contains('.') || s.contains('e') || s.contains('E') {
             if let Ok(f) = s.parse::<f64>() {
                 Ok(Token::Number(f))
             } else {
-                Ok(Token::String(s.to_string(), false))
+                Ok(Token::String(
+                    self.source.slice(start, self.position),
+                    false,
+                ))
             }
-        } else if let Ok(i) = s.parse::<i64>() {
-            Ok(Token::Integer(i))
         } else {
-            Ok(Token::String(s.to_string(), false))
+            Ok(Token::String(
+                self.source.slice(start, self.position),
+                false,
+            ))
         }
     }
 
@@ -379,10 +730,47 @@ mod tests {
     fn test_scan_numbers() {
         let mut scanner = Scanner::new("42 3.14 -5");
         assert_eq!(scanner.scan_token().unwrap(), Token::Integer(42));
+        #[cfg(not(feature = "arbitrary_precision"))]
         assert_eq!(scanner.scan_token().unwrap(), Token::Number(3.14));
+        #[cfg(feature = "arbitrary_precision")]
+        assert_eq!(
+            scanner.scan_token().unwrap(),
+            Token::Decimal("3.14".parse().unwrap())
+        );
         assert_eq!(scanner.scan_token().unwrap(), Token::Integer(-5));
     }
 
+    #[test]
+    fn test_scan_large_integers() {
+        let mut scanner = Scanner::new("18446744073709551615 -9223372036854775808");
+        assert_eq!(scanner.scan_token().unwrap(), Token::Unsigned(u64::MAX));
+        assert_eq!(scanner.scan_token().unwrap(), Token::Integer(i64::MIN));
+    }
+
+    #[test]
+    fn test_scan_number_like_strings() {
+        let mut scanner = Scanner::new("1,99|2 12abc 3|]");
+        scanner.set_active_delimiter(Some(Delimiter::Pipe));
+        assert_eq!(
+            scanner.scan_token().unwrap(),
+            Token::String("1,99".into(), false)
+        );
+        assert_eq!(
+            scanner.scan_token().unwrap(),
+            Token::Delimiter(Delimiter::Pipe)
+        );
+        assert_eq!(scanner.scan_token().unwrap(), Token::Integer(2));
+        assert_eq!(
+            scanner.scan_token().unwrap(),
+            Token::String("12abc".into(), false)
+        );
+        assert_eq!(scanner.scan_token().unwrap(), Token::Integer(3));
+        assert_eq!(
+            scanner.scan_token().unwrap(),
+            Token::Delimiter(Delimiter::Pipe)
+        );
+    }
+
     #[test]
     fn test_scan_booleans() {
         let mut scanner = Scanner::new("true false");
@@ -401,7 +789,7 @@ mod tests {
         let mut scanner = Scanner::new(r#""hello world""#);
         assert_eq!(
             scanner.scan_token().unwrap(),
-            Token::String("hello world".to_string(), true)
+            Token::String("hello world".into(), true)
         );
     }
 
@@ -410,7 +798,7 @@ mod tests {
         let mut scanner = Scanner::new(r#""hello\nworld""#);
         assert_eq!(
             scanner.scan_token().unwrap(),
-            Token::String("hello\nworld".to_string(), true)
+            Token::String("hello\nworld".into(), true)
         );
     }
 
@@ -419,10 +807,27 @@ mod tests {
         let mut scanner = Scanner::new("hello");
         assert_eq!(
             scanner.scan_token().unwrap(),
-            Token::String("hello".to_string(), false)
+            Token::String("hello".into(), false)
         );
     }
 
+    #[test]
+    fn test_scan_borrows_unescaped_strings() {
+        let mut scanner = Scanner::new("plain \"quoted\" \"esc\\\"aped\"");
+        assert!(matches!(
+            scanner.scan_token().unwrap(),
+            Token::String(Cow::Borrowed("plain"), false)
+        ));
+        assert!(matches!(
+            scanner.scan_token().unwrap(),
+            Token::String(Cow::Borrowed("quoted"), true)
+        ));
+        assert!(matches!(
+            scanner.scan_token().unwrap(),
+            Token::String(Cow::Owned(s), true) if s == "esc\"aped"
+        ));
+    }
+
     #[test]
     fn test_detect_delimiter() {
         let mut scanner = Scanner::new("a,b,c");
@@ -0,0 +1,143 @@
+//! Decoding with the location of every key and value in the input.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::{
+    decode::parser::Parser,
+    diagnostic::Span,
+    error::ToonResult,
+    types::DecodeOptions,
+};
+
+/// Where a value, and the key it is stored under, were written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    /// The key of an object field or the field name of a tabular column, as
+    /// written including any quotes. `None` for array elements and the root.
+    pub key: Option<Span>,
+    /// The value. Strings include their quotes, arrays run from `[` to their
+    /// last element, and objects from their first key to their last value.
+    /// An empty object is the empty span after its `:`.
+    pub value: Span,
+}
+
+/// Decode a TOON string, also returning the location of every value by JSON
+/// Pointer (RFC 6901). The root value is under the empty pointer `""`.
+///
+/// With `expand_paths` or `flatten_tabular`, a dotted key is located at the
+/// end of its path, and the objects created for the path are not located.
+/// Repair mode is not supported: `repair` is ignored.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     decode_with_spans,
+///     DecodeOptions,
+///     Span,
+/// };
+///
+/// let input = "name: Alice\nusers[2]{id,name}:\n  1,Ada\n  2,\"Bob\"";
+/// let (value, spans) = decode_with_spans(input, &DecodeOptions::default())?;
+/// assert_eq!(value["users"][1]["name"], "Bob");
+///
+/// let location = spans["/users/1/name"];
+/// assert_eq!(location.key, Some(Span::new(2, 13, 17)));
+/// assert_eq!(location.value, Span::new(4, 5, 10));
+/// assert_eq!(spans["/name"].value, Span::new(1, 7, 12));
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn decode_with_spans(
+    input: &str,
+    options: &DecodeOptions,
+) -> ToonResult<(Value, HashMap<String, Location>)> {
+    let mut options = options.clone();
+    options.repair = false;
+
+    let mut parser = Parser::with_spans(input, options);
+    let value = parser.parse()?;
+    Ok((value, parser.take_spans()))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn spans(input: &str) -> HashMap<String, Location> {
+        decode_with_spans(input, &DecodeOptions::default())
+            .unwrap()
+            .1
+    }
+
+    fn span(line: usize, column: usize, end_line: usize, end_column: usize) -> Span {
+        Span {
+            line,
+            column,
+            end_line,
+            end_column,
+        }
+    }
+
+    #[test]
+    fn test_every_value_is_located() {
+        let input = "a: 1\nb:\n  c: \"x y\"\n  d[2]: p,q\nrows[1]{k}:\n  v\nempty:\n";
+        let (value, spans) = decode_with_spans(input, &DecodeOptions::default()).unwrap();
+        assert_eq!(
+            value,
+            json!({"a": 1, "b": {"c": "x y", "d": ["p", "q"]}, "rows": [{"k": "v"}], "empty": {}})
+        );
+
+        let mut pointers: Vec<_> = spans.keys().map(String::as_str).collect();
+        pointers.sort_unstable();
+        assert_eq!(
+            pointers,
+            vec![
+                "",
+                "/a",
+                "/b",
+                "/b/c",
+                "/b/d",
+                "/b/d/0",
+                "/b/d/1",
+                "/empty",
+                "/rows",
+                "/rows/0",
+                "/rows/0/k"
+            ]
+        );
+
+        assert_eq!(spans[""].value, span(1, 1, 7, 7));
+        assert_eq!(spans["/b"].key, Some(Span::new(2, 1, 2)));
+        assert_eq!(spans["/b"].value, span(3, 3, 4, 12));
+        assert_eq!(spans["/b/c"].value, Span::new(3, 6, 11));
+        assert_eq!(spans["/b/d"].key, Some(Span::new(4, 3, 4)));
+        assert_eq!(spans["/b/d"].value, Span::new(4, 4, 12));
+        assert_eq!(spans["/b/d/1"].key, None);
+        assert_eq!(spans["/b/d/1"].value, Span::new(4, 11, 12));
+        assert_eq!(spans["/rows"].value, span(5, 5, 6, 4));
+        assert_eq!(spans["/rows/0"].value, Span::new(6, 3, 4));
+        assert_eq!(spans["/rows/0/k"].key, Some(Span::new(5, 9, 10)));
+        assert_eq!(spans["/empty"].value, Span::new(7, 7, 7));
+    }
+
+    #[test]
+    fn test_root_values() {
+        assert_eq!(spans("  hello world")[""].value, Span::new(1, 3, 14));
+        assert_eq!(spans("[2]: 1,2")[""].value, Span::new(1, 1, 9));
+        assert_eq!(spans("[2]: 1,2")["/1"].value, Span::new(1, 8, 9));
+    }
+
+    #[test]
+    fn test_expanded_paths() {
+        let options = DecodeOptions::new().with_expand_paths(true);
+        let (value, spans) = decode_with_spans("a.b: 1\n\"c.d\": 2", &options).unwrap();
+        assert_eq!(value, json!({"a": {"b": 1}, "c.d": 2}));
+        assert_eq!(spans["/a/b"].key, Some(Span::new(1, 1, 4)));
+        assert!(!spans.contains_key("/a"));
+        assert_eq!(spans["/c.d"].value, Span::new(2, 8, 9));
+    }
+}
@@ -12,7 +12,7 @@ pub fn validate_array_length(expected: usize, actual: usize, strict: bool) -> To
 }
 
 /// Validate field list for tabular arrays (no duplicates, non-empty names).
-pub fn validate_field_list(fields: &[String]) -> ToonResult<()> {
+pub fn validate_field_list<S: AsRef<str>>(fields: &[S]) -> ToonResult<()> {
     if fields.is_empty() {
         return Err(ToonError::InvalidInput(
             "Field list cannot be empty for tabular arrays".to_string(),
@@ -21,17 +21,17 @@ pub fn validate_field_list(fields: &[String]) -> ToonResult<()> {
 
     for i in 0..fields.len() {
         for j in (i + 1)..fields.len() {
-            if fields[i] == fields[j] {
+            if fields[i].as_ref() == fields[j].as_ref() {
                 return Err(ToonError::InvalidInput(format!(
                     "Duplicate field name: '{}'",
-                    fields[i]
+                    fields[i].as_ref()
                 )));
             }
         }
     }
 
     for field in fields {
-        if field.is_empty() {
+        if field.as_ref().is_empty() {
             return Err(ToonError::InvalidInput(
                 "Field name cannot be empty".to_string(),
             ));
@@ -91,7 +91,7 @@ mod tests {
         assert!(validate_field_list(&["id".to_string(), "name".to_string()]).is_ok());
         assert!(validate_field_list(&["field1".to_string()]).is_ok());
 
-        assert!(validate_field_list(&[]).is_err());
+        assert!(validate_field_list::<String>(&[]).is_err());
 
         assert!(
             validate_field_list(&["id".to_string(), "name".to_string(), "id".to_string()]).is_err()
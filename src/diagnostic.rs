@@ -0,0 +1,1297 @@
+//! Reporting every problem in a TOON document at once.
+
+use std::{
+    collections::HashMap,
+    fmt,
+};
+
+use crate::{
+    error::ErrorContext,
+    lint::{
+        Lint,
+        LintOptions,
+    },
+    types::{
+        DecodeOptions,
+        Delimiter,
+        DuplicateKeys,
+        Indent,
+    },
+    utils::{
+        literal::is_literal_like,
+        string::{
+            is_valid_unquoted_key,
+            needs_quoting,
+            unescape_string,
+        },
+    },
+};
+
+/// How serious a diagnostic is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// What a diagnostic is about. The strict-mode checks follow SPEC.md §14.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DiagnosticCode {
+    /// The input has no non-blank lines.
+    EmptyInput,
+    /// A line could not be read at all.
+    Syntax,
+    /// A key is not followed by `:`.
+    MissingColon,
+    /// A quoted string has no closing quote.
+    UnterminatedString,
+    /// A quoted string uses an escape other than `\\`, `\"`, `\n`, `\r` or
+    /// `\t`.
+    InvalidEscape,
+    /// Indentation is not a multiple of the indent size.
+    Indentation,
+    /// Indentation contains a tab.
+    TabIndentation,
+    /// An array has a different number of elements than its `[N]` header.
+    ArrayLength,
+    /// A tabular row has a different number of values than there are fields.
+    RowWidth,
+    /// Values are separated by a different delimiter than the header
+    /// declares.
+    DelimiterMismatch,
+    /// A blank line appears inside an array.
+    BlankLine,
+    /// An object repeats a key, and duplicate keys are errors.
+    DuplicateKey,
+    /// A style problem found by [`lint`](crate::lint::lint).
+    Lint(Lint),
+}
+
+impl DiagnosticCode {
+    /// The code as a short kebab-case name, e.g. `array-length`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DiagnosticCode::EmptyInput => "empty-input",
+            DiagnosticCode::Syntax => "syntax",
+            DiagnosticCode::MissingColon => "missing-colon",
+            DiagnosticCode::UnterminatedString => "unterminated-string",
+            DiagnosticCode::InvalidEscape => "invalid-escape",
+            DiagnosticCode::Indentation => "indentation",
+            DiagnosticCode::TabIndentation => "tab-indentation",
+            DiagnosticCode::ArrayLength => "array-length",
+            DiagnosticCode::RowWidth => "row-width",
+            DiagnosticCode::DelimiterMismatch => "delimiter-mismatch",
+            DiagnosticCode::BlankLine => "blank-line",
+            DiagnosticCode::DuplicateKey => "duplicate-key",
+            DiagnosticCode::Lint(lint) => lint.as_str(),
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A range of the input. Lines and columns are 1-based, columns count
+/// characters, and the end is exclusive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+    pub end_line: usize,
+    pub end_column: usize,
+}
+
+impl Span {
+    /// A span within a single line.
+    pub fn new(line: usize, column: usize, end_column: usize) -> Self {
+        Self {
+            line,
+            column,
+            end_line: line,
+            end_column,
+        }
+    }
+
+    /// The span from the start of `self` to the end of `other`.
+    pub(crate) fn to(self, other: Span) -> Span {
+        Span {
+            end_line: other.end_line,
+            end_column: other.end_column,
+            ..self
+        }
+    }
+
+    /// The empty span at the end of `self`.
+    pub(crate) fn end(self) -> Span {
+        Span {
+            line: self.end_line,
+            column: self.end_column,
+            ..self
+        }
+    }
+}
+
+/// A problem found by [`validate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: DiagnosticCode,
+    pub message: String,
+    pub span: Span,
+    /// The offending line with its neighbours, and a suggestion for fixing
+    /// it.
+    pub context: ErrorContext,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] at line {}, column {}: {}",
+            self.severity, self.code, self.span.line, self.span.column, self.message
+        )
+    }
+}
+
+/// Check a document against the strict-mode rules of SPEC.md §14 and report
+/// every violation, in line order, instead of stopping at the first.
+///
+/// Checking resumes at the next line after a problem. Some rules are
+/// stricter than [`decode`](crate::decode): blank lines inside arrays and
+/// empty input are reported although `decode` accepts them. Indentation is
+/// checked against `options.indent`. When `options.strict` is off,
+/// strict-mode violations are reported as warnings and only syntax errors
+/// are errors.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     validate,
+///     DecodeOptions,
+///     DiagnosticCode,
+/// };
+///
+/// let input = "tags[3]: a,b\nusers[2]{id,name}:\n  1,Alice\n  2\nname \"Bob\"";
+/// let diagnostics = validate(input, &DecodeOptions::default());
+///
+/// let codes: Vec<_> = diagnostics.iter().map(|d| (d.span.line, d.code)).collect();
+/// assert_eq!(
+///     codes,
+///     vec![
+///         (1, DiagnosticCode::ArrayLength),
+///         (4, DiagnosticCode::RowWidth),
+///         (5, DiagnosticCode::MissingColon),
+///     ]
+/// );
+/// ```
+pub fn validate(input: &str, options: &DecodeOptions) -> Vec<Diagnostic> {
+    check(input, options, None)
+}
+
+/// Walk the document, reporting strict-mode violations, or only the given
+/// lints if `lints` is set.
+pub(crate) fn check(
+    input: &str,
+    options: &DecodeOptions,
+    lints: Option<&LintOptions>,
+) -> Vec<Diagnostic> {
+    let mut validator = Validator {
+        input,
+        options,
+        lints,
+        indent: options.indent,
+        indent_line: None,
+        frames: Vec::new(),
+        blank: None,
+        root_array: None,
+        length_marker: None,
+        diagnostics: Vec::new(),
+    };
+    validator.run();
+
+    let mut diagnostics = validator.diagnostics;
+    diagnostics.sort_by_key(|d| (d.span.line, d.span.column));
+    diagnostics
+}
+
+struct Line<'a> {
+    number: usize,
+    text: &'a str,
+    /// Byte offset of `content` within `text`.
+    start: usize,
+    content: &'a str,
+}
+
+impl Line<'_> {
+    /// Span of `len` bytes of the line starting at byte `offset`.
+    fn span(&self, offset: usize, len: usize) -> Span {
+        let column = |byte: usize| self.text[..byte.min(self.text.len())].chars().count() + 1;
+        Span::new(self.number, column(offset), column(offset + len))
+    }
+
+    /// Span of `len` bytes of the content starting at byte `offset`.
+    fn content_span(&self, offset: usize, len: usize) -> Span {
+        self.span(self.start + offset, len)
+    }
+}
+
+enum FrameKind {
+    Object,
+    Tabular { width: usize, delimiter: Delimiter },
+    List { delimiter: Delimiter },
+}
+
+/// An open object or array whose children are at `depth`.
+struct Frame {
+    depth: usize,
+    kind: FrameKind,
+    /// For arrays: the declared length, the header's span and the number of
+    /// elements seen.
+    declared: usize,
+    header: Option<Span>,
+    count: usize,
+    /// For objects: the line of each key seen.
+    keys: HashMap<String, usize>,
+}
+
+struct Validator<'a> {
+    input: &'a str,
+    options: &'a DecodeOptions,
+    lints: Option<&'a LintOptions>,
+    /// How lines are indented. `Indent::Auto` is replaced by the style of
+    /// the first indented line.
+    indent: Indent,
+    /// Line whose indentation `Indent::Auto` was replaced by.
+    indent_line: Option<usize>,
+    frames: Vec<Frame>,
+    /// First blank line since the last non-blank one.
+    blank: Option<usize>,
+    /// Line of the header, if the root is an array.
+    root_array: Option<usize>,
+    /// Whether the first array header used a `#` length marker, and its line.
+    length_marker: Option<(bool, usize)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Validator<'a> {
+    fn run(&mut self) {
+        let lines: Vec<Line<'a>> = self
+            .input
+            .lines()
+            .enumerate()
+            .map(|(i, text)| {
+                let content = text.trim_start_matches([' ', '\t']);
+                Line {
+                    number: i + 1,
+                    text,
+                    start: text.len() - content.len(),
+                    content: content.trim_end(),
+                }
+            })
+            .collect();
+
+        for line in &lines {
+            let trimmed = line.text.trim_end_matches([' ', '\t']);
+            if trimmed.len() < line.text.len() {
+                self.lint(
+                    Lint::TrailingSpaces,
+                    "Trailing whitespace",
+                    line.span(trimmed.len(), line.text.len() - trimmed.len()),
+                    "Remove the whitespace at the end of the line",
+                );
+            }
+        }
+        if self.input.ends_with('\n') {
+            let last = lines.last().map_or(1, |line| line.number);
+            let end = lines.last().map_or(1, |line| line.text.chars().count() + 1);
+            self.lint(
+                Lint::TrailingNewline,
+                "Document ends with a newline",
+                Span::new(last, end, end),
+                "Remove the newline at the end of the document",
+            );
+        }
+
+        let mut content_lines = lines.iter().filter(|line| !line.content.is_empty());
+        let Some(first) = content_lines.next() else {
+            self.strict(
+                DiagnosticCode::EmptyInput,
+                "Document is empty",
+                Span::new(1, 1, 1),
+                "A TOON document needs at least one value",
+            );
+            return;
+        };
+        if content_lines.next().is_none() && key_end(first.content).is_none() {
+            self.check_indentation(first);
+            let delimiter = self.document_delimiter();
+            self.check_value(first, 0, first.content, delimiter);
+            return;
+        }
+        if first.content.starts_with('[') {
+            self.root_array = Some(first.number);
+        }
+
+        self.frames.push(Frame {
+            depth: 0,
+            kind: FrameKind::Object,
+            declared: 0,
+            header: None,
+            count: 0,
+            keys: HashMap::new(),
+        });
+        for line in &lines {
+            self.line(line);
+        }
+        self.close_frames(0, None);
+        if let Some(root) = self.frames.pop() {
+            self.finish(root);
+        }
+    }
+
+    fn line(&mut self, line: &Line<'a>) {
+        if line.content.is_empty() {
+            self.blank.get_or_insert(line.number);
+            return;
+        }
+
+        let depth = self.check_indentation(line);
+        self.close_frames(depth, Some(line.content));
+
+        if let Some(blank) = self.blank.take() {
+            let in_array = self
+                .frames
+                .iter()
+                .any(|frame| !matches!(frame.kind, FrameKind::Object));
+            if in_array {
+                self.strict(
+                    DiagnosticCode::BlankLine,
+                    "Blank lines are not allowed inside arrays",
+                    Span::new(blank, 1, 1),
+                    "Remove the blank line",
+                );
+            }
+        }
+
+        let Some(top) = self.frames.last() else {
+            return;
+        };
+        if top.depth < depth
+            || (depth == 0 && self.root_array.is_some_and(|header| header != line.number))
+        {
+            self.error(
+                DiagnosticCode::Syntax,
+                "Unexpected indentation",
+                line.span(0, line.start),
+                "Check the indentation of this line",
+            );
+            return;
+        }
+
+        match top.kind {
+            FrameKind::Object => self.field(line, 0, depth + 1, depth + 1),
+            FrameKind::Tabular { width, delimiter } => self.row(line, width, delimiter),
+            FrameKind::List { delimiter } => self.item(line, depth, delimiter),
+        }
+    }
+
+    /// Close the frames that `content` at `depth` is not part of.
+    fn close_frames(&mut self, depth: usize, content: Option<&str>) {
+        while let Some(top) = self.frames.last() {
+            let ends_rows = match (&top.kind, content) {
+                (FrameKind::Tabular { delimiter, .. }, Some(content)) => {
+                    top.depth == depth && is_field(content, *delimiter)
+                }
+                _ => false,
+            };
+            if (top.depth <= depth && !ends_rows) || top.depth == 0 {
+                break;
+            }
+            if let Some(frame) = self.frames.pop() {
+                self.finish(frame);
+            }
+        }
+    }
+
+    /// Check the element count of an array that has ended.
+    fn finish(&mut self, frame: Frame) {
+        let Some(header) = frame.header else {
+            return;
+        };
+        let what = match frame.kind {
+            FrameKind::Tabular { .. } => "tabular rows",
+            _ => "list array items",
+        };
+        if frame.count != frame.declared {
+            self.strict(
+                DiagnosticCode::ArrayLength,
+                format!(
+                    "Expected {} {}, but got {}",
+                    frame.declared, what, frame.count
+                ),
+                header,
+                format!("Change the header to [{}]", frame.count),
+            );
+        }
+    }
+
+    /// Check leading whitespace and return the line's depth.
+    fn check_indentation(&mut self, line: &Line<'a>) -> usize {
+        let prefix = &line.text[..line.start];
+        if self.indent == Indent::Auto && !prefix.is_empty() {
+            self.indent = if prefix.starts_with('\t') {
+                Indent::Tabs
+            } else {
+                Indent::Spaces(prefix.len() - prefix.trim_start_matches(' ').len())
+            };
+            self.indent_line = Some(line.number);
+        }
+
+        let size = match self.indent {
+            Indent::Spaces(size) => size.max(1),
+            Indent::Tabs => {
+                if prefix.contains(' ') {
+                    self.strict(
+                        DiagnosticCode::Indentation,
+                        "Indentation must use tabs only",
+                        line.span(0, line.start),
+                        "Indent with one tab per level",
+                    );
+                }
+                return prefix.matches('\t').count();
+            }
+            Indent::Auto => return 0,
+        };
+        let width: usize = prefix
+            .chars()
+            .map(|ch| if ch == '\t' { size } else { 1 })
+            .sum();
+
+        if prefix.contains('\t') {
+            self.strict(
+                DiagnosticCode::TabIndentation,
+                "Tabs are not allowed in indentation",
+                line.span(0, line.start),
+                format!("Indent with {} spaces per level", size),
+            );
+        } else if !width.is_multiple_of(size) {
+            let origin = match self.indent_line {
+                Some(line) => format!(", as on line {}", line),
+                None => String::new(),
+            };
+            self.strict(
+                DiagnosticCode::Indentation,
+                format!(
+                    "Indentation must be an exact multiple of {} spaces{}",
+                    size, origin
+                ),
+                line.span(0, line.start),
+                format!(
+                    "Indent by {} or {} spaces",
+                    width / size * size,
+                    (width / size + 1) * size
+                ),
+            );
+        }
+        width / size
+    }
+
+    /// Check an object field starting at byte `offset` of the line content.
+    /// Nested object fields go at `object_depth` and array elements at
+    /// `array_depth`.
+    fn field(&mut self, line: &Line<'a>, offset: usize, object_depth: usize, array_depth: usize) {
+        let content = &line.content[offset..];
+        let key_len = match key_end(content) {
+            Some(end) => end,
+            None => {
+                let len = content.find([' ', ':']).unwrap_or(content.len());
+                self.error(
+                    DiagnosticCode::MissingColon,
+                    "Missing colon after key",
+                    line.content_span(offset, len),
+                    "Use ':' after object keys",
+                );
+                return;
+            }
+        };
+        if content.starts_with('"') {
+            self.check_key(line, offset, &content[..key_len]);
+        }
+        self.check_duplicate_key(line, offset, &content[..key_len]);
+
+        let mut rest = offset + key_len;
+        let header = if line.content[rest..].starts_with('[') {
+            match self.header(line, rest) {
+                Some((header, end)) => {
+                    rest = end;
+                    Some(header)
+                }
+                None => return,
+            }
+        } else {
+            None
+        };
+
+        if !line.content[rest..].starts_with(':') {
+            self.error(
+                DiagnosticCode::MissingColon,
+                "Missing colon after key",
+                line.content_span(rest, 0),
+                match header {
+                    Some(_) => "Array header must end with ':'",
+                    None => "Use ':' after object keys",
+                },
+            );
+            return;
+        }
+        let value = line.content[rest + 1..].trim_start();
+        let value_start = line.content.len() - value.len();
+
+        let Some(header) = header else {
+            if value.is_empty() {
+                self.push(object_depth, FrameKind::Object, 0, None);
+            } else {
+                let delimiter = self.document_delimiter();
+                self.check_value(line, value_start, value, delimiter);
+            }
+            return;
+        };
+
+        let span = line.content_span(offset, rest + 1 - offset);
+        if !value.is_empty() {
+            let cells = split_cells(value, header.delimiter);
+            for &(start, cell) in &cells {
+                self.check_value(line, value_start + start, cell, header.delimiter);
+            }
+            if !header.marked {
+                self.check_delimiter_mark(span, value, Some(header.length));
+            }
+            if cells.len() != header.length {
+                self.count_mismatch(
+                    span,
+                    header.length,
+                    cells.len(),
+                    value,
+                    header.delimiter,
+                    "inline array values",
+                );
+            }
+        } else if let Some(width) = header.width {
+            self.push(
+                array_depth,
+                FrameKind::Tabular {
+                    width,
+                    delimiter: header.delimiter,
+                },
+                header.length,
+                Some(span),
+            );
+        } else if header.length > 0 {
+            self.push(
+                array_depth,
+                FrameKind::List {
+                    delimiter: header.delimiter,
+                },
+                header.length,
+                Some(span),
+            );
+        }
+    }
+
+    /// Parse an array header starting at byte `offset` of the line content.
+    /// Returns the header and the offset just past it.
+    fn header(&mut self, line: &Line<'a>, offset: usize) -> Option<(Header, usize)> {
+        let content = &line.content[offset..];
+        let Some(close) = content.find(']') else {
+            self.error(
+                DiagnosticCode::Syntax,
+                "Expected ']'",
+                line.content_span(offset, content.len()),
+                "Close array length with ']'",
+            );
+            return None;
+        };
+
+        let marker = content[1..close].starts_with('#');
+        match self.length_marker {
+            Some((first, first_line)) if first != marker => self.lint(
+                Lint::InconsistentLengthMarker,
+                format!(
+                    "Header {} a '#' length marker but the first header, on line {}, {}",
+                    if marker { "uses" } else { "does not use" },
+                    first_line,
+                    if first { "does" } else { "does not" }
+                ),
+                line.content_span(offset, close + 1),
+                "Use the same length marker style in every header",
+            ),
+            Some(_) => {}
+            None => self.length_marker = Some((marker, line.number)),
+        }
+
+        let bracket = content[1..close].trim_start_matches('#');
+        let (digits, declared) = match bracket.chars().last() {
+            Some('|') => (&bracket[..bracket.len() - 1], Some(Delimiter::Pipe)),
+            Some('\t') => (&bracket[..bracket.len() - 1], Some(Delimiter::Tab)),
+            _ => (bracket, None),
+        };
+        let Ok(length) = digits.parse::<usize>() else {
+            self.error(
+                DiagnosticCode::Syntax,
+                format!("Invalid array length: {}", digits),
+                line.content_span(offset, close + 1),
+                "Array must have a length like [5] or #5",
+            );
+            return None;
+        };
+        let delimiter = self
+            .options
+            .delimiter
+            .or(declared)
+            .unwrap_or(Delimiter::Comma);
+
+        let mut end = offset + close + 1;
+        let mut width = None;
+        if line.content[end..].starts_with('{') {
+            let Some(close) = line.content[end..].find('}') else {
+                self.error(
+                    DiagnosticCode::Syntax,
+                    "Expected '}'",
+                    line.content_span(end, line.content.len() - end),
+                    "Close field list with '}'",
+                );
+                return None;
+            };
+            let fields = &line.content[end + 1..end + close];
+            if fields.trim().is_empty() {
+                self.error(
+                    DiagnosticCode::Syntax,
+                    "Field list cannot be empty for tabular arrays",
+                    line.content_span(end, close + 1),
+                    "Tabular arrays need field list like {id,name}",
+                );
+                return None;
+            }
+            for &(start, field) in &split_cells(fields, delimiter) {
+                if field.starts_with('"') {
+                    self.check_key(line, end + 1 + start, field);
+                }
+            }
+            if declared.is_none() {
+                self.check_delimiter_mark(
+                    line.content_span(offset, end + close + 1 - offset),
+                    fields,
+                    None,
+                );
+            }
+            width = Some(split_cells(fields, delimiter).len());
+            end += close + 1;
+        }
+
+        Some((
+            Header {
+                length,
+                delimiter,
+                marked: declared.is_some(),
+                width,
+            },
+            end,
+        ))
+    }
+
+    fn row(&mut self, line: &Line<'a>, width: usize, delimiter: Delimiter) {
+        if let Some(frame) = self.frames.last_mut() {
+            frame.count += 1;
+        }
+
+        let cells = split_cells(line.content, delimiter);
+        for &(start, cell) in &cells {
+            self.check_value(line, start, cell, delimiter);
+        }
+        if cells.len() != width {
+            let span = line.content_span(0, line.content.len());
+            self.count_mismatch(
+                span,
+                width,
+                cells.len(),
+                line.content,
+                delimiter,
+                "values in row",
+            );
+        }
+    }
+
+    fn item(&mut self, line: &Line<'a>, depth: usize, delimiter: Delimiter) {
+        if line.content != "-" && !line.content.starts_with("- ") {
+            self.error(
+                DiagnosticCode::Syntax,
+                "Expected '-' for list item",
+                line.content_span(0, line.content.len()),
+                "List arrays need '-' prefix for each item",
+            );
+            return;
+        }
+        if let Some(frame) = self.frames.last_mut() {
+            frame.count += 1;
+        }
+        if line.content == "-" {
+            return;
+        }
+
+        let offset = 2;
+        let rest = &line.content[offset..];
+        if rest.starts_with('[') {
+            self.field(line, offset, depth + 1, depth + 1);
+        } else if key_end(rest).is_some() {
+            self.push(depth + 1, FrameKind::Object, 0, None);
+            self.field(line, offset, depth + 2, depth + 1);
+        } else {
+            self.check_value(line, offset, rest, delimiter);
+        }
+    }
+
+    /// Report a key that the enclosing object already has, unless the
+    /// duplicate key policy allows it.
+    fn check_duplicate_key(&mut self, line: &Line<'a>, offset: usize, raw: &str) {
+        if self.lints.is_some()
+            || self.options.expand_paths
+            || self.options.duplicate_key_policy() != DuplicateKeys::Error
+        {
+            return;
+        }
+        let Some(frame) = self
+            .frames
+            .last_mut()
+            .filter(|frame| matches!(frame.kind, FrameKind::Object))
+        else {
+            return;
+        };
+
+        let key = match raw.strip_prefix('"').and_then(|k| k.strip_suffix('"')) {
+            Some(inner) => unescape_string(inner),
+            None => raw.to_string(),
+        };
+        let Some(&first) = frame.keys.get(&key) else {
+            frame.keys.insert(key, line.number);
+            return;
+        };
+        self.error(
+            DiagnosticCode::DuplicateKey,
+            format!("Duplicate key '{}' (first on line {})", key, first),
+            line.content_span(offset, raw.len()),
+            "Remove one of the keys, or set a DuplicateKeys policy",
+        );
+        if let Some(diagnostic) = self.diagnostics.last_mut() {
+            diagnostic.context.related_line = Some(first);
+        }
+    }
+
+    fn push(&mut self, depth: usize, kind: FrameKind, declared: usize, header: Option<Span>) {
+        self.frames.push(Frame {
+            depth,
+            kind,
+            declared,
+            header,
+            count: 0,
+            keys: HashMap::new(),
+        });
+    }
+
+    /// Report `found` values where `expected` were declared, as a delimiter
+    /// mismatch if splitting on another delimiter gives the expected count.
+    fn count_mismatch(
+        &mut self,
+        span: Span,
+        expected: usize,
+        found: usize,
+        values: &str,
+        delimiter: Delimiter,
+        what: &str,
+    ) {
+        let other = [Delimiter::Comma, Delimiter::Pipe, Delimiter::Tab]
+            .into_iter()
+            .find(|&d| d != delimiter && split_cells(values, d).len() == expected);
+
+        match other {
+            Some(other) if expected > 1 => self.strict(
+                DiagnosticCode::DelimiterMismatch,
+                format!(
+                    "Values are separated by {:?} but the header declares {:?}",
+                    other.as_char(),
+                    delimiter.as_char()
+                ),
+                span,
+                "Use the delimiter declared in the array header",
+            ),
+            _ => self.strict(
+                if what == "values in row" {
+                    DiagnosticCode::RowWidth
+                } else {
+                    DiagnosticCode::ArrayLength
+                },
+                format!("Expected {} {}, but got {}", expected, what, found),
+                span,
+                "Quote values that contain the delimiter",
+            ),
+        }
+    }
+
+    /// Check a primitive value starting at byte `offset` of the content,
+    /// where `delimiter` is active.
+    fn check_value(&mut self, line: &Line<'a>, offset: usize, value: &str, delimiter: Delimiter) {
+        let span = line.content_span(offset, value.len());
+        if !value.starts_with('"') {
+            let must_quote = |ch: char| {
+                matches!(ch, ':' | '"' | '\\' | '[' | ']' | '{' | '}') || ch == delimiter.as_char()
+            };
+            if !is_literal_like(value) && value.contains(must_quote) {
+                self.lint(
+                    Lint::UnquotedValue,
+                    format!("Value {} must be quoted", value),
+                    span,
+                    "Quote values that contain the delimiter, ':', '\"', '\\', brackets or braces",
+                );
+            }
+            return;
+        }
+
+        let Some(end) = self.check_quoted(line, offset, value) else {
+            return;
+        };
+        if !value[end..].trim().is_empty() {
+            self.error(
+                DiagnosticCode::Syntax,
+                "Unexpected characters after closing quote",
+                line.content_span(offset + end, value.len() - end),
+                "Quote the whole value",
+            );
+        } else if !needs_quoting(&unescape_string(&value[1..end - 1]), delimiter) {
+            self.lint(
+                Lint::UnnecessaryQuotes,
+                format!("Value {} does not need quotes", value),
+                span,
+                "Remove the quotes",
+            );
+        }
+    }
+
+    /// Check a quoted key or field name starting at byte `offset` of the
+    /// content.
+    fn check_key(&mut self, line: &Line<'a>, offset: usize, key: &str) {
+        let Some(end) = self.check_quoted(line, offset, key) else {
+            return;
+        };
+        // Quotes keep dotted keys from being expanded into paths.
+        let unquoted = unescape_string(&key[1..end - 1]);
+        if is_valid_unquoted_key(&unquoted) && !unquoted.contains('.') {
+            self.lint(
+                Lint::UnnecessaryQuotes,
+                format!("Key {} does not need quotes", key),
+                line.content_span(offset, end),
+                "Remove the quotes",
+            );
+        }
+    }
+
+    /// Report a header without a delimiter symbol whose `values` are
+    /// separated by a pipe or tab instead of commas. With a declared
+    /// `length`, only a split that matches it counts, so a single value
+    /// that contains a pipe is not reported.
+    fn check_delimiter_mark(&mut self, span: Span, values: &str, length: Option<usize>) {
+        let fits = |count: usize| length.map_or(count > 1, |length| count == length);
+        let commas = split_cells(values, Delimiter::Comma).len();
+        if commas > 1 || fits(commas) {
+            return;
+        }
+        let used = [Delimiter::Pipe, Delimiter::Tab]
+            .into_iter()
+            .find(|&d| fits(split_cells(values, d).len()));
+        if let Some(used) = used {
+            self.lint(
+                Lint::MissingDelimiterMark,
+                format!(
+                    "Header does not declare the {:?} delimiter its values use",
+                    used.as_char()
+                ),
+                span,
+                format!("Add {:?} before the ']' of the header", used.as_char()),
+            );
+        }
+    }
+
+    /// The delimiter for values outside any array.
+    fn document_delimiter(&self) -> Delimiter {
+        self.lints
+            .map(|lints| lints.delimiter)
+            .or(self.options.delimiter)
+            .unwrap_or(Delimiter::Comma)
+    }
+
+    /// Check the escapes of a quoted string starting at byte `offset` of the
+    /// content, and return the offset just past its closing quote.
+    fn check_quoted(&mut self, line: &Line<'a>, offset: usize, text: &str) -> Option<usize> {
+        let mut chars = text.char_indices().skip(1);
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '"' => return Some(i + 1),
+                '\\' => match chars.next() {
+                    Some((_, '\\' | '"' | 'n' | 'r' | 't')) => {}
+                    Some((_, escaped)) => self.strict(
+                        DiagnosticCode::InvalidEscape,
+                        format!("Invalid escape sequence: \\{}", escaped),
+                        line.content_span(offset + i, 1 + escaped.len_utf8()),
+                        "Valid escapes are \\\\, \\\", \\n, \\r and \\t",
+                    ),
+                    None => break,
+                },
+                _ => {}
+            }
+        }
+
+        self.error(
+            DiagnosticCode::UnterminatedString,
+            "Unterminated string: missing closing quote",
+            line.content_span(offset, text.len()),
+            "Add a closing '\"'",
+        );
+        None
+    }
+
+    fn error(
+        &mut self,
+        code: DiagnosticCode,
+        message: impl Into<String>,
+        span: Span,
+        suggestion: impl Into<String>,
+    ) {
+        if self.lints.is_none() {
+            self.report(
+                Severity::Error,
+                code,
+                message.into(),
+                span,
+                suggestion.into(),
+            );
+        }
+    }
+
+    /// Report a strict-mode violation: an error in strict mode, a warning
+    /// otherwise.
+    fn strict(
+        &mut self,
+        code: DiagnosticCode,
+        message: impl Into<String>,
+        span: Span,
+        suggestion: impl Into<String>,
+    ) {
+        if self.lints.is_some() {
+            return;
+        }
+        let severity = if self.options.strict {
+            Severity::Error
+        } else {
+            Severity::Warning
+        };
+        self.report(severity, code, message.into(), span, suggestion.into());
+    }
+
+    /// Report a style problem, if the lint is enabled.
+    fn lint(
+        &mut self,
+        lint: Lint,
+        message: impl Into<String>,
+        span: Span,
+        suggestion: impl Into<String>,
+    ) {
+        if self.lints.is_some_and(|lints| lints.is_enabled(lint)) {
+            self.report(
+                Severity::Warning,
+                DiagnosticCode::Lint(lint),
+                message.into(),
+                span,
+                suggestion.into(),
+            );
+        }
+    }
+
+    fn report(
+        &mut self,
+        severity: Severity,
+        code: DiagnosticCode,
+        message: String,
+        span: Span,
+        suggestion: String,
+    ) {
+        let context = ErrorContext::from_input(self.input, span.line, span.column, 1)
+            .unwrap_or_else(|| ErrorContext::new(""))
+            .with_suggestion(suggestion);
+        self.diagnostics.push(Diagnostic {
+            severity,
+            code,
+            message,
+            span,
+            context,
+        });
+    }
+}
+
+struct Header {
+    length: usize,
+    delimiter: Delimiter,
+    /// Whether the bracket declares the delimiter.
+    marked: bool,
+    /// Number of fields, for tabular arrays.
+    width: Option<usize>,
+}
+
+/// If `content` starts with a key followed by `:` or an array header, the
+/// byte offset just past the key.
+fn key_end(content: &str) -> Option<usize> {
+    let end = if content.starts_with('"') {
+        quoted_end(content)?
+    } else {
+        content.find([':', '[', '"'])?
+    };
+    matches!(content[end..].chars().next(), Some(':' | '[')).then_some(end)
+}
+
+/// Whether a line under a tabular array is a field rather than a row: its
+/// key ends before the first delimiter.
+fn is_field(content: &str, delimiter: Delimiter) -> bool {
+    let Some(end) = key_end(content) else {
+        return false;
+    };
+    match content.find(delimiter.as_char()) {
+        Some(delim) => end < delim || content[end..].starts_with('['),
+        None => true,
+    }
+}
+
+/// The byte offset just past the closing quote of the string that starts
+/// `text`.
+fn quoted_end(text: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, ch) in text.char_indices().skip(1) {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' => return Some(i + 1),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split delimited values, respecting quotes. Returns each value, trimmed,
+/// with its byte offset.
+fn split_cells(text: &str, delimiter: Delimiter) -> Vec<(usize, &str)> {
+    let delimiter = delimiter.as_char();
+    let mut cells = Vec::new();
+    let mut start = 0;
+    let mut in_quotes = false;
+    let mut escaped = false;
+
+    let mut push = |from: usize, to: usize| {
+        let cell = &text[from..to];
+        let trimmed = cell.trim_start_matches(' ');
+        let offset = from + cell.len() - trimmed.len();
+        cells.push((offset, trimmed.trim_end_matches(' ')));
+    };
+    for (i, ch) in text.char_indices() {
+        match ch {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            _ if ch == delimiter && !in_quotes => {
+                push(start, i);
+                start = i + ch.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    push(start, text.len());
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        encode_default,
+        EncodeOptions,
+    };
+
+    fn codes(input: &str) -> Vec<(usize, DiagnosticCode)> {
+        validate(input, &DecodeOptions::default())
+            .into_iter()
+            .map(|d| (d.span.line, d.code))
+            .collect()
+    }
+
+    #[test]
+    fn test_encoder_output_is_valid() {
+        let value = json!({
+            "name": "Ada",
+            "tags": ["a", "b,c", ""],
+            "users": [{"id": 1, "name": "Alice"}, {"id": 2, "name": "Bob \"B\""}],
+            "matrix": [[1, 2], [3]],
+            "mixed": [1, "x", [], ["a|b", "c\td"]],
+            "nested": {"deep": {"empty": {}, "list": []}}
+        });
+        for options in [
+            EncodeOptions::default(),
+            EncodeOptions::new().with_delimiter(Delimiter::Pipe),
+            EncodeOptions::new().with_delimiter(Delimiter::Tab),
+            EncodeOptions::new().with_length_marker('#'),
+        ] {
+            let toon = crate::encode(&value, &options).unwrap();
+            assert_eq!(
+                validate(&toon, &DecodeOptions::default()),
+                vec![],
+                "{}",
+                toon
+            );
+        }
+        assert_eq!(codes(&encode_default(&json!([1, 2])).unwrap()), vec![]);
+        assert_eq!(codes("hello world"), vec![]);
+    }
+
+    #[test]
+    fn test_reports_every_violation() {
+        let input = "a[3]: x,y\nrows[2]{id,name}:\n  1,Alice,extra\n\n  2,Bob\nlist[1]:\n  - x\n  \
+                     - y\n   b: 1\nc \"d\"\ne: \"bad \\q\"\nf: \"open";
+        assert_eq!(
+            codes(input),
+            vec![
+                (1, DiagnosticCode::ArrayLength),
+                (3, DiagnosticCode::RowWidth),
+                (4, DiagnosticCode::BlankLine),
+                (6, DiagnosticCode::ArrayLength),
+                (9, DiagnosticCode::Indentation),
+                (9, DiagnosticCode::Syntax),
+                (10, DiagnosticCode::MissingColon),
+                (11, DiagnosticCode::InvalidEscape),
+                (12, DiagnosticCode::UnterminatedString),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_delimiter_mismatch_and_tabs() {
+        let input = "rows[2|]{a|b}:\n  1,2\n\t2|3\nv[2]: 1|2";
+        assert_eq!(
+            codes(input),
+            vec![
+                (2, DiagnosticCode::DelimiterMismatch),
+                (3, DiagnosticCode::TabIndentation),
+                (4, DiagnosticCode::DelimiterMismatch),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let input = "a: 1\nb:\n  a: 2\n  \"a\": 3\nrows[1]:\n  - a: 1\n    a: 2\na: 4";
+        assert_eq!(
+            codes(input),
+            vec![
+                (4, DiagnosticCode::DuplicateKey),
+                (7, DiagnosticCode::DuplicateKey),
+                (8, DiagnosticCode::DuplicateKey),
+            ]
+        );
+        assert_eq!(
+            validate(input, &DecodeOptions::default())[2]
+                .context
+                .related_line,
+            Some(1)
+        );
+
+        let options = DecodeOptions::new().with_duplicate_keys(DuplicateKeys::LastWins);
+        assert_eq!(validate(input, &options), vec![]);
+        assert_eq!(
+            validate(input, &DecodeOptions::new().with_strict(false)),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn test_indent_option() {
+        let indent = |input, indent| {
+            validate(input, &DecodeOptions::new().with_indent(indent))
+                .into_iter()
+                .map(|d| (d.span.line, d.code))
+                .collect::<Vec<_>>()
+        };
+        let four = "a:\n    b:\n        c: 1\n    d: 2";
+        assert_eq!(indent(four, Indent::Spaces(4)), vec![]);
+        assert_eq!(indent(four, Indent::Auto), vec![]);
+
+        let mixed = "a:\n    b:\n        c: 1\n  d: 2";
+        assert_eq!(
+            indent(mixed, Indent::Spaces(4)),
+            vec![(4, DiagnosticCode::Indentation)]
+        );
+        assert_eq!(
+            indent(mixed, Indent::Auto),
+            vec![(4, DiagnosticCode::Indentation)]
+        );
+
+        let tabs = DecodeOptions::new().with_indent(Indent::Tabs);
+        assert_eq!(validate("a:\n\tb:\n\t\tc: 1\n\td: 2", &tabs), vec![]);
+        assert_eq!(
+            validate("a:\n\tb: 1\n  c: 2", &tabs)[0].code,
+            DiagnosticCode::Indentation
+        );
+    }
+
+    #[test]
+    fn test_list_item_objects() {
+        let input = "items[2]:\n  - id: 1\n    tags[2]: a,b\n    rows[1]{x}:\n      1\n    more: \
+                     2\n  - id: 2\n    nested:\n      deep: 1";
+        assert_eq!(codes(input), vec![]);
+    }
+
+    #[test]
+    fn test_span_and_context() {
+        let diagnostics = validate("a: 1\nb[2]: x", &DecodeOptions::default());
+        assert_eq!(diagnostics.len(), 1);
+
+        let diagnostic = &diagnostics[0];
+        assert_eq!(diagnostic.severity, Severity::Error);
+        assert_eq!(diagnostic.span, Span::new(2, 1, 6));
+        assert_eq!(diagnostic.context.source_line, "b[2]: x");
+        assert_eq!(diagnostic.context.preceding_lines, vec!["a: 1"]);
+        assert!(diagnostic.context.suggestion.is_some());
+        assert_eq!(
+            diagnostic.to_string(),
+            "error[array-length] at line 2, column 1: Expected 2 inline array values, but got 1"
+        );
+    }
+
+    #[test]
+    fn test_lenient_mode_downgrades_strict_checks() {
+        let options = DecodeOptions::new().with_strict(false);
+        let diagnostics = validate("a[2]: x\nb \"c\"", &options);
+        let severities: Vec<_> = diagnostics.iter().map(|d| d.severity).collect();
+        assert_eq!(severities, vec![Severity::Warning, Severity::Error]);
+
+        let empty = validate("\n\n", &options);
+        assert_eq!(empty[0].code, DiagnosticCode::EmptyInput);
+    }
+}
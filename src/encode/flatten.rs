@@ -0,0 +1,103 @@
+//! Flattened tabular form for arrays of objects with differing keys or
+//! nested objects.
+
+use indexmap::IndexMap;
+
+use crate::{
+    constants::ABSENT_MARKER,
+    encode::{
+        write_cell,
+        writer::{
+            Sink,
+            Writer,
+        },
+    },
+    error::ToonResult,
+    types::JsonValue as Value,
+    utils::is_valid_unquoted_key,
+};
+
+/// Work out the columns of a flattened table: the union of the rows' keys in
+/// first-seen order, with nested objects expanded into one column per key.
+/// Returns `None` if some row is not an object or holds a value that cannot
+/// be flattened. Only non-empty objects of primitives whose keys are valid
+/// path segments can be, and a key must hold an object in every row that
+/// has it or in none.
+pub(crate) fn flattened_columns(arr: &[Value]) -> Option<Vec<Vec<&str>>> {
+    let is_segment = |key: &str| is_valid_unquoted_key(key) && !key.contains('.');
+    let mut columns: IndexMap<&str, Option<Vec<&str>>> = IndexMap::new();
+
+    for item in arr {
+        for (key, value) in item.as_object()? {
+            let column = columns.entry(key.as_str());
+            match value {
+                Value::Array(_) => return None,
+                Value::Object(obj) => {
+                    let flat = obj
+                        .iter()
+                        .all(|(k, v)| is_segment(k) && !v.is_array() && !v.is_object());
+                    if obj.is_empty() || !is_segment(key) || !flat {
+                        return None;
+                    }
+                    let subkeys = column.or_insert_with(|| Some(Vec::new())).as_mut()?;
+                    for k in obj.keys() {
+                        if !subkeys.contains(&k.as_str()) {
+                            subkeys.push(k);
+                        }
+                    }
+                }
+                _ => {
+                    if column.or_insert(None).is_some() {
+                        return None;
+                    }
+                }
+            }
+        }
+    }
+
+    if columns.is_empty() {
+        return None;
+    }
+
+    Some(
+        columns
+            .into_iter()
+            .flat_map(|(key, subkeys)| match subkeys {
+                None => vec![vec![key]],
+                Some(subkeys) => subkeys.into_iter().map(|sub| vec![key, sub]).collect(),
+            })
+            .collect(),
+    )
+}
+
+/// Write `arr` as a flattened table with the given columns.
+pub(crate) fn encode_flattened_tabular<S: Sink>(
+    writer: &mut Writer<S>,
+    key: Option<&str>,
+    arr: &[Value],
+    columns: &[Vec<&str>],
+    depth: usize,
+) -> ToonResult<()> {
+    writer.write_flattened_header(key, arr.len(), columns)?;
+
+    for item in arr {
+        writer.write_newline()?;
+        writer.write_indent(depth + 1)?;
+
+        for (i, path) in columns.iter().enumerate() {
+            if i > 0 {
+                writer.write_delimiter()?;
+            }
+
+            let cell = path
+                .iter()
+                .try_fold(item, |value, key| value.as_object()?.get(*key));
+            match cell {
+                None => writer.write_str(ABSENT_MARKER)?,
+                Some(value) => write_cell(writer, value)?,
+            }
+        }
+    }
+
+    Ok(())
+}
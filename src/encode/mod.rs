@@ -1,19 +1,29 @@
+mod flatten;
 pub mod primitives;
+pub mod tabular;
 pub mod writer;
 use indexmap::IndexMap;
+pub use tabular::TabularWriter;
+use writer::Sink;
 
 use crate::{
-    constants::MAX_DEPTH,
+    constants::{
+        ABSENT_MARKER,
+        MAX_DEPTH,
+    },
     error::{
         ToonError,
         ToonResult,
     },
     types::{
+        Delimiter,
         EncodeOptions,
         IntoJsonValue,
         JsonValue as Value,
     },
     utils::{
+        is_valid_unquoted_key,
+        needs_quoting,
         normalize,
         validation::validate_depth,
     },
@@ -157,7 +167,7 @@ pub fn encode_array<V: IntoJsonValue>(value: V, options: &EncodeOptions) -> Toon
     encode_impl(&json_value, options)
 }
 
-fn value_type_name(value: &Value) -> &'static str {
+pub(crate) fn value_type_name(value: &Value) -> &'static str {
     match value {
         Value::Null => "null",
         Value::Bool(_) => "boolean",
@@ -168,8 +178,8 @@ fn value_type_name(value: &Value) -> &'static str {
     }
 }
 
-fn write_object(
-    writer: &mut writer::Writer,
+pub(crate) fn write_object<S: Sink>(
+    writer: &mut writer::Writer<S>,
     obj: &IndexMap<String, Value>,
     depth: usize,
 ) -> ToonResult<()> {
@@ -186,20 +196,30 @@ fn write_object(
             writer.write_indent(depth)?;
         }
 
-        let value = &obj[*key];
+        let mut value = &obj[*key];
+
+        let mut path = vec![key.as_str()];
+        if writer.options().key_folding {
+            value = fold_path(&mut path, value);
+        }
+        if path.len() > 1 {
+            writer.write_folded_key(&path)?;
+        } else {
+            writer.write_key(key)?;
+        }
 
         match value {
             Value::Array(arr) => {
-                write_array(writer, Some(key), arr, depth)?;
+                write_array(writer, None, arr, depth)?;
             }
             Value::Object(nested_obj) => {
-                writer.write_key(key)?;
                 writer.write_char(':')?;
-                writer.write_newline()?;
-                write_object(writer, nested_obj, depth + 1)?;
+                if !nested_obj.is_empty() {
+                    writer.write_newline()?;
+                    write_object(writer, nested_obj, depth + 1)?;
+                }
             }
             _ => {
-                writer.write_key(key)?;
                 writer.write_char(':')?;
                 writer.write_char(' ')?;
                 write_primitive_value(writer, value)?;
@@ -210,29 +230,90 @@ fn write_object(
     Ok(())
 }
 
-fn write_array(
-    writer: &mut writer::Writer,
+/// Extend `path` through objects that have exactly one key, returning the
+/// value at the end of the chain. Folding stops at keys that could not be
+/// written as an unquoted path segment.
+fn fold_path<'v>(path: &mut Vec<&'v str>, value: &'v Value) -> &'v Value {
+    let is_segment = |key: &str| is_valid_unquoted_key(key) && !key.contains('.');
+    if !is_segment(path[0]) {
+        return value;
+    }
+
+    let mut value = value;
+    while let Value::Object(obj) = value {
+        match obj.first() {
+            Some((key, inner)) if obj.len() == 1 && is_segment(key) => {
+                path.push(key);
+                value = inner;
+            }
+            _ => break,
+        }
+    }
+    value
+}
+
+pub(crate) fn write_array<S: Sink>(
+    writer: &mut writer::Writer<S>,
     key: Option<&str>,
     arr: &[Value],
     depth: usize,
 ) -> ToonResult<()> {
     validate_depth(depth, MAX_DEPTH)?;
 
-    if arr.is_empty() {
-        writer.write_empty_array_with_key(key)?;
-        return Ok(());
+    let outer = writer.delimiter();
+    if writer.options().auto_delimiter {
+        writer.set_delimiter(choose_delimiter(writer.options().delimiter, arr));
     }
 
-    // Choose encoding format: tabular > primitive inline > nested list
-    if let Some(keys) = is_tabular_array(arr) {
-        encode_tabular_array(writer, key, arr, &keys, depth)?;
+    let result = if arr.is_empty() {
+        writer.write_empty_array_with_key(key)
+    } else if let Some(keys) = is_tabular_array(arr) {
+        // Choose encoding format: tabular > primitive inline > nested list
+        encode_tabular_array(writer, key, arr, &keys, depth)
+    } else if let Some(columns) = writer
+        .options()
+        .flatten_tabular
+        .then(|| flatten::flattened_columns(arr))
+        .flatten()
+    {
+        flatten::encode_flattened_tabular(writer, key, arr, &columns, depth)
     } else if is_primitive_array(arr) {
-        encode_primitive_array(writer, key, arr, depth)?;
+        encode_primitive_array(writer, key, arr)
     } else {
-        encode_nested_array(writer, key, arr, depth)?;
-    }
+        encode_nested_array(writer, key, arr, depth)
+    };
 
-    Ok(())
+    writer.set_delimiter(outer);
+    result
+}
+
+/// Pick the delimiter that needs the fewest quoted values in the scope of
+/// `arr`: its primitive elements and the primitive fields of its objects.
+/// Nested arrays choose their own. Ties go to `preferred`.
+fn choose_delimiter(preferred: Delimiter, arr: &[Value]) -> Delimiter {
+    let strings: Vec<&str> = arr
+        .iter()
+        .flat_map(|value| match value {
+            Value::Object(obj) => obj.values().collect(),
+            other => vec![other],
+        })
+        .filter_map(|value| value.as_str())
+        .filter(|s| {
+            [Delimiter::Comma, Delimiter::Pipe, Delimiter::Tab]
+                .iter()
+                .any(|d| d.contains_in(s))
+        })
+        .collect();
+
+    [preferred, Delimiter::Comma, Delimiter::Pipe, Delimiter::Tab]
+        .into_iter()
+        .min_by_key(|&delimiter| {
+            strings
+                .iter()
+                .filter(|s| needs_quoting(s, delimiter))
+                .count()
+        })
+        .unwrap_or(preferred)
 }
 
 /// Check if an array can be encoded as tabular format (uniform objects with
@@ -290,13 +371,12 @@ fn is_primitive_array(arr: &[Value]) -> bool {
     arr.iter().all(is_primitive)
 }
 
-fn encode_primitive_array(
-    writer: &mut writer::Writer,
+fn encode_primitive_array<S: Sink>(
+    writer: &mut writer::Writer<S>,
     key: Option<&str>,
     arr: &[Value],
-    depth: usize,
 ) -> ToonResult<()> {
-    writer.write_array_header(key, arr.len(), None, depth)?;
+    writer.write_array_header(key, arr.len(), None)?;
     writer.write_char(' ')?;
 
     for (i, val) in arr.iter().enumerate() {
@@ -309,11 +389,17 @@ fn encode_primitive_array(
     Ok(())
 }
 
-fn write_primitive_value(writer: &mut writer::Writer, value: &Value) -> ToonResult<()> {
+pub(crate) fn write_primitive_value<S: Sink>(
+    writer: &mut writer::Writer<S>,
+    value: &Value,
+) -> ToonResult<()> {
     match value {
         Value::Null => writer.write_str("null"),
         Value::Bool(b) => writer.write_str(&b.to_string()),
-        Value::Number(n) => writer.write_str(&n.to_string()),
+        Value::Number(n) => {
+            let text = writer.options().format_number(n);
+            writer.write_str(&text)
+        }
         Value::String(s) => {
             if writer.needs_quoting(s) {
                 writer.write_quoted_string(s)
@@ -327,14 +413,25 @@ fn write_primitive_value(writer: &mut writer::Writer, value: &Value) -> ToonResu
     }
 }
 
-fn encode_tabular_array(
-    writer: &mut writer::Writer,
+/// Write one cell of a tabular row. With `flatten_tabular`, a string equal
+/// to [`ABSENT_MARKER`] is quoted so it is not read back as an absent field.
+pub(crate) fn write_cell<S: Sink>(writer: &mut writer::Writer<S>, value: &Value) -> ToonResult<()> {
+    match value {
+        Value::String(s) if writer.options().flatten_tabular && s == ABSENT_MARKER => {
+            writer.write_quoted_string(s)
+        }
+        _ => write_primitive_value(writer, value),
+    }
+}
+
+fn encode_tabular_array<S: Sink>(
+    writer: &mut writer::Writer<S>,
     key: Option<&str>,
     arr: &[Value],
     keys: &[String],
     depth: usize,
 ) -> ToonResult<()> {
-    writer.write_array_header(key, arr.len(), Some(keys), depth)?;
+    writer.write_array_header(key, arr.len(), Some(keys))?;
     writer.write_newline()?;
 
     for (row_index, obj_val) in arr.iter().enumerate() {
@@ -347,7 +444,7 @@ fn encode_tabular_array(
                 }
 
                 if let Some(val) = obj.get(key) {
-                    write_primitive_value(writer, val)?;
+                    write_cell(writer, val)?;
                 } else {
                     writer.write_str("null")?;
                 }
@@ -362,13 +459,13 @@ fn encode_tabular_array(
     Ok(())
 }
 
-fn encode_nested_array(
-    writer: &mut writer::Writer,
+fn encode_nested_array<S: Sink>(
+    writer: &mut writer::Writer<S>,
     key: Option<&str>,
     arr: &[Value],
     depth: usize,
 ) -> ToonResult<()> {
-    writer.write_array_header(key, arr.len(), None, depth)?;
+    writer.write_array_header(key, arr.len(), None)?;
     writer.write_newline()?;
 
     for (i, val) in arr.iter().enumerate() {
@@ -520,4 +617,33 @@ mod tests {
         assert!(result.contains("name: Alice"));
         assert!(result.contains("age: 30"));
     }
+
+    #[test]
+    fn test_encode_empty_nested_object() {
+        // An empty nested object is just its key, with no blank line after it.
+        let obj = json!({"a": {}});
+        assert_eq!(encode_default(&obj).unwrap(), "a:");
+        assert_eq!(crate::to_toon(&obj, None).unwrap(), "a:");
+        assert_eq!(crate::decode_default("a:").unwrap(), obj);
+
+        let obj = json!({"a": {}, "b": 1});
+        assert_eq!(encode_default(&obj).unwrap(), "a:\nb: 1");
+        assert_eq!(crate::to_toon(&obj, None).unwrap(), "a:\nb: 1");
+        assert_eq!(crate::decode_default("a:\nb: 1").unwrap(), obj);
+    }
+
+    #[test]
+    fn test_encode_nested_array_indentation() {
+        let obj = json!({
+            "a": {
+                "tags": ["x", "y"],
+                "rows": [{"id": 1}],
+                "none": []
+            }
+        });
+        assert_eq!(
+            encode_default(&obj).unwrap(),
+            "a:\n  tags[2]: x,y\n  rows[1]{id}:\n    1\n  none[0]:"
+        );
+    }
 }
@@ -0,0 +1,249 @@
+use serde::Serialize;
+
+use crate::{
+    decode::validation::validate_field_list,
+    encode::{
+        value_type_name,
+        write_cell,
+        writer::{
+            Sink,
+            Writer,
+        },
+    },
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    ser::to_value,
+    types::{
+        EncodeOptions,
+        JsonValue as Value,
+    },
+};
+
+/// Streams the rows of a tabular array one at a time.
+///
+/// The header (`key[N]{fields}:`) is written up front, so the number of rows
+/// must be known in advance. Each row is written as soon as it is passed to
+/// [`write_row`](Self::write_row), which makes it possible to export large
+/// result sets without holding them in memory. The output is identical to
+/// what [`encode`](crate::encode) produces for the same array.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     EncodeOptions,
+///     TabularWriter,
+/// };
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User<'a> {
+///     id: u32,
+///     name: &'a str,
+/// }
+///
+/// let mut rows = TabularWriter::new(
+///     String::new(),
+///     "users",
+///     &["id", "name"],
+///     2,
+///     EncodeOptions::default(),
+/// )?;
+/// rows.write_row(&User {
+///     id: 1,
+///     name: "Alice",
+/// })?;
+/// rows.write_row(&User { id: 2, name: "Bob" })?;
+///
+/// assert_eq!(rows.finish()?, "users[2]{id,name}:\n  1,Alice\n  2,Bob");
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub struct TabularWriter<S: Sink> {
+    writer: Writer<S>,
+    fields: Vec<String>,
+    expected_len: usize,
+    rows: usize,
+}
+
+impl<S: Sink> TabularWriter<S> {
+    /// Write the array header to `sink` and prepare to stream
+    /// `expected_len` rows.
+    pub fn new<F: AsRef<str>>(
+        sink: S,
+        key: &str,
+        fields: &[F],
+        expected_len: usize,
+        options: EncodeOptions,
+    ) -> ToonResult<Self> {
+        validate_field_list(fields)?;
+        let fields: Vec<String> = fields.iter().map(|f| f.as_ref().to_string()).collect();
+
+        let mut writer = Writer::with_sink(sink, options);
+        if expected_len == 0 {
+            writer.write_empty_array_with_key(Some(key))?;
+        } else {
+            writer.write_array_header(Some(key), expected_len, Some(&fields))?;
+        }
+
+        Ok(Self {
+            writer,
+            fields,
+            expected_len,
+            rows: 0,
+        })
+    }
+
+    /// Serialize a row and write it immediately.
+    ///
+    /// The row must serialize to an object whose values are primitives.
+    /// Fields missing from the row are written as `null`; fields that are
+    /// not in the header are an error.
+    pub fn write_row<T: ?Sized + Serialize>(&mut self, row: &T) -> ToonResult<()> {
+        if self.rows == self.expected_len {
+            return Err(ToonError::length_mismatch(self.expected_len, self.rows + 1));
+        }
+
+        let obj = match to_value(row)? {
+            Value::Object(obj) => obj,
+            other => return Err(ToonError::type_mismatch("object", value_type_name(&other))),
+        };
+
+        for (key, value) in &obj {
+            if !self.fields.contains(key) {
+                return Err(ToonError::SerializationError(format!(
+                    "Row {} has field '{}' which is not in the header",
+                    self.rows, key
+                )));
+            }
+            if matches!(value, Value::Array(_) | Value::Object(_)) {
+                return Err(ToonError::SerializationError(format!(
+                    "Row {} field '{}' is not a primitive value",
+                    self.rows, key
+                )));
+            }
+        }
+
+        self.writer.write_newline()?;
+        self.writer.write_indent(1)?;
+
+        for (i, field) in self.fields.iter().enumerate() {
+            if i > 0 {
+                self.writer.write_delimiter()?;
+            }
+
+            match obj.get(field) {
+                Some(value) => write_cell(&mut self.writer, value)?,
+                None => self.writer.write_str("null")?,
+            }
+        }
+
+        self.rows += 1;
+        Ok(())
+    }
+
+    /// Number of rows written so far.
+    pub fn rows_written(&self) -> usize {
+        self.rows
+    }
+
+    /// Check that the declared `[N]` matches the number of rows written and
+    /// return the sink.
+    pub fn finish(self) -> ToonResult<S> {
+        if self.rows != self.expected_len {
+            return Err(ToonError::length_mismatch(self.expected_len, self.rows));
+        }
+        Ok(self.writer.into_sink())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        encode,
+        encode::writer::IoSink,
+        types::Delimiter,
+    };
+
+    #[test]
+    fn test_matches_encode() {
+        let rows = vec![
+            json!({"id": 1, "name": "Alice", "note": "a,b"}),
+            json!({"id": 2, "name": "Bob", "note": null}),
+            json!({"id": 3, "name": "true", "note": "x|y"}),
+        ];
+
+        for options in [
+            EncodeOptions::default(),
+            EncodeOptions::new()
+                .with_delimiter(Delimiter::Pipe)
+                .with_length_marker('#'),
+        ] {
+            let mut tabular = TabularWriter::new(
+                String::new(),
+                "items",
+                &["id", "name", "note"],
+                rows.len(),
+                options.clone(),
+            )
+            .unwrap();
+            for row in &rows {
+                tabular.write_row(row).unwrap();
+            }
+
+            let expected = encode(&json!({ "items": rows }), &options).unwrap();
+            assert_eq!(tabular.finish().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn test_length_is_checked() {
+        let mut tabular =
+            TabularWriter::new(String::new(), "a", &["x"], 2, EncodeOptions::default()).unwrap();
+        tabular.write_row(&json!({"x": 1})).unwrap();
+        assert_eq!(
+            tabular.finish().unwrap_err(),
+            ToonError::length_mismatch(2, 1)
+        );
+
+        let mut tabular =
+            TabularWriter::new(String::new(), "a", &["x"], 1, EncodeOptions::default()).unwrap();
+        tabular.write_row(&json!({"x": 1})).unwrap();
+        assert!(tabular.write_row(&json!({"x": 2})).is_err());
+        assert_eq!(tabular.rows_written(), 1);
+
+        let tabular =
+            TabularWriter::new(String::new(), "a", &["x"], 0, EncodeOptions::default()).unwrap();
+        assert_eq!(tabular.finish().unwrap(), "a[0]:");
+    }
+
+    #[test]
+    fn test_invalid_rows() {
+        let mut tabular =
+            TabularWriter::new(String::new(), "a", &["x"], 3, EncodeOptions::default()).unwrap();
+        assert!(tabular.write_row(&json!([1])).is_err());
+        assert!(tabular.write_row(&json!({"x": [1]})).is_err());
+        assert!(tabular.write_row(&json!({"x": 1, "y": 2})).is_err());
+        tabular.write_row(&json!({"x": 1})).unwrap();
+        assert_eq!(tabular.writer.into_sink(), "a[3]{x}:\n  1");
+
+        assert!(
+            TabularWriter::new(String::new(), "a", &["x", "x"], 1, Default::default()).is_err()
+        );
+    }
+
+    #[test]
+    fn test_io_sink() {
+        let sink = IoSink::new(Vec::new());
+        let mut tabular =
+            TabularWriter::new(sink, "a", &["x", "y"], 1, EncodeOptions::default()).unwrap();
+        tabular.write_row(&json!({"y": "b"})).unwrap();
+
+        let bytes = tabular.finish().unwrap().into_inner();
+        assert_eq!(bytes, b"a[1]{x,y}:\n  null,b");
+    }
+}
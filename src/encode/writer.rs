@@ -1,3 +1,5 @@
+use std::io;
+
 use crate::{
     error::ToonResult,
     types::{
@@ -11,87 +13,164 @@ use crate::{
     },
 };
 
-/// Writer that builds TOON output string from JSON values.
-pub struct Writer {
-    buffer: String,
+/// Destination for encoded TOON text.
+pub trait Sink {
+    /// Append a string to the output.
+    fn write_str(&mut self, s: &str) -> ToonResult<()>;
+
+    /// Append a single character to the output.
+    fn write_char(&mut self, ch: char) -> ToonResult<()> {
+        self.write_str(ch.encode_utf8(&mut [0; 4]))
+    }
+}
+
+impl Sink for String {
+    fn write_str(&mut self, s: &str) -> ToonResult<()> {
+        self.push_str(s);
+        Ok(())
+    }
+
+    fn write_char(&mut self, ch: char) -> ToonResult<()> {
+        self.push(ch);
+        Ok(())
+    }
+}
+
+/// Sink that forwards output to an [`io::Write`].
+///
+/// Output is written in many small pieces, so wrap unbuffered destinations
+/// such as files and sockets in an [`io::BufWriter`].
+pub struct IoSink<W> {
+    inner: W,
+}
+
+impl<W: io::Write> IoSink<W> {
+    /// Create a sink that writes to the given destination.
+    pub fn new(inner: W) -> Self {
+        Self { inner }
+    }
+
+    /// Return the underlying writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: io::Write> Sink for IoSink<W> {
+    fn write_str(&mut self, s: &str) -> ToonResult<()> {
+        self.inner.write_all(s.as_bytes())?;
+        Ok(())
+    }
+}
+
+/// Writer that emits TOON output from JSON values into a [`Sink`].
+pub struct Writer<S = String> {
+    sink: S,
     options: EncodeOptions,
+    /// Delimiter of the array currently being written.
+    delimiter: Delimiter,
 }
 
 impl Writer {
     /// Create a new writer with the given options.
     pub fn new(options: EncodeOptions) -> Self {
+        Self::with_sink(String::new(), options)
+    }
+
+    /// Finish writing and return the complete TOON string.
+    pub fn finish(self) -> String {
+        self.sink
+    }
+}
+
+impl<S: Sink> Writer<S> {
+    /// Create a new writer that emits into the given sink.
+    pub fn with_sink(sink: S, options: EncodeOptions) -> Self {
+        let delimiter = options.delimiter;
         Self {
-            buffer: String::new(),
+            sink,
             options,
+            delimiter,
         }
     }
 
-    /// Finish writing and return the complete TOON string.
-    pub fn finish(self) -> String {
-        self.buffer
+    /// Finish writing and return the sink.
+    pub fn into_sink(self) -> S {
+        self.sink
+    }
+
+    pub fn options(&self) -> &EncodeOptions {
+        &self.options
+    }
+
+    /// The active delimiter, used for array headers, separators and quoting.
+    pub fn delimiter(&self) -> Delimiter {
+        self.delimiter
+    }
+
+    /// Switch the active delimiter, e.g. for the scope of a single array.
+    pub fn set_delimiter(&mut self, delimiter: Delimiter) {
+        self.delimiter = delimiter;
     }
 
     pub fn write_str(&mut self, s: &str) -> ToonResult<()> {
-        self.buffer.push_str(s);
-        Ok(())
+        self.sink.write_str(s)
     }
 
     pub fn write_char(&mut self, ch: char) -> ToonResult<()> {
-        self.buffer.push(ch);
-        Ok(())
+        self.sink.write_char(ch)
     }
 
     pub fn write_newline(&mut self) -> ToonResult<()> {
-        self.buffer.push('\n');
-        Ok(())
+        self.sink.write_char('\n')
     }
 
     pub fn write_indent(&mut self, depth: usize) -> ToonResult<()> {
         let indent_string = self.options.indent.get_string(depth);
         if !indent_string.is_empty() {
-            self.buffer.push_str(&indent_string);
+            self.sink.write_str(&indent_string)?;
         }
         Ok(())
     }
 
     pub fn write_delimiter(&mut self) -> ToonResult<()> {
-        self.buffer.push(self.options.delimiter.as_char());
-        Ok(())
+        self.sink.write_char(self.delimiter.as_char())
     }
 
     pub fn write_key(&mut self, key: &str) -> ToonResult<()> {
-        if is_valid_unquoted_key(key) {
+        // With key folding or flattened tables, an unquoted dot separates
+        // path segments.
+        let dots_are_paths = self.options.key_folding || self.options.flatten_tabular;
+        if is_valid_unquoted_key(key) && !(dots_are_paths && key.contains('.')) {
             self.write_str(key)
         } else {
             self.write_quoted_string(key)
         }
     }
 
+    /// Write a folded key such as `a.b.c`. Every segment must be a valid
+    /// unquoted key without dots.
+    pub fn write_folded_key(&mut self, segments: &[&str]) -> ToonResult<()> {
+        for (i, segment) in segments.iter().enumerate() {
+            if i > 0 {
+                self.write_char('.')?;
+            }
+            self.write_str(segment)?;
+        }
+        Ok(())
+    }
+
     /// Write an array header with key, length, and optional field list.
+    ///
+    /// Like other keys, the header is written at the current position; the
+    /// caller is responsible for indentation.
     pub fn write_array_header(
         &mut self,
         key: Option<&str>,
         length: usize,
         fields: Option<&[String]>,
-        depth: usize,
     ) -> ToonResult<()> {
-        if let Some(k) = key {
-            if depth > 0 {
-                self.write_indent(depth)?;
-            }
-            self.write_key(k)?;
-        }
-
-        self.write_char('[')?;
-
-        let length_str = self.options.format_length(length);
-        self.write_str(&length_str)?;
-
-        if self.options.delimiter != Delimiter::Comma {
-            self.write_delimiter()?;
-        }
-
-        self.write_char(']')?;
+        self.write_array_length(key, length)?;
 
         if let Some(field_list) = fields {
             self.write_char('{')?;
@@ -107,26 +186,57 @@ impl Writer {
         self.write_char(':')
     }
 
-    /// Write an empty array header.
-    pub fn write_empty_array_with_key(&mut self, key: Option<&str>) -> ToonResult<()> {
+    /// Write the header of a flattened tabular array, whose columns are
+    /// either a single key or a path like `["address", "city"]`.
+    pub fn write_flattened_header(
+        &mut self,
+        key: Option<&str>,
+        length: usize,
+        columns: &[Vec<&str>],
+    ) -> ToonResult<()> {
+        self.write_array_length(key, length)?;
+
+        self.write_char('{')?;
+        for (i, column) in columns.iter().enumerate() {
+            if i > 0 {
+                self.write_delimiter()?;
+            }
+            match column.as_slice() {
+                [field] => self.write_key(field)?,
+                path => self.write_folded_key(path)?,
+            }
+        }
+        self.write_char('}')?;
+
+        self.write_char(':')
+    }
+
+    /// Write `key[N]`, including the delimiter symbol if it is not a comma.
+    fn write_array_length(&mut self, key: Option<&str>, length: usize) -> ToonResult<()> {
         if let Some(k) = key {
             self.write_key(k)?;
         }
+
         self.write_char('[')?;
 
-        let length_str = self.options.format_length(0);
+        let length_str = self.options.format_length(length);
         self.write_str(&length_str)?;
 
-        if self.options.delimiter != Delimiter::Comma {
+        if self.delimiter != Delimiter::Comma {
             self.write_delimiter()?;
         }
 
-        self.write_char(']')?;
+        self.write_char(']')
+    }
+
+    /// Write an empty array header.
+    pub fn write_empty_array_with_key(&mut self, key: Option<&str>) -> ToonResult<()> {
+        self.write_array_length(key, 0)?;
         self.write_char(':')
     }
 
     pub fn needs_quoting(&self, s: &str) -> bool {
-        needs_quoting(s, self.options.delimiter)
+        needs_quoting(s, self.delimiter)
     }
 
     pub fn write_quoted_string(&mut self, s: &str) -> ToonResult<()> {
@@ -203,9 +313,7 @@ mod tests {
         let opts = EncodeOptions::default();
         let mut writer = Writer::new(opts);
 
-        writer
-            .write_array_header(Some("items"), 3, None, 0)
-            .unwrap();
+        writer.write_array_header(Some("items"), 3, None).unwrap();
         assert_eq!(writer.finish(), "items[3]:");
 
         let opts = EncodeOptions::default();
@@ -213,7 +321,7 @@ mod tests {
         let fields = vec!["id".to_string(), "name".to_string()];
 
         writer
-            .write_array_header(Some("users"), 2, Some(&fields), 0)
+            .write_array_header(Some("users"), 2, Some(&fields))
             .unwrap();
         assert_eq!(writer.finish(), "users[2]{id,name}:");
     }
@@ -223,9 +331,7 @@ mod tests {
         let opts = EncodeOptions::new().with_length_marker('#');
         let mut writer = Writer::new(opts);
 
-        writer
-            .write_array_header(Some("items"), 3, None, 0)
-            .unwrap();
+        writer.write_array_header(Some("items"), 3, None).unwrap();
         assert_eq!(writer.finish(), "items[#3]:");
     }
 
@@ -234,9 +340,7 @@ mod tests {
         let opts = EncodeOptions::new().with_delimiter(Delimiter::Pipe);
         let mut writer = Writer::new(opts);
 
-        writer
-            .write_array_header(Some("items"), 3, None, 0)
-            .unwrap();
+        writer.write_array_header(Some("items"), 3, None).unwrap();
         assert_eq!(writer.finish(), "items[3|]:");
 
         let opts = EncodeOptions::new().with_delimiter(Delimiter::Pipe);
@@ -244,7 +348,7 @@ mod tests {
         let fields = vec!["id".to_string(), "name".to_string()];
 
         writer
-            .write_array_header(Some("users"), 2, Some(&fields), 0)
+            .write_array_header(Some("users"), 2, Some(&fields))
             .unwrap();
         assert_eq!(writer.finish(), "users[2|]{id|name}:");
     }
@@ -294,6 +398,19 @@ mod tests {
         assert!(writer.needs_quoting("hello:world"));
     }
 
+    #[test]
+    fn test_write_to_io_sink() {
+        let mut writer = Writer::with_sink(IoSink::new(Vec::new()), EncodeOptions::default());
+
+        writer.write_key("name").unwrap();
+        writer.write_char(':').unwrap();
+        writer.write_char(' ').unwrap();
+        writer.write_value("Ünïcode").unwrap();
+
+        let bytes = writer.into_sink().into_inner();
+        assert_eq!(String::from_utf8(bytes).unwrap(), "name: Ünïcode");
+    }
+
     #[test]
     fn test_write_empty_array() {
         let opts = EncodeOptions::default();
@@ -46,6 +46,46 @@ pub enum ToonError {
 
     #[error("Deserialization error: {0}")]
     DeserializationError(String),
+
+    #[error("I/O error: {message}")]
+    Io {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
+
+    #[error("Limit exceeded at line {line}: {limit} of {found} is over the maximum of {max}")]
+    LimitExceeded {
+        limit: Limit,
+        max: usize,
+        found: usize,
+        line: usize,
+        /// Boxed so that this variant is no larger than `ParseError`.
+        #[source]
+        context: Option<Box<ErrorContext>>,
+    },
+}
+
+/// A resource limit set in [`Limits`](crate::types::Limits).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    Depth,
+    DocumentBytes,
+    StringLength,
+    ArrayLength,
+    ObjectKeys,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Limit::Depth => "nesting depth",
+            Limit::DocumentBytes => "document size in bytes",
+            Limit::StringLength => "string length in bytes",
+            Limit::ArrayLength => "declared array length",
+            Limit::ObjectKeys => "object key count",
+        };
+        f.write_str(name)
+    }
 }
 
 /// Contextual information for error reporting, including source location
@@ -57,6 +97,9 @@ pub struct ErrorContext {
     pub following_lines: Vec<String>,
     pub suggestion: Option<String>,
     pub indicator: Option<String>,
+    /// Another line involved in the error, such as the first use of a
+    /// duplicate key.
+    pub related_line: Option<usize>,
 }
 
 impl std::fmt::Display for ErrorContext {
@@ -77,6 +120,10 @@ impl std::fmt::Display for ErrorContext {
             writeln!(f, "  {}", line)?;
         }
 
+        if let Some(line) = self.related_line {
+            writeln!(f, "\nSee also line {}", line)?;
+        }
+
         if let Some(suggestion) = &self.suggestion {
             writeln!(f, "\nSuggestion: {}", suggestion)?;
         }
@@ -96,6 +143,7 @@ impl ErrorContext {
             following_lines: Vec::new(),
             suggestion: None,
             indicator: None,
+            related_line: None,
         }
     }
 
@@ -117,6 +165,12 @@ impl ErrorContext {
         self
     }
 
+    /// Add another line involved in the error.
+    pub fn with_related_line(mut self, line: usize) -> Self {
+        self.related_line = Some(line);
+        self
+    }
+
     /// Add a column indicator (caret) pointing to the error position.
     pub fn with_indicator(mut self, column: usize) -> Self {
         let indicator = format!("{}^", " ".repeat(column));
@@ -160,6 +214,7 @@ impl ErrorContext {
             following_lines,
             suggestion: None,
             indicator: Some(format!("{}^", " ".repeat(column.saturating_sub(1)))),
+            related_line: None,
         })
     }
 }
@@ -225,6 +280,23 @@ impl ToonError {
         }
     }
 
+    /// Create an error for a value over one of the decoding limits.
+    pub fn limit_exceeded(
+        limit: Limit,
+        max: usize,
+        found: usize,
+        line: usize,
+        context: Option<ErrorContext>,
+    ) -> Self {
+        ToonError::LimitExceeded {
+            limit,
+            max,
+            found,
+            line,
+            context: context.map(Box::new),
+        }
+    }
+
     /// Add context to an error if it supports it.
     pub fn with_context(self, context: ErrorContext) -> Self {
         match self {
@@ -246,10 +318,34 @@ impl ToonError {
                 found,
                 context: Some(context),
             },
+            ToonError::LimitExceeded {
+                limit,
+                max,
+                found,
+                line,
+                ..
+            } => ToonError::LimitExceeded {
+                limit,
+                max,
+                found,
+                line,
+                context: Some(Box::new(context)),
+            },
             other => other,
         }
     }
 
+    /// The context attached to this error, if any.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            ToonError::ParseError { context, .. } | ToonError::LengthMismatch { context, .. } => {
+                context.as_ref()
+            }
+            ToonError::LimitExceeded { context, .. } => context.as_deref(),
+            _ => None,
+        }
+    }
+
     /// Add a suggestion to help fix the error.
     pub fn with_suggestion(self, suggestion: impl Into<String>) -> Self {
         let suggestion = suggestion.into();
@@ -275,6 +371,27 @@ impl ToonError {
     }
 }
 
+impl From<std::io::Error> for ToonError {
+    fn from(err: std::io::Error) -> Self {
+        ToonError::Io {
+            kind: err.kind(),
+            message: err.to_string(),
+        }
+    }
+}
+
+impl serde::ser::Error for ToonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ToonError::SerializationError(msg.to_string())
+    }
+}
+
+impl serde::de::Error for ToonError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        ToonError::DeserializationError(msg.to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -364,4 +481,14 @@ mod tests {
             _ => panic!("Wrong error type"),
         }
     }
+
+    #[test]
+    fn test_limit_exceeded_is_not_the_largest_variant() {
+        let err = ToonError::limit_exceeded(Limit::Depth, 1, 2, 3, Some(ErrorContext::new("a:")));
+        assert_eq!(err.context().unwrap().source_line, "a:");
+        assert_eq!(
+            std::mem::size_of::<ToonError>(),
+            std::mem::size_of::<(usize, usize, String, Option<ErrorContext>)>()
+        );
+    }
 }
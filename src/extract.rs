@@ -0,0 +1,280 @@
+//! Finding TOON inside markdown and chat responses.
+
+use std::ops::Range;
+
+use serde_json::Value;
+
+use crate::{
+    decode::decode,
+    error::ToonResult,
+    types::DecodeOptions,
+    utils::is_valid_unquoted_key,
+};
+
+/// A region of TOON found by [`blocks`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractedBlock<'a> {
+    /// The TOON source, without any surrounding fence.
+    pub text: &'a str,
+    /// Byte offsets of `text` within the searched input.
+    pub range: Range<usize>,
+    /// Whether the block was inside a markdown code fence.
+    pub fenced: bool,
+}
+
+impl ExtractedBlock<'_> {
+    /// Decode the block.
+    pub fn decode(&self, options: &DecodeOptions) -> ToonResult<Value> {
+        decode(self.text, options)
+    }
+}
+
+/// Find the TOON blocks in `text`, in order.
+///
+/// Code fences labeled `toon`, `text/toon` or a `.toon` file name are always
+/// returned. Unlabeled fences are returned if they start with a key and
+/// decode. Runs of unfenced lines that start with a key are returned if they
+/// decode and look like more than a line of prose: at least two lines, or an
+/// array header. Fences labeled with another language are skipped.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     extract,
+///     DecodeOptions,
+/// };
+/// use serde_json::json;
+///
+/// let response = "Here are the users:\n\n```toon\nusers[2]{id,name}:\n  1,Alice\n  2,Bob\n```\n\nAnything else?";
+/// let blocks: Vec<_> = extract::blocks(response).collect();
+///
+/// assert_eq!(blocks.len(), 1);
+/// assert_eq!(&response[blocks[0].range.clone()], blocks[0].text);
+/// assert_eq!(
+///     blocks[0].decode(&DecodeOptions::default())?,
+///     json!({"users": [{"id": 1, "name": "Alice"}, {"id": 2, "name": "Bob"}]})
+/// );
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn blocks(text: &str) -> Blocks<'_> {
+    Blocks { text, pos: 0 }
+}
+
+/// Iterator over the TOON blocks in a text, created by [`blocks`].
+pub struct Blocks<'a> {
+    text: &'a str,
+    pos: usize,
+}
+
+impl<'a> Blocks<'a> {
+    /// The line starting at `start`, without its line ending, and the offset
+    /// of the next line.
+    fn line_at(&self, start: usize) -> (&'a str, usize) {
+        let rest = &self.text[start..];
+        let (line, next) = match rest.find('\n') {
+            Some(i) => (&rest[..i], start + i + 1),
+            None => (rest, self.text.len()),
+        };
+        (line.strip_suffix('\r').unwrap_or(line), next)
+    }
+
+    fn block(&self, range: Range<usize>, fenced: bool) -> ExtractedBlock<'a> {
+        ExtractedBlock {
+            text: &self.text[range.clone()],
+            range,
+            fenced,
+        }
+    }
+
+    /// Read a fenced block whose opening line ends at `content_start`.
+    /// Returns the content range and the offset after the closing fence.
+    fn fenced(&self, fence: &str, content_start: usize) -> (Range<usize>, usize) {
+        let mut pos = content_start;
+        let mut content_end = content_start;
+        while pos < self.text.len() {
+            let (line, next) = self.line_at(pos);
+            if line.trim_start().starts_with(fence)
+                && line.trim().chars().all(|c| c == fence_char(fence))
+            {
+                return (content_start..content_end, next);
+            }
+            content_end = pos + line.len();
+            pos = next;
+        }
+        (content_start..self.text.len(), self.text.len())
+    }
+
+    /// Read a run of unfenced TOON lines starting at `start`. Returns the
+    /// range, the number of lines and whether it contains an array header.
+    fn bare(&self, start: usize) -> (Range<usize>, usize, bool) {
+        let mut pos = start;
+        let mut end = start;
+        let mut lines = 0;
+        let mut has_array = false;
+        while pos < self.text.len() {
+            let (line, next) = self.line_at(pos);
+            let indented = line.starts_with([' ', '\t']) && !line.trim().is_empty();
+            if !indented && !is_key_line(line) {
+                break;
+            }
+            has_array |= line.contains('[');
+            lines += 1;
+            end = pos + line.len();
+            pos = next;
+        }
+        (start..end, lines, has_array)
+    }
+}
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = ExtractedBlock<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pos < self.text.len() {
+            let (line, next) = self.line_at(self.pos);
+            let trimmed = line.trim_start();
+
+            if let Some(fence) = ["```", "~~~"].into_iter().find(|f| trimmed.starts_with(f)) {
+                let fence_len = trimmed
+                    .chars()
+                    .take_while(|&c| c == fence_char(fence))
+                    .count();
+                let fence = &trimmed[..fence_len];
+                let info = trimmed[fence_len..].trim();
+
+                let (range, after) = self.fenced(fence, next);
+                self.pos = after;
+                let block = self.block(range, true);
+                let unlabeled = info.is_empty()
+                    && block.text.lines().next().is_some_and(is_key_line)
+                    && looks_like_toon(block.text, 1, true);
+                if is_toon_info(info) || unlabeled {
+                    return Some(block);
+                }
+                continue;
+            }
+
+            if is_key_line(line) {
+                let (range, lines, has_array) = self.bare(self.pos);
+                self.pos = range.end;
+                let block = self.block(range, false);
+                if looks_like_toon(block.text, lines, has_array) {
+                    return Some(block);
+                }
+                continue;
+            }
+
+            self.pos = next;
+        }
+        None
+    }
+}
+
+fn fence_char(fence: &str) -> char {
+    fence.chars().next().unwrap_or('`')
+}
+
+/// Whether a fence info string names TOON (SPEC §18).
+fn is_toon_info(info: &str) -> bool {
+    let name = info
+        .split_whitespace()
+        .next()
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    name == "toon" || name == "text/toon" || name.ends_with(".toon")
+}
+
+fn looks_like_toon(text: &str, lines: usize, has_array: bool) -> bool {
+    (lines >= 2 || has_array) && decode(text, &DecodeOptions::default()).is_ok()
+}
+
+/// Whether a line starts a field at the root: `key:`, `key: value`,
+/// `key[N]...:` or `[N]...:`.
+fn is_key_line(line: &str) -> bool {
+    let rest = if let Some(quoted) = line.strip_prefix('"') {
+        match quoted.find('"') {
+            Some(end) => &quoted[end + 1..],
+            None => return false,
+        }
+    } else {
+        let key_len = line
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.'))
+            .unwrap_or(line.len());
+        if key_len > 0 && !is_valid_unquoted_key(&line[..key_len]) {
+            return false;
+        }
+        &line[key_len..]
+    };
+
+    let rest = match rest.strip_prefix('[') {
+        Some(header) => match header.find(':') {
+            Some(colon) if header[..colon].contains(']') => &header[colon..],
+            _ => return false,
+        },
+        None if rest.len() == line.len() => return false,
+        None => rest,
+    };
+
+    rest == ":" || rest.starts_with(": ")
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_fenced_blocks() {
+        let text = "Intro\n```json\n{\"a\": 1}\n```\n```text/toon\na: 1\n```\n~~~ \
+                    users.toon\nb[1]: x\n~~~\n```toon\nc: 2";
+        let found: Vec<_> = blocks(text).collect();
+        assert_eq!(found.len(), 3);
+
+        assert_eq!(found[0].text, "a: 1");
+        assert!(found[0].fenced);
+        assert_eq!(&text[found[0].range.clone()], "a: 1");
+        assert_eq!(found[1].text, "b[1]: x");
+        // An unterminated fence runs to the end of the text.
+        assert_eq!(found[2].text, "c: 2");
+    }
+
+    #[test]
+    fn test_bare_blocks() {
+        let text = "Sure! Note: this is prose.\n\ntags[2]: a,b\n\nSummary: done\n\nname: \
+                    Alice\nrole:\n  admin: true\nThanks.";
+        let found: Vec<_> = blocks(text).collect();
+        assert_eq!(found.len(), 2);
+
+        assert_eq!(found[0].text, "tags[2]: a,b");
+        assert!(!found[0].fenced);
+        assert_eq!(found[1].text, "name: Alice\nrole:\n  admin: true");
+        assert_eq!(
+            found[1].decode(&DecodeOptions::default()).unwrap(),
+            json!({"name": "Alice", "role": {"admin": true}})
+        );
+        assert_eq!(found[1].range.start, text.find("name:").unwrap());
+    }
+
+    #[test]
+    fn test_unlabeled_fence() {
+        let text = "```\nid: 1\nok: true\n```\n```\nplain text\n```";
+        let found: Vec<_> = blocks(text).collect();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].text, "id: 1\nok: true");
+    }
+
+    #[test]
+    fn test_is_key_line() {
+        assert!(is_key_line("a: 1"));
+        assert!(is_key_line("a:"));
+        assert!(is_key_line("users[2]{id,name}:"));
+        assert!(is_key_line("[3]: a,b,c"));
+        assert!(is_key_line("\"my key\": 1"));
+        assert!(!is_key_line("Hello there"));
+        assert!(!is_key_line("  a: 1"));
+        assert!(!is_key_line("http://example.com"));
+        assert!(!is_key_line("- item"));
+    }
+}
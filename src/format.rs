@@ -0,0 +1,134 @@
+//! Canonical formatting of TOON documents.
+
+use crate::{
+    decode::parser::Parser,
+    encode::encode,
+    error::ToonResult,
+    types::{
+        DecodeOptions,
+        FormatOptions,
+    },
+};
+
+/// Rewrite a TOON document in canonical form.
+///
+/// The document is re-indented, strings are quoted only where
+/// [`needs_quoting`](crate::needs_quoting) requires it, every array is
+/// written with the chosen delimiter, and `[N]` headers are rewritten to
+/// match the number of elements actually present. The result decodes to the
+/// same value as the input.
+///
+/// Trailing content that cannot be placed in the document is an error rather
+/// than being dropped.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     format,
+///     Delimiter,
+///     FormatOptions,
+/// };
+///
+/// let input = "tags[3]: \"a\",b\nusers[1]{id,name}:\n  1,Alice\n  2,Bob";
+/// let options = FormatOptions::new().with_delimiter(Delimiter::Pipe);
+/// assert_eq!(
+///     format(input, &options)?,
+///     "tags[2|]: a|b\nusers[2|]{id|name}:\n  1|Alice\n  2|Bob"
+/// );
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn format(input: &str, options: &FormatOptions) -> ToonResult<String> {
+    let mut parser = Parser::new(input, DecodeOptions::default()).count_lengths();
+    let value = parser.parse()?;
+    parser.finish()?;
+    encode(&value, &options.encode_options())
+}
+
+/// Check whether a document is already in canonical form.
+///
+/// A single trailing newline is allowed.
+pub fn is_formatted(input: &str, options: &FormatOptions) -> ToonResult<bool> {
+    let formatted = format(input, options)?;
+    Ok(input.strip_suffix('\n').unwrap_or(input) == formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        decode_default,
+        types::{
+            Delimiter,
+            Indent,
+        },
+    };
+
+    #[test]
+    fn test_format_reindents_and_quotes() {
+        let input = "a:\n    b: \"plain\"\n    c[2]: \"x\",\"needs, quotes\"\nd: \"123\"";
+        assert_eq!(
+            format(input, &FormatOptions::default()).unwrap(),
+            "a:\n  b: plain\n  c[2]: x,\"needs, quotes\"\nd: \"123\""
+        );
+
+        let tabs = FormatOptions::new().with_indent(Indent::Tabs);
+        assert_eq!(format("a:\n  b: 1", &tabs).unwrap(), "a:\n\tb: 1");
+    }
+
+    #[test]
+    fn test_format_fixes_lengths() {
+        let input = "items[5]: 1,2\nrows[1]{id}:\n  1\n\n  2\nlist[0]:\n  - a\n  - [1]: x,y";
+        let formatted = format(input, &FormatOptions::default()).unwrap();
+        assert_eq!(
+            formatted,
+            "items[2]: 1,2\nrows[2]{id}:\n  1\n  2\nlist[2]:\n  - a\n  - [2]: x,y"
+        );
+        assert_eq!(
+            decode_default(&formatted).unwrap(),
+            json!({"items": [1, 2], "rows": [{"id": 1}, {"id": 2}], "list": ["a", ["x", "y"]]})
+        );
+    }
+
+    #[test]
+    fn test_format_switches_delimiter() {
+        let input = "t[2\t]{a\tb}:\n  1\tx|y\n  2\tz";
+        let options = FormatOptions::new()
+            .with_delimiter(Delimiter::Comma)
+            .with_length_marker('#');
+        assert_eq!(
+            format(input, &options).unwrap(),
+            "t[#2]{a,b}:\n  1,x|y\n  2,z"
+        );
+    }
+
+    #[test]
+    fn test_format_preserves_value() {
+        let inputs = [
+            "users[2]{id,name}:\n  1,Alice\n  2,\"Bob Smith\"",
+            "a:\n  b:\n    c: 1\nd: \"x\\ty\"",
+            "[3]: x,y,z",
+            "hello world",
+        ];
+
+        for input in inputs {
+            let formatted = format(input, &FormatOptions::default()).unwrap();
+            assert_eq!(
+                decode_default(&formatted).unwrap(),
+                decode_default(input).unwrap()
+            );
+            assert!(is_formatted(&formatted, &FormatOptions::default()).unwrap());
+            assert!(is_formatted(&(formatted + "\n"), &FormatOptions::default()).unwrap());
+        }
+
+        assert!(!is_formatted("a:   1", &FormatOptions::default()).unwrap());
+    }
+
+    #[test]
+    fn test_format_rejects_stray_content() {
+        assert!(format("a: 1\n]", &FormatOptions::default()).is_err());
+        assert!(format("d[1]:\n  - x: 1", &FormatOptions::default()).is_err());
+    }
+}
@@ -16,12 +16,25 @@
 //! ```
 
 pub mod constants;
+pub mod cst;
+pub mod de;
 pub mod decode;
+pub mod diagnostic;
 pub mod encode;
 pub mod error;
+pub mod extract;
+pub mod format;
+pub mod lint;
+pub mod ser;
+pub mod stats;
 pub mod types;
 pub mod utils;
 
+use std::io::{
+    self,
+    Read,
+};
+
 pub use decode::{
     decode,
     decode_default,
@@ -29,17 +42,39 @@ pub use decode::{
     decode_no_coerce_with_options,
     decode_strict,
     decode_strict_with_options,
+    decode_with_repairs,
+    decode_with_spans,
+    Incomplete,
+    IncompleteKind,
+    IncrementalDecoder,
+    Location,
+    Repair,
+    RepairKind,
 };
+pub use diagnostic::{
+    validate,
+    Diagnostic,
+    DiagnosticCode,
+    Severity,
+    Span,
+};
+use encode::writer::IoSink;
 pub use encode::{
     encode,
     encode_array,
     encode_default,
     encode_object,
+    TabularWriter,
 };
 pub use error::{
+    Limit,
     ToonError,
     ToonResult,
 };
+pub use format::{
+    format,
+    is_formatted,
+};
 use serde::{
     Deserialize,
     Serialize,
@@ -47,7 +82,11 @@ use serde::{
 pub use types::{
     DecodeOptions,
     Delimiter,
+    DuplicateKeys,
     EncodeOptions,
+    FloatFormat,
+    FormatOptions,
+    Limits,
 };
 pub use utils::{
     literal::{
@@ -64,9 +103,9 @@ pub use utils::{
 
 /// Serialize any Rust type that implements `Serialize` to TOON format.
 ///
-/// This function converts the value to JSON first, then encodes it to TOON.
-/// You can optionally provide custom encoding options to control the output
-/// format.
+/// The value is written directly through [`ser::Serializer`] without building
+/// an intermediate JSON value. You can optionally provide custom encoding
+/// options to control the output format.
 ///
 /// # Arguments
 ///
@@ -125,21 +164,68 @@ pub use utils::{
 /// # Ok::<(), rtoon::ToonError>(())
 /// ```
 pub fn to_toon<T: Serialize>(value: &T, options: Option<&EncodeOptions>) -> ToonResult<String> {
-    let json_value = serde_json::to_value(value)
-        .map_err(|e| ToonError::InvalidInput(format!("Serialization error: {}", e)))?;
+    let options = options.cloned().unwrap_or_default();
+    if options.key_folding {
+        // Folding needs to see whole objects, so build the value first.
+        return encode(ser::to_value(value)?, &options);
+    }
+
+    let mut serializer = ser::Serializer::new(options);
+    value.serialize(&mut serializer)?;
+    Ok(serializer.finish())
+}
 
-    match options {
-        Some(opts) => encode(&json_value, opts),
-        None => encode_default(&json_value),
+/// Serialize a value as TOON into an [`io::Write`](std::io::Write).
+///
+/// Produces the same output as [`to_toon`]. The output is written in small
+/// pieces, so wrap files and sockets in a [`BufWriter`](std::io::BufWriter).
+/// Write failures are reported as [`ToonError::Io`].
+///
+/// # Examples
+///
+/// ```
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let user = User {
+///     name: "Alice".to_string(),
+///     age: 30,
+/// };
+///
+/// let mut out = Vec::new();
+/// rtoon::to_writer(&mut out, &user, None)?;
+/// assert_eq!(out, b"name: Alice\nage: 30");
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn to_writer<W: io::Write, T: Serialize>(
+    mut writer: W,
+    value: &T,
+    options: Option<&EncodeOptions>,
+) -> ToonResult<()> {
+    let options = options.cloned().unwrap_or_default();
+    if options.key_folding {
+        let toon = encode(ser::to_value(value)?, &options)?;
+        return writer.write_all(toon.as_bytes()).map_err(ToonError::from);
     }
+
+    let sink = IoSink::new(writer);
+    let mut serializer = ser::Serializer::with_sink(sink, options);
+    value.serialize(&mut serializer)
 }
 
 /// Deserialize TOON format directly to any Rust type that implements
 /// `Deserialize`.
 ///
-/// This function decodes TOON to JSON first, then deserializes it to the target
-/// type. You can optionally provide custom decoding options to control parsing
-/// behavior.
+/// The input is read directly by a [`de::Deserializer`], without building an
+/// intermediate JSON value. Errors raised by the target type (such as a missing
+/// field) are reported as [`ToonError::ParseError`] with the line and column at
+/// which they were detected. You can optionally provide custom decoding options
+/// to control parsing behavior.
 ///
 /// # Arguments
 ///
@@ -219,13 +305,173 @@ pub fn from_toon<T: for<'de> Deserialize<'de>>(
     s: &str,
     options: Option<&DecodeOptions>,
 ) -> ToonResult<T> {
-    let json_value = match options {
-        Some(opts) => decode(s, opts)?,
-        None => decode_default(s)?,
-    };
+    from_toon_str(s, options)
+}
+
+/// Deserialize TOON into a type that may borrow from the input.
+///
+/// Unquoted strings and quoted strings without escape sequences are passed to
+/// the visitor as borrowed `&'de str`, so keys and values can be deserialized
+/// into `&str` or `Cow<str>` fields without allocating. Strings that need
+/// unescaping are passed as owned values; use `Cow<'de, str>` for fields that
+/// may contain escapes.
+///
+/// Path expansion, flattened tables, repair and an explicit duplicate key
+/// policy other than [`DuplicateKeys::Error`] need the whole document, so with
+/// those options the input is decoded to a value first and every string is
+/// passed as owned. Without an explicit policy, non-strict decoding passes a
+/// repeated key to the visitor again: maps keep the last value and structs
+/// fail with a duplicate field error.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct User<'a> {
+///     id: u32,
+///     name: &'a str,
+/// }
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct Data<'a> {
+///     #[serde(borrow)]
+///     users: Vec<User<'a>>,
+/// }
+///
+/// let toon = "users[2]{id,name}:\n  1,Alice\n  2,\"Bob Smith\"";
+/// let data: Data = rtoon::from_toon_str(toon, None)?;
+/// assert_eq!(data.users[1].name, "Bob Smith");
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn from_toon_str<'de, T: Deserialize<'de>>(
+    s: &'de str,
+    options: Option<&DecodeOptions>,
+) -> ToonResult<T> {
+    let options = options.cloned().unwrap_or_default();
+    let merges_keys = options
+        .duplicate_keys
+        .is_some_and(|policy| policy != DuplicateKeys::Error);
+    if options.expand_paths || options.flatten_tabular || options.repair || merges_keys {
+        // Nested objects, and the value kept for a repeated key, are only
+        // known once the whole document is read.
+        let value = decode(s, &options)?;
+        return T::deserialize(value).map_err(|e| ToonError::DeserializationError(e.to_string()));
+    }
+
+    let mut deserializer = de::Deserializer::new(s, options);
+    T::deserialize(&mut deserializer)
+}
+
+/// Deserialize TOON read from an [`io::Read`](std::io::Read).
+///
+/// The whole input is read before parsing, stopping once it is larger than
+/// the `max_document_bytes` limit. Read failures and invalid UTF-8 are
+/// reported as [`ToonError::Io`].
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct User {
+///     name: String,
+///     age: u32,
+/// }
+///
+/// let input: &[u8] = b"name: Alice\nage: 30";
+/// let user: User = rtoon::from_reader(input, None)?;
+/// assert_eq!(user.name, "Alice");
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn from_reader<R: io::Read, T: for<'de> Deserialize<'de>>(
+    reader: R,
+    options: Option<&DecodeOptions>,
+) -> ToonResult<T> {
+    let max = options.map_or(usize::MAX, |options| options.limits.max_document_bytes);
+    let mut input = Vec::new();
+    reader
+        .take((max as u64).saturating_add(1))
+        .read_to_end(&mut input)?;
+    if input.len() > max {
+        return Err(ToonError::limit_exceeded(
+            Limit::DocumentBytes,
+            max,
+            input.len(),
+            1,
+            None,
+        ));
+    }
+    let input =
+        String::from_utf8(input).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    from_toon_str(&input, options)
+}
+
+/// Iterate over the rows of the tabular array under a root-level key.
+///
+/// Rows are read from `reader` and deserialized one at a time, so large
+/// tables never need to fit in memory. Uses the default (strict) decode
+/// options; see [`rows_with_options`] to customize them.
+///
+/// # Examples
+///
+/// ```
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize, Debug, PartialEq)]
+/// struct User {
+///     id: u32,
+///     name: String,
+/// }
+///
+/// let input: &[u8] = b"users[2]{id,name}:\n  1,Alice\n  2,Bob\n";
+/// for user in rtoon::rows::<_, User>(input, "users")? {
+///     println!("{}", user?.name);
+/// }
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn rows<R: io::BufRead, T: for<'de> Deserialize<'de>>(
+    reader: R,
+    key: &str,
+) -> ToonResult<de::Rows<R, T>> {
+    rows_with_options(reader, key, &DecodeOptions::default())
+}
 
-    serde_json::from_value(json_value)
-        .map_err(|e| ToonError::InvalidInput(format!("Deserialization error: {}", e)))
+/// Iterate over the rows of a tabular array with custom decode options.
+///
+/// Like [`rows`], but the options apply to the whole stream. Set
+/// [`Limits`] to bound what untrusted input may claim: a header declaring
+/// more rows than `max_array_length` fails before any row is read, and the
+/// stream stops once it is larger than `max_document_bytes`.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     DecodeOptions,
+///     Limits,
+///     ToonError,
+/// };
+/// use serde::Deserialize;
+///
+/// #[derive(Deserialize)]
+/// struct User {
+///     id: u32,
+/// }
+///
+/// let options = DecodeOptions::new().with_limits(Limits::new().with_max_array_length(1000));
+/// let input: &[u8] = b"users[999999999]{id}:\n  1\n";
+/// let err = rtoon::rows_with_options::<_, User>(input, "users", &options).err();
+/// assert!(matches!(err, Some(ToonError::LimitExceeded { .. })));
+/// ```
+pub fn rows_with_options<R: io::BufRead, T: for<'de> Deserialize<'de>>(
+    reader: R,
+    key: &str,
+    options: &DecodeOptions,
+) -> ToonResult<de::Rows<R, T>> {
+    de::Rows::new(reader, key, options)
 }
 
 #[cfg(test)]
@@ -309,4 +555,56 @@ mod tests {
         assert_eq!(escape_string("hello\nworld"), "hello\\nworld");
         assert!(needs_quoting("true", Delimiter::Comma));
     }
+
+    #[test]
+    fn test_writer_and_reader_round_trip() {
+        let original = json!({
+            "users": [{"id": 1, "name": "Alice"}, {"id": 2, "name": "Bob"}],
+            "tags": ["a", "b"]
+        });
+        let options = EncodeOptions::new().with_delimiter(Delimiter::Pipe);
+
+        let mut out = Vec::new();
+        to_writer(&mut out, &original, Some(&options)).unwrap();
+        assert_eq!(
+            String::from_utf8(out.clone()).unwrap(),
+            to_toon(&original, Some(&options)).unwrap()
+        );
+
+        let decoded: serde_json::Value = from_reader(out.as_slice(), None).unwrap();
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn test_io_errors() {
+        struct Broken;
+
+        impl io::Write for Broken {
+            fn write(&mut self, _buf: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let err = to_writer(Broken, &json!({"a": 1}), None).unwrap_err();
+        assert!(matches!(
+            err,
+            ToonError::Io {
+                kind: io::ErrorKind::BrokenPipe,
+                ..
+            }
+        ));
+
+        let err = from_reader::<_, serde_json::Value>(&[0xff, 0xfe][..], None).unwrap_err();
+        assert!(matches!(
+            err,
+            ToonError::Io {
+                kind: io::ErrorKind::InvalidData,
+                ..
+            }
+        ));
+    }
 }
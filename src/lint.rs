@@ -0,0 +1,240 @@
+//! Style checks for TOON documents.
+
+use crate::{
+    diagnostic::{
+        check,
+        Diagnostic,
+    },
+    types::{
+        DecodeOptions,
+        Delimiter,
+    },
+};
+
+/// A style rule checked by [`lint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    /// A line ends with spaces or tabs.
+    TrailingSpaces,
+    /// The document ends with a newline.
+    TrailingNewline,
+    /// An array header without a delimiter symbol whose fields or values are
+    /// separated by pipes or tabs, so they are read as a single value.
+    MissingDelimiterMark,
+    /// An unquoted value contains the active delimiter, `:`, `"`, `\`, or a
+    /// bracket or brace, so it must be quoted (SPEC §7.2, §11).
+    UnquotedValue,
+    /// A quoted key or value that could be written without quotes.
+    UnnecessaryQuotes,
+    /// Some array headers use the `#` length marker and others do not.
+    InconsistentLengthMarker,
+}
+
+impl Lint {
+    /// Every lint, in the order they are listed above.
+    pub const ALL: [Lint; 6] = [
+        Lint::TrailingSpaces,
+        Lint::TrailingNewline,
+        Lint::MissingDelimiterMark,
+        Lint::UnquotedValue,
+        Lint::UnnecessaryQuotes,
+        Lint::InconsistentLengthMarker,
+    ];
+
+    /// The lint as a short kebab-case name, e.g. `trailing-spaces`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lint::TrailingSpaces => "trailing-spaces",
+            Lint::TrailingNewline => "trailing-newline",
+            Lint::MissingDelimiterMark => "missing-delimiter-mark",
+            Lint::UnquotedValue => "unquoted-value",
+            Lint::UnnecessaryQuotes => "unnecessary-quotes",
+            Lint::InconsistentLengthMarker => "inconsistent-length-marker",
+        }
+    }
+}
+
+/// Options for [`lint`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LintOptions {
+    /// The lints to check.
+    pub enabled: Vec<Lint>,
+    /// The delimiter that values outside any array are quoted for.
+    pub delimiter: Delimiter,
+}
+
+impl Default for LintOptions {
+    fn default() -> Self {
+        Self {
+            enabled: Lint::ALL.to_vec(),
+            delimiter: Delimiter::Comma,
+        }
+    }
+}
+
+impl LintOptions {
+    /// Create new lint options with every lint enabled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Enable or disable a lint.
+    pub fn with_lint(mut self, lint: Lint, enabled: bool) -> Self {
+        self.enabled.retain(|&l| l != lint);
+        if enabled {
+            self.enabled.push(lint);
+        }
+        self
+    }
+
+    /// Set the delimiter that values outside any array are quoted for.
+    pub fn with_delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Whether a lint is enabled.
+    pub fn is_enabled(&self, lint: Lint) -> bool {
+        self.enabled.contains(&lint)
+    }
+}
+
+/// Check a document for style problems, returned as warnings in line order.
+///
+/// The checks cover the invariants that SPEC.md §14 says validators should
+/// report, plus unnecessary quotes and inconsistent length markers. Output
+/// of [`encode`](crate::encode) passes every lint. Use
+/// [`validate`](crate::validate) to check for errors.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     lint::{
+///         lint,
+///         Lint,
+///         LintOptions,
+///     },
+///     DiagnosticCode,
+/// };
+///
+/// let input = "name: \"Alice\"\ntags[#2]: a,b\nids[2]: 1,2 \n";
+/// let options = LintOptions::new().with_lint(Lint::TrailingNewline, false);
+///
+/// let found: Vec<_> = lint(input, &options)
+///     .into_iter()
+///     .map(|d| (d.span.line, d.code))
+///     .collect();
+/// assert_eq!(
+///     found,
+///     vec![
+///         (1, DiagnosticCode::Lint(Lint::UnnecessaryQuotes)),
+///         (3, DiagnosticCode::Lint(Lint::InconsistentLengthMarker)),
+///         (3, DiagnosticCode::Lint(Lint::TrailingSpaces)),
+///     ]
+/// );
+/// ```
+pub fn lint(input: &str, options: &LintOptions) -> Vec<Diagnostic> {
+    check(input, &DecodeOptions::default(), Some(options))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        diagnostic::DiagnosticCode,
+        EncodeOptions,
+    };
+
+    fn lints(input: &str) -> Vec<(usize, Lint)> {
+        lint(input, &LintOptions::default())
+            .into_iter()
+            .map(|d| match d.code {
+                DiagnosticCode::Lint(lint) => (d.span.line, lint),
+                code => panic!("unexpected {}", code),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_encoder_output_passes() {
+        let value = json!({
+            "name": "Ada Lovelace",
+            "quoted": ["true", "42", "", "a:b", "- x", "a|b"],
+            "users": [{"id": 1, "name": "Alice"}, {"id": 2, "name": "Bob, Jr."}],
+            "nested": {"list": [[1, 2], ["x y"]]},
+            "key with space": 1,
+            "a.b": 2,
+            "single": ["p|q"],
+            "column": [{"a": "p|q"}],
+            "split": [{"a": "p|q"}, {"a": "x\ty"}]
+        });
+        for options in [
+            EncodeOptions::default(),
+            EncodeOptions::new().with_delimiter(Delimiter::Pipe),
+            EncodeOptions::new().with_delimiter(Delimiter::Tab),
+            EncodeOptions::new().with_length_marker('#'),
+            EncodeOptions::new().with_key_folding(true),
+        ] {
+            let toon = crate::encode(&value, &options).unwrap();
+            let lint_options = LintOptions::new().with_delimiter(options.delimiter);
+            assert_eq!(lint(&toon, &lint_options), vec![], "{}", toon);
+        }
+    }
+
+    #[test]
+    fn test_each_lint() {
+        let input =
+            "a: 1 \n\"b\": \"x\"\nc: x:y\nrows[2]{id|name}:\n  1|A\n  2|B\nv[#2]: \"q\",z\n";
+        assert_eq!(
+            lints(input),
+            vec![
+                (1, Lint::TrailingSpaces),
+                (2, Lint::UnnecessaryQuotes),
+                (2, Lint::UnnecessaryQuotes),
+                (3, Lint::UnquotedValue),
+                (4, Lint::MissingDelimiterMark),
+                (7, Lint::InconsistentLengthMarker),
+                (7, Lint::UnnecessaryQuotes),
+                (7, Lint::TrailingNewline),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lints_can_be_disabled() {
+        let input = "a: \"x\" \n";
+        let mut options = LintOptions::new();
+        assert_eq!(lint(input, &options).len(), 3);
+
+        for lint_kind in Lint::ALL {
+            options = options.with_lint(lint_kind, false);
+        }
+        assert!(lint(input, &options).is_empty());
+
+        options = options.with_lint(Lint::TrailingNewline, true);
+        let found = lint(input, &options);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].code.as_str(), "trailing-newline");
+    }
+
+    #[test]
+    fn test_delimiter_quoting() {
+        let options = LintOptions::new().with_delimiter(Delimiter::Pipe);
+        assert_eq!(lint("a: x,y", &options), vec![]);
+        assert_eq!(lint("a: x|y", &options).len(), 1);
+        assert_eq!(lints("tags[2|]: a,b|c"), vec![]);
+        assert_eq!(
+            lints("tags[2]: \"a|b\",c"),
+            vec![(1, Lint::UnnecessaryQuotes)]
+        );
+
+        // Pipes only count as the delimiter when they split the declared
+        // length.
+        assert_eq!(lints("tags[2]: a|b"), vec![(1, Lint::MissingDelimiterMark)]);
+        assert_eq!(lints("tags[1]: a|b"), vec![]);
+        assert_eq!(lints("tags[3]: a|b"), vec![]);
+    }
+}
@@ -0,0 +1,186 @@
+use serde::ser::{
+    self,
+    Impossible,
+    Serialize,
+};
+
+use crate::error::{
+    ToonError,
+    ToonResult,
+};
+
+/// Serializer for map keys. TOON keys are strings, so only scalar keys are
+/// accepted and converted to their string form.
+pub(crate) struct MapKeySerializer;
+
+fn key_must_be_a_string() -> ToonError {
+    ToonError::SerializationError("Map key must be a string".to_string())
+}
+
+impl ser::Serializer for MapKeySerializer {
+    type Ok = String;
+    type Error = ToonError;
+
+    type SerializeSeq = Impossible<String, ToonError>;
+    type SerializeTuple = Impossible<String, ToonError>;
+    type SerializeTupleStruct = Impossible<String, ToonError>;
+    type SerializeTupleVariant = Impossible<String, ToonError>;
+    type SerializeMap = Impossible<String, ToonError>;
+    type SerializeStruct = Impossible<String, ToonError>;
+    type SerializeStructVariant = Impossible<String, ToonError>;
+
+    fn serialize_bool(self, v: bool) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i8(self, v: i8) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i16(self, v: i16) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i32(self, v: i32) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i64(self, v: i64) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_i128(self, v: i128) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u8(self, v: u8) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u16(self, v: u16) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u32(self, v: u32) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u64(self, v: u64) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_u128(self, v: u128) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_f32(self, _v: f32) -> ToonResult<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_f64(self, _v: f64) -> ToonResult<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_char(self, v: char) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_str(self, v: &str) -> ToonResult<String> {
+        Ok(v.to_string())
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> ToonResult<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_none(self) -> ToonResult<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _value: &T) -> ToonResult<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit(self) -> ToonResult<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> ToonResult<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> ToonResult<String> {
+        Ok(variant.to_string())
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> ToonResult<String> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _value: &T,
+    ) -> ToonResult<String> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> ToonResult<Self::SerializeSeq> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> ToonResult<Self::SerializeTuple> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> ToonResult<Self::SerializeTupleStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> ToonResult<Self::SerializeTupleVariant> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> ToonResult<Self::SerializeMap> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        _len: usize,
+    ) -> ToonResult<Self::SerializeStruct> {
+        Err(key_must_be_a_string())
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        _variant: &'static str,
+        _len: usize,
+    ) -> ToonResult<Self::SerializeStructVariant> {
+        Err(key_must_be_a_string())
+    }
+}
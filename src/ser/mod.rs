@@ -0,0 +1,867 @@
+//! Native serde serializer that writes TOON directly.
+//!
+//! Objects and primitives are streamed straight into the output. Arrays are
+//! the only values that are buffered, because their layout (inline,
+//! tabular or list) depends on every element.
+
+mod key;
+mod value;
+
+use std::borrow::Cow;
+
+use serde::ser::{
+    self,
+    Serialize,
+};
+pub(crate) use value::to_value;
+
+use crate::{
+    constants::MAX_DEPTH,
+    encode::{
+        self,
+        writer::{
+            Sink,
+            Writer,
+        },
+    },
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    types::{
+        EncodeOptions,
+        JsonValue as Value,
+    },
+    utils::validation::validate_depth,
+};
+
+/// The struct name that serde_json serializes a `Number` as with the
+/// `arbitrary_precision` feature. Its only field holds the number's text.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) const NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+/// A serde `Serializer` that emits TOON.
+///
+/// Produces the same output as [`encode`](crate::encode) on the equivalent
+/// JSON value, without building the intermediate value tree. Key folding
+/// needs whole objects and is not applied here; [`to_toon`](crate::to_toon)
+/// and [`to_writer`](crate::to_writer) handle it by building the value first.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     ser::Serializer,
+///     EncodeOptions,
+/// };
+/// use serde::Serialize;
+///
+/// #[derive(Serialize)]
+/// struct User {
+///     name: String,
+///     tags: Vec<String>,
+/// }
+///
+/// let user = User {
+///     name: "Alice".to_string(),
+///     tags: vec!["admin".to_string(), "ops".to_string()],
+/// };
+///
+/// let mut serializer = Serializer::new(EncodeOptions::default());
+/// user.serialize(&mut serializer)?;
+/// assert_eq!(serializer.finish(), "name: Alice\ntags[2]: admin,ops");
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub struct Serializer<S = String> {
+    writer: Writer<S>,
+}
+
+impl Serializer {
+    /// Create a new serializer with the given encoding options.
+    pub fn new(options: EncodeOptions) -> Self {
+        Self {
+            writer: Writer::new(options),
+        }
+    }
+
+    /// Finish serializing and return the complete TOON string.
+    pub fn finish(self) -> String {
+        self.writer.finish()
+    }
+}
+
+impl<S: Sink> Serializer<S> {
+    /// Create a serializer that writes into the given sink.
+    pub fn with_sink(sink: S, options: EncodeOptions) -> Self {
+        Self {
+            writer: Writer::with_sink(sink, options),
+        }
+    }
+
+    /// Finish serializing and return the sink.
+    pub fn into_sink(self) -> S {
+        self.writer.into_sink()
+    }
+
+    fn root(&mut self) -> SlotSerializer<'_, S> {
+        SlotSerializer {
+            ser: self,
+            slot: Slot::Root,
+        }
+    }
+}
+
+/// Where the next value is written: the document root or an object field.
+enum Slot {
+    Root,
+    Field {
+        key: Cow<'static, str>,
+        depth: usize,
+        first: bool,
+    },
+}
+
+/// Serializes a single value into its slot.
+struct SlotSerializer<'a, S> {
+    ser: &'a mut Serializer<S>,
+    slot: Slot,
+}
+
+impl<'a, S: Sink> SlotSerializer<'a, S> {
+    fn write_field_prefix(writer: &mut Writer<S>, depth: usize, first: bool) -> ToonResult<()> {
+        // Nested objects start on the line after their key, which is only
+        // written once the object turns out to have fields.
+        if !first || depth > 0 {
+            writer.write_newline()?;
+        }
+        if depth > 0 {
+            writer.write_indent(depth)?;
+        }
+        Ok(())
+    }
+
+    fn write_scalar(self, f: impl FnOnce(&mut Writer<S>) -> ToonResult<()>) -> ToonResult<()> {
+        let writer = &mut self.ser.writer;
+        if let Slot::Field { key, depth, first } = self.slot {
+            Self::write_field_prefix(writer, depth, first)?;
+            writer.write_key(&key)?;
+            writer.write_char(':')?;
+            writer.write_char(' ')?;
+        }
+        f(writer)
+    }
+
+    fn write_primitive(self, value: Value) -> ToonResult<()> {
+        self.write_scalar(|w| encode::write_primitive_value(w, &value))
+    }
+
+    fn write_array(self, arr: Vec<Value>) -> ToonResult<()> {
+        let writer = &mut self.ser.writer;
+        match self.slot {
+            Slot::Root => encode::write_array(writer, None, &arr, 0),
+            Slot::Field { key, depth, first } => {
+                Self::write_field_prefix(writer, depth, first)?;
+                encode::write_array(writer, Some(&key), &arr, depth)
+            }
+        }
+    }
+
+    fn begin_object(self) -> ToonResult<Compound<'a, S>> {
+        let depth = match self.slot {
+            Slot::Root => 0,
+            Slot::Field { key, depth, first } => {
+                validate_depth(depth + 1, MAX_DEPTH)?;
+                let writer = &mut self.ser.writer;
+                Self::write_field_prefix(writer, depth, first)?;
+                writer.write_key(&key)?;
+                writer.write_char(':')?;
+                depth + 1
+            }
+        };
+
+        Ok(Compound {
+            ser: self.ser,
+            depth,
+            index: 0,
+            next_key: None,
+            #[cfg(feature = "arbitrary_precision")]
+            number: None,
+        })
+    }
+
+    fn begin_array(self, len: Option<usize>) -> ToonResult<SeqBuffer<'a, S>> {
+        if let Slot::Field { depth, .. } = self.slot {
+            validate_depth(depth, MAX_DEPTH)?;
+        }
+        Ok(SeqBuffer {
+            target: self,
+            items: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+}
+
+impl<'a, S: Sink> ser::Serializer for SlotSerializer<'a, S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    type SerializeSeq = SeqBuffer<'a, S>;
+    type SerializeTuple = SeqBuffer<'a, S>;
+    type SerializeTupleStruct = SeqBuffer<'a, S>;
+    type SerializeTupleVariant = SeqBuffer<'a, S>;
+    type SerializeMap = Compound<'a, S>;
+    type SerializeStruct = Compound<'a, S>;
+    type SerializeStructVariant = Compound<'a, S>;
+
+    fn serialize_bool(self, v: bool) -> ToonResult<()> {
+        self.write_primitive(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> ToonResult<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> ToonResult<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> ToonResult<()> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> ToonResult<()> {
+        self.write_primitive(value::number_value(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> ToonResult<()> {
+        self.write_primitive(value::i128_value(v)?)
+    }
+
+    fn serialize_u8(self, v: u8) -> ToonResult<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> ToonResult<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> ToonResult<()> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> ToonResult<()> {
+        self.write_primitive(value::number_value(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> ToonResult<()> {
+        self.write_primitive(value::u128_value(v)?)
+    }
+
+    fn serialize_f32(self, v: f32) -> ToonResult<()> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> ToonResult<()> {
+        self.write_primitive(value::number_value(v))
+    }
+
+    fn serialize_char(self, v: char) -> ToonResult<()> {
+        let mut buf = [0u8; 4];
+        self.serialize_str(v.encode_utf8(&mut buf))
+    }
+
+    fn serialize_str(self, v: &str) -> ToonResult<()> {
+        self.write_scalar(|w| w.write_value(v))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> ToonResult<()> {
+        let arr = v.iter().map(|&b| value::number_value(b)).collect();
+        self.write_array(arr)
+    }
+
+    fn serialize_none(self) -> ToonResult<()> {
+        self.write_primitive(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> ToonResult<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> ToonResult<()> {
+        self.write_primitive(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> ToonResult<()> {
+        self.write_primitive(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> ToonResult<()> {
+        self.serialize_str(variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> ToonResult<()> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> ToonResult<()> {
+        value.serialize(self.begin_object()?.into_only_field(variant))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> ToonResult<SeqBuffer<'a, S>> {
+        self.begin_array(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> ToonResult<SeqBuffer<'a, S>> {
+        self.begin_array(Some(len))
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> ToonResult<SeqBuffer<'a, S>> {
+        self.begin_array(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> ToonResult<SeqBuffer<'a, S>> {
+        self.begin_object()?
+            .into_only_field(variant)
+            .begin_array(Some(len))
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> ToonResult<Compound<'a, S>> {
+        self.begin_object()
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> ToonResult<Compound<'a, S>> {
+        #[cfg(feature = "arbitrary_precision")]
+        if _name == NUMBER_TOKEN {
+            return Ok(Compound {
+                ser: self.ser,
+                depth: 0,
+                index: 0,
+                next_key: None,
+                number: Some(self.slot),
+            });
+        }
+        self.begin_object()
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> ToonResult<Compound<'a, S>> {
+        self.begin_object()?.into_only_field(variant).begin_object()
+    }
+}
+
+/// Streams the fields of an object at a fixed depth.
+pub struct Compound<'a, S = String> {
+    ser: &'a mut Serializer<S>,
+    depth: usize,
+    index: usize,
+    next_key: Option<String>,
+    /// The slot of a serde_json number, written once its text arrives.
+    #[cfg(feature = "arbitrary_precision")]
+    number: Option<Slot>,
+}
+
+impl<'a, S: Sink> Compound<'a, S> {
+    fn field(&mut self, key: Cow<'static, str>) -> SlotSerializer<'_, S> {
+        let first = self.index == 0;
+        self.index += 1;
+        SlotSerializer {
+            ser: &mut *self.ser,
+            slot: Slot::Field {
+                key,
+                depth: self.depth,
+                first,
+            },
+        }
+    }
+
+    /// Turn a freshly opened object into the slot for its single field (used
+    /// for externally tagged enum variants).
+    fn into_only_field(self, key: &'static str) -> SlotSerializer<'a, S> {
+        SlotSerializer {
+            ser: self.ser,
+            slot: Slot::Field {
+                key: Cow::Borrowed(key),
+                depth: self.depth,
+                first: true,
+            },
+        }
+    }
+}
+
+impl<S: Sink> ser::SerializeMap for Compound<'_, S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> ToonResult<()> {
+        self.next_key = Some(key.serialize(key::MapKeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        let key = self.next_key.take().ok_or_else(|| {
+            ToonError::SerializationError("serialize_value called before serialize_key".into())
+        })?;
+        value.serialize(self.field(Cow::Owned(key)))
+    }
+
+    fn end(self) -> ToonResult<()> {
+        Ok(())
+    }
+}
+
+impl<S: Sink> ser::SerializeStruct for Compound<'_, S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ToonResult<()> {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(slot) = self.number.take() {
+            let text = value.serialize(key::MapKeySerializer)?;
+            let target = SlotSerializer {
+                ser: &mut *self.ser,
+                slot,
+            };
+            return target.write_primitive(value::decimal_value(&text)?);
+        }
+        value.serialize(self.field(Cow::Borrowed(key)))
+    }
+
+    fn end(self) -> ToonResult<()> {
+        Ok(())
+    }
+}
+
+impl<S: Sink> ser::SerializeStructVariant for Compound<'_, S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ToonResult<()> {
+        ser::SerializeStruct::serialize_field(self, key, value)
+    }
+
+    fn end(self) -> ToonResult<()> {
+        Ok(())
+    }
+}
+
+/// Buffers array elements until the array ends, then writes it in whichever
+/// layout the elements call for.
+pub struct SeqBuffer<'a, S = String> {
+    target: SlotSerializer<'a, S>,
+    items: Vec<Value>,
+}
+
+impl<S: Sink> ser::SerializeSeq for SeqBuffer<'_, S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        self.items.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> ToonResult<()> {
+        self.target.write_array(self.items)
+    }
+}
+
+impl<S: Sink> ser::SerializeTuple for SeqBuffer<'_, S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> ToonResult<()> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<S: Sink> ser::SerializeTupleStruct for SeqBuffer<'_, S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> ToonResult<()> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<S: Sink> ser::SerializeTupleVariant for SeqBuffer<'_, S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> ToonResult<()> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl<'a, S: Sink> ser::Serializer for &'a mut Serializer<S> {
+    type Ok = ();
+    type Error = ToonError;
+
+    type SerializeSeq = SeqBuffer<'a, S>;
+    type SerializeTuple = SeqBuffer<'a, S>;
+    type SerializeTupleStruct = SeqBuffer<'a, S>;
+    type SerializeTupleVariant = SeqBuffer<'a, S>;
+    type SerializeMap = Compound<'a, S>;
+    type SerializeStruct = Compound<'a, S>;
+    type SerializeStructVariant = Compound<'a, S>;
+
+    fn serialize_bool(self, v: bool) -> ToonResult<()> {
+        self.root().serialize_bool(v)
+    }
+
+    fn serialize_i8(self, v: i8) -> ToonResult<()> {
+        self.root().serialize_i8(v)
+    }
+
+    fn serialize_i16(self, v: i16) -> ToonResult<()> {
+        self.root().serialize_i16(v)
+    }
+
+    fn serialize_i32(self, v: i32) -> ToonResult<()> {
+        self.root().serialize_i32(v)
+    }
+
+    fn serialize_i64(self, v: i64) -> ToonResult<()> {
+        self.root().serialize_i64(v)
+    }
+
+    fn serialize_i128(self, v: i128) -> ToonResult<()> {
+        self.root().serialize_i128(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> ToonResult<()> {
+        self.root().serialize_u8(v)
+    }
+
+    fn serialize_u16(self, v: u16) -> ToonResult<()> {
+        self.root().serialize_u16(v)
+    }
+
+    fn serialize_u32(self, v: u32) -> ToonResult<()> {
+        self.root().serialize_u32(v)
+    }
+
+    fn serialize_u64(self, v: u64) -> ToonResult<()> {
+        self.root().serialize_u64(v)
+    }
+
+    fn serialize_u128(self, v: u128) -> ToonResult<()> {
+        self.root().serialize_u128(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> ToonResult<()> {
+        self.root().serialize_f32(v)
+    }
+
+    fn serialize_f64(self, v: f64) -> ToonResult<()> {
+        self.root().serialize_f64(v)
+    }
+
+    fn serialize_char(self, v: char) -> ToonResult<()> {
+        self.root().serialize_char(v)
+    }
+
+    fn serialize_str(self, v: &str) -> ToonResult<()> {
+        self.root().serialize_str(v)
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> ToonResult<()> {
+        self.root().serialize_bytes(v)
+    }
+
+    fn serialize_none(self) -> ToonResult<()> {
+        self.root().serialize_none()
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> ToonResult<()> {
+        self.root().serialize_some(value)
+    }
+
+    fn serialize_unit(self) -> ToonResult<()> {
+        self.root().serialize_unit()
+    }
+
+    fn serialize_unit_struct(self, name: &'static str) -> ToonResult<()> {
+        self.root().serialize_unit_struct(name)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> ToonResult<()> {
+        self.root()
+            .serialize_unit_variant(name, variant_index, variant)
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> ToonResult<()> {
+        self.root().serialize_newtype_struct(name, value)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> ToonResult<()> {
+        self.root()
+            .serialize_newtype_variant(name, variant_index, variant, value)
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> ToonResult<SeqBuffer<'a, S>> {
+        self.root().serialize_seq(len)
+    }
+
+    fn serialize_tuple(self, len: usize) -> ToonResult<SeqBuffer<'a, S>> {
+        self.root().serialize_tuple(len)
+    }
+
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        len: usize,
+    ) -> ToonResult<SeqBuffer<'a, S>> {
+        self.root().serialize_tuple_struct(name, len)
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> ToonResult<SeqBuffer<'a, S>> {
+        self.root()
+            .serialize_tuple_variant(name, variant_index, variant, len)
+    }
+
+    fn serialize_map(self, len: Option<usize>) -> ToonResult<Compound<'a, S>> {
+        self.root().serialize_map(len)
+    }
+
+    fn serialize_struct(self, name: &'static str, len: usize) -> ToonResult<Compound<'a, S>> {
+        self.root().serialize_struct(name, len)
+    }
+
+    fn serialize_struct_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> ToonResult<Compound<'a, S>> {
+        self.root()
+            .serialize_struct_variant(name, variant_index, variant, len)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{
+        encode,
+        Delimiter,
+    };
+
+    fn assert_same_as_encode<T: Serialize>(value: &T, options: &EncodeOptions) {
+        let mut serializer = Serializer::new(options.clone());
+        value.serialize(&mut serializer).unwrap();
+        let expected = encode(serde_json::to_value(value).unwrap(), options).unwrap();
+        assert_eq!(serializer.finish(), expected);
+    }
+
+    #[derive(Serialize)]
+    struct User {
+        id: u32,
+        name: String,
+        role: Role,
+    }
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Role {
+        Admin,
+        User,
+    }
+
+    #[derive(Serialize)]
+    enum Event {
+        Click { x: i32, y: i32 },
+        Key(char),
+        Scroll(f64, f64),
+    }
+
+    #[derive(Serialize)]
+    struct Document {
+        title: &'static str,
+        version: Option<u8>,
+        users: Vec<User>,
+        events: Vec<Event>,
+        last: Event,
+        meta: BTreeMap<String, serde_json::Value>,
+        empty: Vec<u8>,
+        nothing: (),
+    }
+
+    fn document() -> Document {
+        let mut meta = BTreeMap::new();
+        meta.insert("nested".to_string(), json!({"a": {"b": [1, 2]}}));
+        meta.insert("empty".to_string(), json!({}));
+        meta.insert("ratio".to_string(), json!(0.5));
+
+        Document {
+            title: "Quarterly report: Q3",
+            version: None,
+            users: vec![
+                User {
+                    id: 1,
+                    name: "Alice".to_string(),
+                    role: Role::Admin,
+                },
+                User {
+                    id: 2,
+                    name: "Bob, Jr.".to_string(),
+                    role: Role::User,
+                },
+            ],
+            events: vec![
+                Event::Click { x: 1, y: -2 },
+                Event::Key('k'),
+                Event::Scroll(0.5, -0.0),
+            ],
+            last: Event::Click { x: 3, y: 4 },
+            meta,
+            empty: vec![],
+            nothing: (),
+        }
+    }
+
+    #[test]
+    fn test_matches_encode_for_structs() {
+        assert_same_as_encode(&document(), &EncodeOptions::default());
+        assert_same_as_encode(
+            &document(),
+            &EncodeOptions::new()
+                .with_delimiter(Delimiter::Pipe)
+                .with_length_marker('#')
+                .with_spaces(4),
+        );
+    }
+
+    #[test]
+    fn test_matches_encode_for_roots() {
+        let options = EncodeOptions::default();
+        assert_same_as_encode(&42, &options);
+        assert_same_as_encode(&"hello world", &options);
+        assert_same_as_encode(&f64::NAN, &options);
+        assert_same_as_encode(&vec![1, 2, 3], &options);
+        assert_same_as_encode(&vec![vec![1, 2], vec![3]], &options);
+        assert_same_as_encode(&Event::Key('x'), &options);
+        assert_same_as_encode(&Event::Scroll(1.0, 2.0), &options);
+        assert_same_as_encode(&Event::Click { x: 0, y: 0 }, &options);
+        assert_same_as_encode(&BTreeMap::<String, i32>::new(), &options);
+    }
+
+    #[test]
+    fn test_depth_limit_matches_encode() {
+        let nest = |depth: usize, leaf: serde_json::Value| {
+            (0..depth).fold(leaf, |inner, _| json!({ "a": inner }))
+        };
+
+        // Debug builds need more stack than the test harness gives for
+        // hundreds of nested serializer frames.
+        let check = move || {
+            for leaf in [json!(1), json!([1, 2])] {
+                let deepest = nest(MAX_DEPTH + 1, leaf.clone());
+                assert_same_as_encode(&deepest, &EncodeOptions::default());
+
+                let too_deep = nest(MAX_DEPTH + 2, leaf);
+                let expected = encode(&too_deep, &EncodeOptions::default()).unwrap_err();
+                let mut serializer = Serializer::new(EncodeOptions::default());
+                let err = too_deep.serialize(&mut serializer).unwrap_err();
+                assert_eq!(err, expected);
+            }
+        };
+        std::thread::Builder::new()
+            .stack_size(64 << 20)
+            .spawn(check)
+            .unwrap()
+            .join()
+            .unwrap();
+    }
+
+    #[test]
+    fn test_map_keys() {
+        let mut map = BTreeMap::new();
+        map.insert(1, "one");
+        map.insert(2, "two");
+        assert_same_as_encode(&map, &EncodeOptions::default());
+
+        let mut bad = BTreeMap::new();
+        bad.insert(vec![1], "one");
+        let mut serializer = Serializer::new(EncodeOptions::default());
+        assert!(matches!(
+            bad.serialize(&mut serializer),
+            Err(ToonError::SerializationError(_))
+        ));
+    }
+}
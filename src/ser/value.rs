@@ -0,0 +1,413 @@
+use indexmap::IndexMap;
+use serde::ser::{
+    self,
+    Serialize,
+};
+
+use crate::{
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    ser::key::MapKeySerializer,
+    types::{
+        JsonValue as Value,
+        Number,
+    },
+    utils::normalize,
+};
+
+type Object = IndexMap<String, Value>;
+
+/// Serialize a value into an in-memory `JsonValue`.
+///
+/// Used for the parts of a document that have to be buffered before they can
+/// be written, such as array elements whose layout depends on every row.
+pub(crate) fn to_value<T: ?Sized + Serialize>(value: &T) -> ToonResult<Value> {
+    value.serialize(ValueSerializer)
+}
+
+/// Build a normalized number value (NaN/Infinity become null, -0 becomes 0).
+pub(crate) fn number_value(n: impl Into<Number>) -> Value {
+    normalize(Value::Number(n.into()))
+}
+
+/// Build a number value from the text of a serde_json number.
+#[cfg(feature = "arbitrary_precision")]
+pub(crate) fn decimal_value(text: &str) -> ToonResult<Value> {
+    let number: serde_json::Number = text
+        .parse()
+        .map_err(|_| ToonError::SerializationError(format!("Invalid number: {}", text)))?;
+    Ok(normalize(serde_json::Value::Number(number).into()))
+}
+
+pub(crate) fn i128_value(v: i128) -> ToonResult<Value> {
+    if let Ok(i) = i64::try_from(v) {
+        Ok(number_value(i))
+    } else if let Ok(u) = u64::try_from(v) {
+        Ok(number_value(u))
+    } else {
+        Err(ToonError::SerializationError(format!(
+            "Number out of range: {}",
+            v
+        )))
+    }
+}
+
+pub(crate) fn u128_value(v: u128) -> ToonResult<Value> {
+    u64::try_from(v)
+        .map(number_value)
+        .map_err(|_| ToonError::SerializationError(format!("Number out of range: {}", v)))
+}
+
+/// Serializer that builds a `JsonValue` tree.
+pub(crate) struct ValueSerializer;
+
+impl ser::Serializer for ValueSerializer {
+    type Ok = Value;
+    type Error = ToonError;
+
+    type SerializeSeq = SerializeVec;
+    type SerializeTuple = SerializeVec;
+    type SerializeTupleStruct = SerializeVec;
+    type SerializeTupleVariant = SerializeTupleVariant;
+    type SerializeMap = SerializeMap;
+    type SerializeStruct = SerializeMap;
+    type SerializeStructVariant = SerializeStructVariant;
+
+    fn serialize_bool(self, v: bool) -> ToonResult<Value> {
+        Ok(Value::Bool(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> ToonResult<Value> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> ToonResult<Value> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> ToonResult<Value> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> ToonResult<Value> {
+        Ok(number_value(v))
+    }
+
+    fn serialize_i128(self, v: i128) -> ToonResult<Value> {
+        i128_value(v)
+    }
+
+    fn serialize_u8(self, v: u8) -> ToonResult<Value> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> ToonResult<Value> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> ToonResult<Value> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> ToonResult<Value> {
+        Ok(number_value(v))
+    }
+
+    fn serialize_u128(self, v: u128) -> ToonResult<Value> {
+        u128_value(v)
+    }
+
+    fn serialize_f32(self, v: f32) -> ToonResult<Value> {
+        self.serialize_f64(v as f64)
+    }
+
+    fn serialize_f64(self, v: f64) -> ToonResult<Value> {
+        Ok(number_value(v))
+    }
+
+    fn serialize_char(self, v: char) -> ToonResult<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> ToonResult<Value> {
+        Ok(Value::String(v.to_string()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> ToonResult<Value> {
+        Ok(Value::Array(v.iter().map(|&b| number_value(b)).collect()))
+    }
+
+    fn serialize_none(self) -> ToonResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> ToonResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> ToonResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> ToonResult<Value> {
+        Ok(Value::Null)
+    }
+
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> ToonResult<Value> {
+        Ok(Value::String(variant.to_string()))
+    }
+
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        value: &T,
+    ) -> ToonResult<Value> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        value: &T,
+    ) -> ToonResult<Value> {
+        let mut obj = Object::new();
+        obj.insert(variant.to_string(), to_value(value)?);
+        Ok(Value::Object(obj))
+    }
+
+    fn serialize_seq(self, len: Option<usize>) -> ToonResult<SerializeVec> {
+        Ok(SerializeVec {
+            vec: Vec::with_capacity(len.unwrap_or(0)),
+        })
+    }
+
+    fn serialize_tuple(self, len: usize) -> ToonResult<SerializeVec> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> ToonResult<SerializeVec> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        len: usize,
+    ) -> ToonResult<SerializeTupleVariant> {
+        Ok(SerializeTupleVariant {
+            name: variant,
+            vec: Vec::with_capacity(len),
+        })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> ToonResult<SerializeMap> {
+        Ok(SerializeMap {
+            map: Object::new(),
+            next_key: None,
+        })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> ToonResult<SerializeMap> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> ToonResult<SerializeStructVariant> {
+        Ok(SerializeStructVariant {
+            name: variant,
+            map: Object::new(),
+        })
+    }
+}
+
+pub(crate) struct SerializeVec {
+    vec: Vec<Value>,
+}
+
+impl ser::SerializeSeq for SerializeVec {
+    type Ok = Value;
+    type Error = ToonError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        self.vec.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> ToonResult<Value> {
+        Ok(Value::Array(self.vec))
+    }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+    type Ok = Value;
+    type Error = ToonError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> ToonResult<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+    type Ok = Value;
+    type Error = ToonError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        ser::SerializeSeq::serialize_element(self, value)
+    }
+
+    fn end(self) -> ToonResult<Value> {
+        ser::SerializeSeq::end(self)
+    }
+}
+
+pub(crate) struct SerializeTupleVariant {
+    name: &'static str,
+    vec: Vec<Value>,
+}
+
+impl ser::SerializeTupleVariant for SerializeTupleVariant {
+    type Ok = Value;
+    type Error = ToonError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        self.vec.push(to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> ToonResult<Value> {
+        let mut obj = Object::new();
+        obj.insert(self.name.to_string(), Value::Array(self.vec));
+        Ok(Value::Object(obj))
+    }
+}
+
+pub(crate) struct SerializeMap {
+    map: Object,
+    next_key: Option<String>,
+}
+
+impl ser::SerializeMap for SerializeMap {
+    type Ok = Value;
+    type Error = ToonError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> ToonResult<()> {
+        self.next_key = Some(key.serialize(MapKeySerializer)?);
+        Ok(())
+    }
+
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> ToonResult<()> {
+        let key = self.next_key.take().ok_or_else(|| {
+            ToonError::SerializationError("serialize_value called before serialize_key".into())
+        })?;
+        self.map.insert(key, to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> ToonResult<Value> {
+        Ok(Value::Object(self.map))
+    }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+    type Ok = Value;
+    type Error = ToonError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ToonResult<()> {
+        self.map.insert(key.to_string(), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> ToonResult<Value> {
+        #[cfg(feature = "arbitrary_precision")]
+        if let Some(Value::String(text)) = self.map.get(super::NUMBER_TOKEN) {
+            return decimal_value(text);
+        }
+        Ok(Value::Object(self.map))
+    }
+}
+
+pub(crate) struct SerializeStructVariant {
+    name: &'static str,
+    map: Object,
+}
+
+impl ser::SerializeStructVariant for SerializeStructVariant {
+    type Ok = Value;
+    type Error = ToonError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> ToonResult<()> {
+        self.map.insert(key.to_string(), to_value(value)?);
+        Ok(())
+    }
+
+    fn end(self) -> ToonResult<Value> {
+        let mut obj = Object::new();
+        obj.insert(self.name.to_string(), Value::Object(self.map));
+        Ok(Value::Object(obj))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Serialize;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_to_value_matches_serde_json() {
+        #[derive(Serialize)]
+        enum Shape {
+            Unit,
+            Circle(f64),
+            Rect { w: u32, h: u32 },
+            Pair(i8, i8),
+        }
+
+        let shapes = vec![
+            Shape::Unit,
+            Shape::Circle(1.5),
+            Shape::Rect { w: 2, h: 3 },
+            Shape::Pair(-1, 1),
+        ];
+
+        let expected = Value::from(serde_json::to_value(&shapes).unwrap());
+        assert_eq!(to_value(&shapes).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_to_value_normalizes_numbers() {
+        assert_eq!(to_value(&f64::NAN).unwrap(), Value::Null);
+        assert_eq!(to_value(&-0.0f64).unwrap(), Value::from(json!(0)));
+        assert_eq!(to_value(&u64::MAX).unwrap(), Value::from(json!(u64::MAX)));
+        assert!(to_value(&u128::MAX).is_err());
+    }
+}
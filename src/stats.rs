@@ -0,0 +1,262 @@
+//! Size and token statistics comparing JSON and TOON encodings.
+
+use std::fmt;
+
+use serde_json::Value;
+
+use crate::{
+    encode::encode,
+    error::{
+        ToonError,
+        ToonResult,
+    },
+    types::EncodeOptions,
+};
+
+/// Counts the tokens a model would see for a piece of text.
+///
+/// Implement this to plug in a real tokenizer; [`HeuristicCounter`] is used
+/// by default.
+pub trait TokenCounter {
+    /// Number of tokens in `text`.
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+impl<F: Fn(&str) -> usize> TokenCounter for F {
+    fn count_tokens(&self, text: &str) -> usize {
+        self(text)
+    }
+}
+
+/// Offline token estimate modeled on common BPE tokenizers.
+///
+/// Words count as one token per four characters, digits as one token per
+/// three, and each punctuation character or newline as one token. A single
+/// space before a word is merged into it; any other run of spaces is one
+/// token. This is only an estimate, but it is consistent enough to compare
+/// formats with each other.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicCounter;
+
+#[derive(PartialEq)]
+enum Class {
+    Word,
+    Digit,
+    Space,
+    Newline,
+    Symbol,
+}
+
+fn classify(ch: char) -> Class {
+    if ch.is_ascii_digit() {
+        Class::Digit
+    } else if ch.is_alphabetic() || ch == '_' {
+        Class::Word
+    } else if ch == '\n' {
+        Class::Newline
+    } else if ch.is_whitespace() {
+        Class::Space
+    } else {
+        Class::Symbol
+    }
+}
+
+impl TokenCounter for HeuristicCounter {
+    fn count_tokens(&self, text: &str) -> usize {
+        let mut tokens = 0;
+        let mut chars = text.chars().peekable();
+
+        while let Some(ch) = chars.next() {
+            let class = classify(ch);
+            let mut len: usize = 1;
+            while class != Class::Symbol
+                && class != Class::Newline
+                && chars.peek().is_some_and(|&next| classify(next) == class)
+            {
+                chars.next();
+                len += 1;
+            }
+
+            tokens += match class {
+                Class::Word => len.div_ceil(4),
+                Class::Digit => len.div_ceil(3),
+                Class::Symbol | Class::Newline => 1,
+                // A lone space is absorbed by the word that follows it.
+                Class::Space => {
+                    let next_is_word = chars
+                        .peek()
+                        .is_some_and(|&next| classify(next) == Class::Word);
+                    usize::from(len > 1 || !next_is_word)
+                }
+            };
+        }
+
+        tokens
+    }
+}
+
+/// Size of one encoding of a value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Measurement {
+    pub bytes: usize,
+    pub chars: usize,
+    pub tokens: usize,
+}
+
+impl Measurement {
+    /// Measure `text` with the given token counter.
+    pub fn of(text: &str, counter: &dyn TokenCounter) -> Self {
+        Self {
+            bytes: text.len(),
+            chars: text.chars().count(),
+            tokens: counter.count_tokens(text),
+        }
+    }
+}
+
+/// Sizes of a value encoded as compact JSON, pretty JSON and TOON.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Comparison {
+    pub json_compact: Measurement,
+    pub json_pretty: Measurement,
+    pub toon: Measurement,
+}
+
+impl Comparison {
+    /// Percentage of tokens saved by TOON relative to compact JSON.
+    ///
+    /// Negative when TOON uses more tokens.
+    pub fn token_savings(&self) -> f64 {
+        if self.json_compact.tokens == 0 {
+            return 0.0;
+        }
+        let json = self.json_compact.tokens as f64;
+        (json - self.toon.tokens as f64) / json * 100.0
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<16} {:>10} {:>10} {:>10}",
+            "Format", "Bytes", "Chars", "Tokens"
+        )?;
+        for (name, m) in [
+            ("JSON (compact)", &self.json_compact),
+            ("JSON (pretty)", &self.json_pretty),
+            ("TOON", &self.toon),
+        ] {
+            writeln!(
+                f,
+                "{:<16} {:>10} {:>10} {:>10}",
+                name, m.bytes, m.chars, m.tokens
+            )?;
+        }
+        write!(
+            f,
+            "TOON saves {:.1}% tokens vs compact JSON",
+            self.token_savings()
+        )
+    }
+}
+
+/// Compare the size of `value` as compact JSON, pretty JSON and TOON, using
+/// [`HeuristicCounter`] to estimate tokens.
+///
+/// # Examples
+///
+/// ```
+/// use rtoon::{
+///     stats,
+///     EncodeOptions,
+/// };
+/// use serde_json::json;
+///
+/// let data = json!({
+///     "users": [
+///         {"id": 1, "name": "Alice", "role": "admin"},
+///         {"id": 2, "name": "Bob", "role": "user"}
+///     ]
+/// });
+/// let comparison = stats::compare(&data, &EncodeOptions::default())?;
+/// assert!(comparison.toon.tokens < comparison.json_compact.tokens);
+/// println!("{}", comparison);
+/// # Ok::<(), rtoon::ToonError>(())
+/// ```
+pub fn compare(value: &Value, options: &EncodeOptions) -> ToonResult<Comparison> {
+    compare_with(value, options, &HeuristicCounter)
+}
+
+/// Like [`compare`], counting tokens with the given counter.
+pub fn compare_with(
+    value: &Value,
+    options: &EncodeOptions,
+    counter: &dyn TokenCounter,
+) -> ToonResult<Comparison> {
+    let compact =
+        serde_json::to_string(value).map_err(|e| ToonError::SerializationError(e.to_string()))?;
+    let pretty = serde_json::to_string_pretty(value)
+        .map_err(|e| ToonError::SerializationError(e.to_string()))?;
+    let toon = encode(value, options)?;
+
+    Ok(Comparison {
+        json_compact: Measurement::of(&compact, counter),
+        json_pretty: Measurement::of(&pretty, counter),
+        toon: Measurement::of(&toon, counter),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_heuristic_counter() {
+        let counter = HeuristicCounter;
+        assert_eq!(counter.count_tokens(""), 0);
+        assert_eq!(counter.count_tokens("name"), 1);
+        assert_eq!(counter.count_tokens("hello world"), 4);
+        assert_eq!(counter.count_tokens("12345"), 2);
+        assert_eq!(counter.count_tokens("{\"a\":1}"), 7);
+        assert_eq!(counter.count_tokens("a:\n  b: 1"), 8);
+    }
+
+    #[test]
+    fn test_compare() {
+        let value = json!({
+            "items": [
+                {"sku": "A1", "qty": 2, "price": 9.99},
+                {"sku": "B2", "qty": 1, "price": 14.5}
+            ]
+        });
+        let comparison = compare(&value, &EncodeOptions::default()).unwrap();
+
+        let toon = encode(&value, &EncodeOptions::default()).unwrap();
+        assert_eq!(comparison.toon.bytes, toon.len());
+        assert_eq!(
+            comparison.json_compact.bytes,
+            serde_json::to_string(&value).unwrap().len()
+        );
+        assert!(comparison.json_pretty.bytes > comparison.json_compact.bytes);
+        assert!(comparison.toon.tokens < comparison.json_compact.tokens);
+        assert!(comparison.token_savings() > 0.0);
+
+        let table = comparison.to_string();
+        assert!(table.starts_with("Format"));
+        assert!(table.contains("JSON (pretty)"));
+    }
+
+    #[test]
+    fn test_custom_counter() {
+        let value = json!({"a": "é"});
+        let by_char = |text: &str| text.chars().count();
+        let comparison = compare_with(&value, &EncodeOptions::default(), &by_char).unwrap();
+
+        assert_eq!(comparison.toon.tokens, comparison.toon.chars);
+        assert_eq!(comparison.toon.chars, 4);
+        assert_eq!(comparison.toon.bytes, 5);
+    }
+}
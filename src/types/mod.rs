@@ -5,8 +5,12 @@ mod value;
 pub use delimeter::Delimiter;
 pub use options::{
     DecodeOptions,
+    DuplicateKeys,
     EncodeOptions,
+    FloatFormat,
+    FormatOptions,
     Indent,
+    Limits,
 };
 pub use value::{
     IntoJsonValue,
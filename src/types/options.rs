@@ -1,12 +1,19 @@
 use crate::{
-    constants::DEFAULT_INDENT,
+    constants::{
+        DEFAULT_INDENT,
+        MAX_DEPTH,
+    },
+    types::Number,
     Delimiter,
 };
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Indent {
     Spaces(usize),
     Tabs,
+    /// When decoding, use the indentation of the first indented line for the
+    /// rest of the document. Encoders indent by two spaces.
+    Auto,
 }
 
 impl Default for Indent {
@@ -22,6 +29,7 @@ impl Indent {
         }
 
         match self {
+            Indent::Auto => Indent::default().get_string(depth),
             Indent::Spaces(count) => {
                 if *count > 0 {
                     " ".repeat(*count * depth)
@@ -34,12 +42,63 @@ impl Indent {
     }
 }
 
+/// How the encoder writes numbers that are not integers.
+///
+/// Every format writes plain decimals without an exponent, and a number that
+/// rounds to zero has no minus sign. Precision is capped at 1100 digits,
+/// more than any `f64` needs.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FloatFormat {
+    /// The shortest decimal that reads back as the same `f64`, e.g.
+    /// `0.30000000000000004` for `0.1 + 0.2`.
+    #[default]
+    Shortest,
+    /// Round to this many digits after the decimal point, e.g. `0.30` for
+    /// two.
+    FixedDecimals(usize),
+    /// Round to this many significant digits, e.g. `0.300` for three or
+    /// `123000` for `123456.7`.
+    SignificantDigits(usize),
+}
+
+/// The most digits [`FloatFormat`] writes; the exact decimal expansion of an
+/// `f64` never has more than 1074 digits after the point.
+const MAX_FLOAT_DIGITS: usize = 1100;
+
+/// Write `f` rounded to `digits` significant digits, without an exponent.
+fn format_significant(f: f64, digits: usize) -> String {
+    // `{:e}` does the rounding, giving text such as `-1.23e-7`.
+    let scientific = format!("{:.*e}", digits.max(1) - 1, f);
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let exponent: i64 = exponent.parse().unwrap_or(0);
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+
+    let digits: String = mantissa.chars().filter(|&c| c != '.').collect();
+    let len = digits.len() as i64;
+    let body = if exponent < 0 {
+        format!("0.{}{}", "0".repeat((-exponent - 1) as usize), digits)
+    } else if exponent + 1 >= len {
+        format!("{}{}", digits, "0".repeat((exponent + 1 - len) as usize))
+    } else {
+        let (int, frac) = digits.split_at((exponent + 1) as usize);
+        format!("{}.{}", int, frac)
+    };
+    format!("{}{}", sign, body)
+}
+
 /// Options for encoding JSON values to TOON format.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct EncodeOptions {
     pub delimiter: Delimiter,
     pub length_marker: Option<char>,
     pub indent: Indent,
+    pub auto_delimiter: bool,
+    pub key_folding: bool,
+    pub flatten_tabular: bool,
+    pub float_format: FloatFormat,
 }
 
 impl Default for EncodeOptions {
@@ -48,6 +107,10 @@ impl Default for EncodeOptions {
             delimiter: Delimiter::Comma,
             length_marker: None,
             indent: Indent::default(),
+            auto_delimiter: false,
+            key_folding: false,
+            flatten_tabular: false,
+            float_format: FloatFormat::default(),
         }
     }
 }
@@ -64,6 +127,36 @@ impl EncodeOptions {
         self
     }
 
+    /// Enable or disable choosing the delimiter separately for each array,
+    /// picking the one that requires the fewest quoted values. Ties go to the
+    /// configured delimiter. Arrays that use another delimiter declare it in
+    /// their header, e.g. `[2|]`.
+    pub fn with_auto_delimiter(mut self, enabled: bool) -> Self {
+        self.auto_delimiter = enabled;
+        self
+    }
+
+    /// Enable or disable folding chains of single-key objects into dotted
+    /// keys, e.g. `a.b.c: 1` for `{"a": {"b": {"c": 1}}}`. Only keys that are
+    /// valid unquoted identifiers are folded, and literal keys containing `.`
+    /// are quoted. Decode with [`DecodeOptions::with_expand_paths`] to
+    /// restore the nesting.
+    pub fn with_key_folding(mut self, fold: bool) -> Self {
+        self.key_folding = fold;
+        self
+    }
+
+    /// Enable or disable writing arrays of objects with differing keys or
+    /// nested objects in tabular form when possible. The columns are the
+    /// union of all keys, with `~` in cells whose field is absent from the
+    /// row, and the fields of nested objects that hold only primitives
+    /// become dotted columns like `address.city`. Decode with
+    /// [`DecodeOptions::with_flatten_tabular`] to restore the objects.
+    pub fn with_flatten_tabular(mut self, flatten: bool) -> Self {
+        self.flatten_tabular = flatten;
+        self
+    }
+
     /// Set a character prefix for array length markers (e.g., `#` for `[#3]`).
     pub fn with_length_marker(mut self, marker: char) -> Self {
         self.length_marker = Some(marker);
@@ -96,6 +189,120 @@ impl EncodeOptions {
         self.indent = Indent::Tabs;
         self
     }
+
+    /// Set how numbers that are not integers are written, e.g.
+    /// `FloatFormat::FixedDecimals(2)` to write `0.1 + 0.2` as `0.30`.
+    pub fn with_float_format(mut self, format: FloatFormat) -> Self {
+        self.float_format = format;
+        self
+    }
+
+    /// Format a number with the configured [`FloatFormat`]. Integers are
+    /// written in full.
+    pub fn format_number(&self, n: &Number) -> String {
+        let f = match n {
+            Number::Float(f) => *f,
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(s) if s.contains(['.', 'e', 'E']) => n.as_f64().unwrap_or_default(),
+            _ => return n.to_string(),
+        };
+
+        let text = match self.float_format {
+            FloatFormat::Shortest => return n.to_string(),
+            FloatFormat::FixedDecimals(decimals) => {
+                format!("{:.*}", decimals.min(MAX_FLOAT_DIGITS), f)
+            }
+            FloatFormat::SignificantDigits(digits) => {
+                format_significant(f, digits.min(MAX_FLOAT_DIGITS))
+            }
+        };
+        match text.strip_prefix('-') {
+            Some(rest) if rest.chars().all(|c| c == '0' || c == '.') => rest.to_string(),
+            _ => text,
+        }
+    }
+}
+
+/// What the decoder does when an object repeats a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    /// Fail with a parse error that gives the lines of both keys.
+    Error,
+    /// Keep the first value and ignore later ones.
+    FirstWins,
+    /// Keep the last value, in the position of the first key.
+    LastWins,
+    /// Collect every value for the key into an array, in order.
+    CollectArray,
+}
+
+/// Bounds on the resources a document may claim, for decoding untrusted
+/// input. Exceeding one fails with
+/// [`ToonError::LimitExceeded`](crate::ToonError::LimitExceeded). Only the
+/// nesting depth is limited by default.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    /// Deepest nesting of objects and arrays.
+    pub max_depth: usize,
+    /// Size of the whole document in bytes.
+    pub max_document_bytes: usize,
+    /// Length in bytes of any key or string value.
+    pub max_string_length: usize,
+    /// Largest `[N]` an array header may declare.
+    pub max_array_length: usize,
+    /// Number of keys in a single object, or fields in a tabular header.
+    pub max_object_keys: usize,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_depth: MAX_DEPTH,
+            max_document_bytes: usize::MAX,
+            max_string_length: usize::MAX,
+            max_array_length: usize::MAX,
+            max_object_keys: usize::MAX,
+        }
+    }
+}
+
+impl Limits {
+    /// Create limits with defaults (nesting depth 256, nothing else bounded).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the deepest nesting of objects and arrays.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Set the largest document, in bytes.
+    pub fn with_max_document_bytes(mut self, bytes: usize) -> Self {
+        self.max_document_bytes = bytes;
+        self
+    }
+
+    /// Set the longest key or string value, in bytes.
+    pub fn with_max_string_length(mut self, length: usize) -> Self {
+        self.max_string_length = length;
+        self
+    }
+
+    /// Set the largest `[N]` an array header may declare. Checked when the
+    /// header is read, before any elements.
+    pub fn with_max_array_length(mut self, length: usize) -> Self {
+        self.max_array_length = length;
+        self
+    }
+
+    /// Set the most keys a single object, or fields a tabular header, may
+    /// have.
+    pub fn with_max_object_keys(mut self, keys: usize) -> Self {
+        self.max_object_keys = keys;
+        self
+    }
 }
 
 /// Options for decoding TOON format to JSON values.
@@ -104,6 +311,15 @@ pub struct DecodeOptions {
     pub delimiter: Option<Delimiter>,
     pub strict: bool,
     pub coerce_types: bool,
+    pub expand_paths: bool,
+    pub flatten_tabular: bool,
+    pub repair: bool,
+    pub indent: Indent,
+    /// How repeated keys are handled. `None` uses [`DuplicateKeys::Error`]
+    /// in strict mode and [`DuplicateKeys::LastWins`] otherwise, or when
+    /// repairing.
+    pub duplicate_keys: Option<DuplicateKeys>,
+    pub limits: Limits,
 }
 
 impl Default for DecodeOptions {
@@ -112,6 +328,12 @@ impl Default for DecodeOptions {
             delimiter: None,
             strict: true,
             coerce_types: true,
+            expand_paths: false,
+            flatten_tabular: false,
+            repair: false,
+            indent: Indent::default(),
+            duplicate_keys: None,
+            limits: Limits::default(),
         }
     }
 }
@@ -140,6 +362,121 @@ impl DecodeOptions {
         self.coerce_types = coerce;
         self
     }
+
+    /// Enable or disable path expansion: unquoted dotted keys like `a.b.c`
+    /// become nested objects. Assigning the same path twice is an error.
+    pub fn with_expand_paths(mut self, expand: bool) -> Self {
+        self.expand_paths = expand;
+        self
+    }
+
+    /// Enable or disable reading flattened tabular arrays: `~` cells are
+    /// left out of the row and unquoted dotted columns become nested objects.
+    pub fn with_flatten_tabular(mut self, flatten: bool) -> Self {
+        self.flatten_tabular = flatten;
+        self
+    }
+
+    /// Set the indentation the document uses. In strict mode each line must
+    /// be indented by a multiple of `Indent::Spaces(n)`, only by tabs for
+    /// `Indent::Tabs`, or consistently with the first indented line for
+    /// `Indent::Auto` (SPEC §14).
+    pub fn with_indent(mut self, indent: Indent) -> Self {
+        self.indent = indent;
+        self
+    }
+
+    /// Set how an object that repeats a key is decoded. Keys that
+    /// `expand_paths` splits into paths are merged as before.
+    pub fn with_duplicate_keys(mut self, policy: DuplicateKeys) -> Self {
+        self.duplicate_keys = Some(policy);
+        self
+    }
+
+    /// Set the resource limits for untrusted input.
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The duplicate key policy in effect.
+    pub(crate) fn duplicate_key_policy(&self) -> DuplicateKeys {
+        self.duplicate_keys
+            .unwrap_or(if self.strict && !self.repair {
+                DuplicateKeys::Error
+            } else {
+                DuplicateKeys::LastWins
+            })
+    }
+
+    /// Decode leniently, fixing the mistakes language models commonly make
+    /// when writing TOON: `[N]` lengths are taken from the content, tabular
+    /// rows with too few cells are padded with `null` and extra cells are
+    /// dropped, drifting indentation is normalized, a repeated key keeps its
+    /// last value unless a [`DuplicateKeys`] policy is set, and markdown code
+    /// fences (and any text outside them) are removed. Use
+    /// [`decode_with_repairs`](crate::decode_with_repairs) to find out which
+    /// fixes were made.
+    pub fn repair(mut self) -> Self {
+        self.repair = true;
+        self
+    }
+}
+
+/// Options for [`format`](crate::format::format).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub delimiter: Delimiter,
+    pub length_marker: Option<char>,
+    pub indent: Indent,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: Delimiter::Comma,
+            length_marker: None,
+            indent: Indent::default(),
+        }
+    }
+}
+
+impl FormatOptions {
+    /// Create new formatting options with defaults.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the delimiter to rewrite every array with.
+    pub fn with_delimiter(mut self, delimiter: Delimiter) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// Set a character prefix for array length markers (e.g., `#` for `[#3]`).
+    pub fn with_length_marker(mut self, marker: char) -> Self {
+        self.length_marker = Some(marker);
+        self
+    }
+
+    /// Set the indentation for nested structures.
+    pub fn with_indent(mut self, style: Indent) -> Self {
+        self.indent = style;
+        self
+    }
+
+    /// The encoding options that produce this format.
+    pub fn encode_options(&self) -> EncodeOptions {
+        EncodeOptions {
+            delimiter: self.delimiter,
+            length_marker: self.length_marker,
+            indent: self.indent,
+            auto_delimiter: false,
+            key_folding: false,
+            flatten_tabular: false,
+            float_format: FloatFormat::default(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -166,6 +503,69 @@ mod tests {
         assert_eq!(opts.indent, Indent::Spaces(2));
     }
 
+    #[test]
+    fn test_encode_options_float_format() {
+        let format = |format, n: f64| {
+            EncodeOptions::new()
+                .with_float_format(format)
+                .format_number(&Number::from(n))
+        };
+
+        assert_eq!(
+            format(FloatFormat::Shortest, 0.1 + 0.2),
+            "0.30000000000000004"
+        );
+        assert_eq!(
+            format(FloatFormat::Shortest, 1e20),
+            "100000000000000000000.0"
+        );
+        assert_eq!(format(FloatFormat::Shortest, 1e-7), "0.0000001");
+
+        assert_eq!(format(FloatFormat::FixedDecimals(2), 0.1 + 0.2), "0.30");
+        assert_eq!(format(FloatFormat::FixedDecimals(0), 2.75), "3");
+        assert_eq!(format(FloatFormat::FixedDecimals(2), -0.001), "0.00");
+        assert_eq!(
+            format(FloatFormat::FixedDecimals(1), 1e20),
+            "100000000000000000000.0"
+        );
+
+        assert_eq!(
+            format(FloatFormat::SignificantDigits(3), 0.1 + 0.2),
+            "0.300"
+        );
+        assert_eq!(
+            format(FloatFormat::SignificantDigits(3), 123456.7),
+            "123000"
+        );
+        assert_eq!(
+            format(FloatFormat::SignificantDigits(2), -0.000123456),
+            "-0.00012"
+        );
+        assert_eq!(format(FloatFormat::SignificantDigits(4), 12.3456), "12.35");
+        assert_eq!(format(FloatFormat::SignificantDigits(1), -0.0), "0");
+
+        let opts = EncodeOptions::new().with_float_format(FloatFormat::FixedDecimals(2));
+        assert_eq!(opts.format_number(&Number::from(42u64)), "42");
+        assert_eq!(opts.format_number(&Number::from(-7i64)), "-7");
+    }
+
+    #[test]
+    fn test_decode_options_limits() {
+        let opts = DecodeOptions::new();
+        assert_eq!(opts.limits.max_depth, MAX_DEPTH);
+        assert_eq!(opts.limits.max_array_length, usize::MAX);
+
+        let limits = Limits::new()
+            .with_max_depth(8)
+            .with_max_document_bytes(1 << 20)
+            .with_max_string_length(4096)
+            .with_max_array_length(10_000)
+            .with_max_object_keys(64);
+        let opts = DecodeOptions::new().with_limits(limits);
+        assert_eq!(opts.limits, limits);
+        assert_eq!(opts.limits.max_document_bytes, 1 << 20);
+    }
+
     #[test]
     fn test_decode_options_coerce_types() {
         let opts = DecodeOptions::new();
@@ -9,10 +9,16 @@ use std::{
 use indexmap::IndexMap;
 
 #[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Number {
     PosInt(u64),
     NegInt(i64),
     Float(f64),
+    /// A number kept as its decimal text because an `f64` would change it,
+    /// such as `1.50` or a 30-digit integer. Only with the
+    /// `arbitrary_precision` feature.
+    #[cfg(feature = "arbitrary_precision")]
+    Decimal(String),
 }
 
 impl Number {
@@ -32,6 +38,8 @@ impl Number {
                 let i = *f as i64;
                 i as f64 == *f && i != i64::MAX
             }
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(s) => Self::decimal_as_float(s).is_i64(),
         }
     }
 
@@ -43,11 +51,18 @@ impl Number {
                 let u = *f as u64;
                 u as f64 == *f
             }
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(s) => Self::decimal_as_float(s).is_u64(),
         }
     }
 
     pub fn is_f64(&self) -> bool {
-        matches!(self, Number::Float(_))
+        match self {
+            Number::Float(_) => true,
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(_) => true,
+            _ => false,
+        }
     }
 
     pub fn as_i64(&self) -> Option<i64> {
@@ -68,6 +83,8 @@ impl Number {
                     None
                 }
             }
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(s) => Self::decimal_as_float(s).as_i64(),
         }
     }
 
@@ -87,6 +104,8 @@ impl Number {
                     None
                 }
             }
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(s) => Self::decimal_as_float(s).as_u64(),
         }
     }
 
@@ -95,6 +114,8 @@ impl Number {
             Number::PosInt(u) => Some(*u as f64),
             Number::NegInt(i) => Some(*i as f64),
             Number::Float(f) => Some(*f),
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(s) => s.parse().ok(),
         }
     }
 
@@ -102,8 +123,68 @@ impl Number {
         match self {
             Number::PosInt(_) | Number::NegInt(_) => true,
             Number::Float(f) => f.fract() == 0.0,
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(s) => Self::decimal_as_float(s).is_integer(),
         }
     }
+
+    /// Convert a `serde_json::Number` that fits in neither `u64` nor `i64`.
+    fn from_fraction(n: &serde_json::Number) -> Option<Number> {
+        #[cfg(feature = "arbitrary_precision")]
+        {
+            let text = n.to_string();
+            match text.parse::<f64>() {
+                Ok(f) if f.to_string() == text => Some(Number::Float(f)),
+                _ => Some(Number::Decimal(text)),
+            }
+        }
+        #[cfg(not(feature = "arbitrary_precision"))]
+        n.as_f64().map(Number::from)
+    }
+
+    #[cfg(feature = "arbitrary_precision")]
+    fn decimal_as_float(s: &str) -> Number {
+        Number::Float(s.parse().unwrap_or(f64::NAN))
+    }
+}
+
+/// Write decimal text such as `1.5e-3` without its exponent, as `0.0015`.
+/// Every digit of the mantissa is kept.
+#[cfg(feature = "arbitrary_precision")]
+fn write_plain_decimal(f: &mut fmt::Formatter<'_>, s: &str) -> fmt::Result {
+    let Some((mantissa, exponent)) = s.split_once(['e', 'E']) else {
+        return f.write_str(s);
+    };
+    let exponent: i64 = exponent.parse().unwrap_or(0);
+    let (sign, mantissa) = match mantissa.strip_prefix('-') {
+        Some(mantissa) => ("-", mantissa),
+        None => ("", mantissa),
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{}{}", int, frac);
+
+    // The position of the decimal point within `digits` once shifted.
+    let point = int.len() as i64 + exponent;
+    let (int, frac) = if point <= 0 {
+        (
+            String::new(),
+            format!("{}{}", "0".repeat(-point as usize), digits),
+        )
+    } else if point as usize >= digits.len() {
+        let zeros = "0".repeat(point as usize - digits.len());
+        (format!("{}{}", digits, zeros), String::new())
+    } else {
+        let (int, frac) = digits.split_at(point as usize);
+        (int.to_string(), frac.to_string())
+    };
+
+    let int = int.trim_start_matches('0');
+    f.write_str(sign)?;
+    f.write_str(if int.is_empty() { "0" } else { int })?;
+    if !frac.is_empty() {
+        write!(f, ".{}", frac)?;
+    }
+    Ok(())
 }
 
 impl fmt::Display for Number {
@@ -113,11 +194,13 @@ impl fmt::Display for Number {
             Number::NegInt(i) => write!(f, "{}", i),
             Number::Float(fl) => {
                 if fl.fract() == 0.0 && fl.is_finite() {
-                    write!(f, "{}.0", *fl as i64)
+                    write!(f, "{}.0", fl)
                 } else {
                     write!(f, "{}", fl)
                 }
             }
+            #[cfg(feature = "arbitrary_precision")]
+            Number::Decimal(s) => write_plain_decimal(f, s),
         }
     }
 }
@@ -451,8 +534,8 @@ impl From<serde_json::Value> for JsonValue {
                     JsonValue::Number(Number::from(i))
                 } else if let Some(u) = n.as_u64() {
                     JsonValue::Number(Number::from(u))
-                } else if let Some(f) = n.as_f64() {
-                    JsonValue::Number(Number::from(f))
+                } else if let Some(number) = Number::from_fraction(&n) {
+                    JsonValue::Number(number)
                 } else {
                     JsonValue::Null
                 }
@@ -483,6 +566,11 @@ impl From<JsonValue> for serde_json::Value {
         match value {
             JsonValue::Null => serde_json::Value::Null,
             JsonValue::Bool(b) => serde_json::Value::Bool(b),
+            #[cfg(feature = "arbitrary_precision")]
+            JsonValue::Number(Number::Decimal(s)) => s
+                .parse()
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
             JsonValue::Number(n) => {
                 if let Some(i) = n.as_i64() {
                     serde_json::Value::Number(i.into())
@@ -33,6 +33,16 @@ pub enum QuotingContext {
 /// Normalize a JSON value (converts NaN/Infinity to null, -0 to 0).
 pub fn normalize(value: Value) -> Value {
     match value {
+        // Decimal text is always finite, so only a negative zero changes.
+        #[cfg(feature = "arbitrary_precision")]
+        Value::Number(Number::Decimal(s)) => {
+            let mantissa = s.split(['e', 'E']).next().unwrap_or_default();
+            if mantissa.starts_with('-') && mantissa[1..].chars().all(|c| c == '0' || c == '.') {
+                Value::Number(Number::from(0u64))
+            } else {
+                Value::Number(Number::Decimal(s))
+            }
+        }
         Value::Number(n) => {
             // Handle NegInt(0) case - convert to PosInt(0)
             if let Number::NegInt(0) = n {
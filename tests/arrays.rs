@@ -1,6 +1,11 @@
 use rtoon::{
+    decode,
     decode_default,
+    encode,
     encode_default,
+    to_toon,
+    DecodeOptions,
+    EncodeOptions,
 };
 use serde_json::json;
 
@@ -87,3 +92,78 @@ fn test_large_arrays() {
     let decoded = decode_default(&encoded).unwrap();
     assert_eq!(large_tabular, decoded);
 }
+
+#[test]
+fn test_flattened_tabular() {
+    let data = json!({
+        "users": [
+            {"id": 1, "name": "Alice", "address": {"city": "Paris", "zip": "75001"}},
+            {"id": 2, "email": "bob@example.com", "address": {"city": "Rome"}},
+            {"id": 3, "name": "~", "email": null}
+        ]
+    });
+
+    let encoded = encode(&data, &EncodeOptions::new().with_flatten_tabular(true)).unwrap();
+    assert_eq!(
+        encoded,
+        "users[3]{id,name,address.city,address.zip,email}:\n  1,Alice,Paris,\"75001\",~\n  \
+         2,~,Rome,~,bob@example.com\n  3,\"~\",~,~,null"
+    );
+
+    let options = DecodeOptions::new().with_flatten_tabular(true);
+    assert_eq!(decode(&encoded, &options).unwrap(), data);
+
+    // Quoted columns stay literal; a column nested under another is an error.
+    assert_eq!(
+        decode("t[1]{\"a.b\",c.d}:\n  1,2", &options).unwrap(),
+        json!({"t": [{"a.b": 1, "c": {"d": 2}}]})
+    );
+    assert!(decode("t[1]{a,a.b}:\n  1,2", &options).is_err());
+
+    // Arrays that cannot be flattened keep the list form.
+    let data = json!({"items": [{"a": 1}, {"a": {"b": [1]}}]});
+    let flattened = encode(&data, &EncodeOptions::new().with_flatten_tabular(true)).unwrap();
+    assert_eq!(flattened, encode_default(&data).unwrap());
+
+    // A `~` string in a uniform table is quoted too.
+    let data = json!([{"a": "~"}, {"a": "x"}]);
+    let options = EncodeOptions::new().with_flatten_tabular(true);
+    let encoded = encode(&data, &options).unwrap();
+    assert_eq!(encoded, "[2]{a}:\n  \"~\"\n  x");
+    assert_eq!(to_toon(&data, Some(&options)).unwrap(), encoded);
+    let decoded = decode(&encoded, &DecodeOptions::new().with_flatten_tabular(true)).unwrap();
+    assert_eq!(decoded, data);
+}
+
+#[test]
+fn test_nested_array_indentation() {
+    // Array headers are indented like any other key, once per level.
+    let data = json!({
+        "a": {
+            "b": {
+                "tags": ["x", "y"],
+                "rows": [{"id": 1}, {"id": 2}],
+                "lists": [[1], [2, 3]],
+                "none": []
+            }
+        }
+    });
+
+    let encoded = encode_default(&data).unwrap();
+    assert_eq!(
+        encoded,
+        "a:\n  b:\n    tags[2]: x,y\n    rows[2]{id}:\n      1\n      2\n    lists[2]:\n      - \
+         [1]: 1\n      - [2]: 2,3\n    none[0]:"
+    );
+    assert_eq!(to_toon(&data, None).unwrap(), encoded);
+    assert_eq!(decode_default(&encoded).unwrap(), data);
+
+    let options = EncodeOptions::new().with_spaces(4);
+    let encoded = encode(&data, &options).unwrap();
+    assert_eq!(
+        encoded,
+        "a:\n    b:\n        tags[2]: x,y\n        rows[2]{id}:\n            1\n            2\n        \
+         lists[2]:\n            - [1]: 1\n            - [2]: 2,3\n        none[0]:"
+    );
+    assert_eq!(to_toon(&data, Some(&options)).unwrap(), encoded);
+}
@@ -0,0 +1,207 @@
+#![cfg(feature = "cli")]
+
+use std::{
+    io::Write,
+    process::{
+        Command,
+        Output,
+        Stdio,
+    },
+};
+
+fn toon(args: &[&str], stdin: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_toon"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(stdin.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn test_encode_and_decode() {
+    let json = r#"{"users":[{"id":1,"name":"Alice"},{"id":2,"name":"Bob"}],"tags":["a","b"]}"#;
+
+    let encoded = toon(&["encode"], json);
+    assert!(encoded.status.success());
+    assert_eq!(
+        stdout(&encoded),
+        "users[2]{id,name}:\n  1,Alice\n  2,Bob\ntags[2]: a,b\n"
+    );
+
+    let decoded = toon(&["decode"], &stdout(&encoded));
+    assert!(decoded.status.success());
+    assert_eq!(stdout(&decoded).trim_end(), json);
+}
+
+#[test]
+fn test_options_are_applied() {
+    let json = r#"{"a":{"b":1},"c":[1,2]}"#;
+    let encoded = toon(
+        &[
+            "encode",
+            "--delimiter",
+            "pipe",
+            "--length-marker",
+            "#",
+            "--tabs",
+        ],
+        json,
+    );
+    assert_eq!(stdout(&encoded), "a:\n\tb: 1\nc[#2|]: 1|2\n");
+
+    let encoded = toon(&["encode", "--indent", "4", "-"], json);
+    assert_eq!(stdout(&encoded), "a:\n    b: 1\nc[2]: 1,2\n");
+
+    let decoded = toon(
+        &["decode", "--no-coerce", "--delimiter", "pipe"],
+        "a[2|]: x|y",
+    );
+    assert_eq!(stdout(&decoded).trim_end(), r#"{"a":["x","y"]}"#);
+
+    let repaired = toon(&["decode", "--repair"], "```toon\nitems[3]: a,b\n```");
+    assert_eq!(stdout(&repaired).trim_end(), r#"{"items":["a","b"]}"#);
+
+    let repeated = "a: 1\nb: 2\na: 3";
+    assert_eq!(toon(&["decode"], repeated).status.code(), Some(65));
+    for (policy, expected) in [
+        ("first-wins", r#"{"a":1,"b":2}"#),
+        ("last-wins", r#"{"a":3,"b":2}"#),
+        ("collect-array", r#"{"a":[1,3],"b":2}"#),
+    ] {
+        let decoded = toon(&["decode", "--duplicate-keys", policy], repeated);
+        assert_eq!(stdout(&decoded).trim_end(), expected);
+    }
+
+    for (flag, input) in [
+        ("--max-depth", "a:\n  b:\n    c:\n      d: 1"),
+        ("--max-document-bytes", "a: 1\nb: 2"),
+        ("--max-string-length", "a: hello"),
+        ("--max-array-length", "a[3]: 1,2,3"),
+        ("--max-object-keys", "a: 1\nb: 2\nc: 3"),
+    ] {
+        assert_eq!(toon(&["decode"], input).status.code(), Some(0));
+        let limited = toon(&["decode", flag, "2"], input);
+        assert_eq!(limited.status.code(), Some(65), "{flag}");
+    }
+
+    let nested = "a:\n   b: 1";
+    assert_eq!(toon(&["decode"], nested).status.code(), Some(65));
+    for flags in [["--indent", "3"].as_slice(), &["--auto-indent"]] {
+        let decoded = toon(&[&["decode"], flags].concat(), nested);
+        assert_eq!(stdout(&decoded).trim_end(), r#"{"a":{"b":1}}"#);
+    }
+    let decoded = toon(&["decode", "--tabs"], "a:\n\tb: 1");
+    assert_eq!(stdout(&decoded).trim_end(), r#"{"a":{"b":1}}"#);
+
+    let decoded = toon(&["decode", "--no-strict", "--pretty"], "a: 1");
+    assert_eq!(stdout(&decoded), "{\n  \"a\": 1\n}\n");
+}
+
+#[test]
+fn test_encode_flags() {
+    let json = r#"{"tags":["a,b","c"]}"#;
+    let encoded = toon(&["encode", "--auto-delimiter"], json);
+    assert_eq!(stdout(&encoded), "tags[2|]: a,b|c\n");
+
+    let json = r#"{"x":0.30000000000000004,"y":123456.7}"#;
+    let fixed = toon(&["encode", "--fixed-decimals", "2"], json);
+    assert_eq!(stdout(&fixed), "x: 0.30\ny: 123456.70\n");
+    let significant = toon(&["encode", "--significant-digits", "3"], json);
+    assert_eq!(stdout(&significant), "x: 0.300\ny: 123000\n");
+    for flag in ["--fixed-decimals", "--significant-digits"] {
+        assert_eq!(
+            toon(&["encode", flag, "70000"], json).status.code(),
+            Some(0)
+        );
+    }
+
+    let json = r#"{"a":{"b":{"c":1}}}"#;
+    let folded = toon(&["encode", "--key-folding"], json);
+    assert_eq!(stdout(&folded), "a.b.c: 1\n");
+    let expanded = toon(&["decode", "--expand-paths"], &stdout(&folded));
+    assert_eq!(stdout(&expanded).trim_end(), json);
+
+    let json = r#"[{"id":1,"tag":"x"},{"id":2}]"#;
+    let flattened = toon(&["encode", "--flatten-tabular"], json);
+    assert_eq!(stdout(&flattened), "[2]{id,tag}:\n  1,x\n  2,~\n");
+    let restored = toon(&["decode", "--flatten-tabular"], &stdout(&flattened));
+    assert_eq!(stdout(&restored).trim_end(), json);
+}
+
+#[test]
+fn test_exit_codes() {
+    let bad_toon = toon(&["decode"], "a: 1\nitems[3]: a,b");
+    assert_eq!(bad_toon.status.code(), Some(65));
+    let stderr = String::from_utf8(bad_toon.stderr).unwrap();
+    assert!(stderr.starts_with("error: "));
+
+    let bad_json = toon(&["encode"], "{\"a\": ");
+    assert_eq!(bad_json.status.code(), Some(65));
+
+    let missing = toon(&["decode", "/nonexistent/input.toon"], "");
+    assert_eq!(missing.status.code(), Some(74));
+
+    let usage = toon(&["frobnicate"], "");
+    assert_eq!(usage.status.code(), Some(2));
+}
+
+#[test]
+fn test_fmt() {
+    let input = "a:\n    b[3]: \"x\",y\n";
+    let formatted = toon(&["fmt", "--delimiter", "pipe"], input);
+    assert!(formatted.status.success());
+    assert_eq!(stdout(&formatted), "a:\n  b[2|]: x|y\n");
+
+    let dir = std::env::temp_dir().join(format!("toon-fmt-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let good = dir.join("good.toon");
+    let bad = dir.join("bad.toon");
+    std::fs::write(&good, "a:\n  b[2]: x,y\n").unwrap();
+    std::fs::write(&bad, input).unwrap();
+
+    let check = toon(&["fmt", "--check", good.to_str().unwrap()], "");
+    assert!(check.status.success());
+
+    let check = toon(
+        &[
+            "fmt",
+            "--check",
+            good.to_str().unwrap(),
+            bad.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert_eq!(check.status.code(), Some(1));
+    let stderr = String::from_utf8(check.stderr).unwrap();
+    assert!(stderr.contains("bad.toon is not formatted"));
+    assert!(!stderr.contains("good.toon"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_stats() {
+    let json = r#"{"users":[{"id":1,"name":"Alice"},{"id":2,"name":"Bob"}]}"#;
+    let output = toon(&["stats"], json);
+    assert!(output.status.success());
+
+    let table = stdout(&output);
+    let lines: Vec<&str> = table.lines().collect();
+    assert_eq!(lines.len(), 5);
+    assert!(lines[1].starts_with("JSON (compact)"));
+    assert!(lines[1].contains(&json.len().to_string()));
+    assert!(lines[4].starts_with("TOON saves"));
+}
@@ -60,3 +60,32 @@ fn test_delimiter_in_values() {
     let decoded = decode_default(&encoded).unwrap();
     assert_eq!(data, decoded);
 }
+
+#[test]
+fn test_auto_delimiter() {
+    let data = json!({
+        "prices": ["1,99", "2,49"],
+        "paths": ["a|b", "c"],
+        "plain": ["x", "y"],
+        "rows": [{"name": "Smith,J", "tag": "a"}],
+        "nested": [["p|q", "r,s"], ["t|u"]],
+        "note": "a|b,c"
+    });
+
+    let opts = EncodeOptions::new().with_auto_delimiter(true);
+    let encoded = encode(&data, &opts).unwrap();
+    assert_eq!(
+        encoded,
+        "prices[2|]: 1,99|2,49\npaths[2]: a|b,c\nplain[2]: x,y\nrows[1|]{name|tag}:\n  \
+         Smith,J|a\nnested[2]:\n  - [2\t]: p|q\tr,s\n  - [1]: t|u\nnote: \"a|b,c\""
+    );
+    assert_eq!(decode_default(&encoded).unwrap(), data);
+
+    let opts = EncodeOptions::new()
+        .with_delimiter(Delimiter::Pipe)
+        .with_auto_delimiter(true);
+    let encoded = encode(&data, &opts).unwrap();
+    assert!(encoded.contains("plain[2|]: x|y"));
+    assert!(encoded.contains("paths[2]: a|b,c"));
+    assert_eq!(decode_default(&encoded).unwrap(), data);
+}
@@ -2,7 +2,11 @@ use rtoon::{
     decode,
     decode_default,
     decode_strict,
+    types::Indent,
     DecodeOptions,
+    DuplicateKeys,
+    Limit,
+    Limits,
     ToonError,
 };
 use serde_json::json;
@@ -492,3 +496,283 @@ fn test_error_context_information() {
         }
     }
 }
+
+#[test]
+fn test_indentation_must_match_indent_option() {
+    let line_of = |input: &str, options: &DecodeOptions| match decode(input, options) {
+        Err(ToonError::ParseError { line, message, .. }) => (line, message),
+        other => panic!(
+            "expected an indentation error for {:?}, got {:?}",
+            input, other
+        ),
+    };
+
+    let default = DecodeOptions::default();
+    assert_eq!(line_of("a:\n  b: 1\n   c: 2", &default).0, 3);
+    assert!(line_of("a:\n\tb: 1", &default).1.contains("Tabs"));
+
+    let four = DecodeOptions::new().with_indent(Indent::Spaces(4));
+    assert_eq!(line_of("a:\n  b: 1", &four).0, 2);
+    assert!(decode("a:\n    b:\n        c: 1", &four).is_ok());
+
+    let tabs = DecodeOptions::new().with_indent(Indent::Tabs);
+    assert_eq!(decode("a:\n\tb: 1", &tabs).unwrap(), json!({"a": {"b": 1}}));
+    assert!(line_of("a:\n  b: 1", &tabs).1.contains("tabs"));
+
+    let auto = DecodeOptions::new().with_indent(Indent::Auto);
+    let (line, message) = line_of("a:\n   b:\n      c: 1\nd:\n  e: 2", &auto);
+    assert_eq!(line, 5);
+    assert!(
+        message.contains("multiple of 3 spaces, as on line 2"),
+        "{}",
+        message
+    );
+
+    // Without strict mode, indentation is only used to find nesting.
+    let lenient = DecodeOptions::new().with_strict(false);
+    assert_eq!(
+        decode("a:\n   b: 1\nc: 2", &lenient).unwrap(),
+        json!({"a": {"b": 1}, "c": 2})
+    );
+}
+
+#[test]
+fn test_duplicate_keys() {
+    let input = "id: 1\nuser:\n  name: Ada\n  id: 7\n  name: Bob\nid: 2";
+
+    match decode_default(input) {
+        Err(ToonError::ParseError {
+            line,
+            column,
+            message,
+            context: Some(context),
+        }) => {
+            assert_eq!((line, column), (5, 3));
+            assert!(message.contains("'name'"), "{}", message);
+            assert_eq!(context.related_line, Some(3));
+            assert_eq!(context.source_line, "  name: Bob");
+        }
+        other => panic!("expected a duplicate key error, got {:?}", other),
+    }
+    let quoted = decode_default("a: 1\n\"a\": 2").unwrap_err();
+    assert_eq!(quoted.context().unwrap().related_line, Some(1));
+
+    let policy = |policy| decode(input, &DecodeOptions::new().with_duplicate_keys(policy)).unwrap();
+    assert_eq!(
+        policy(DuplicateKeys::FirstWins),
+        json!({"id": 1, "user": {"name": "Ada", "id": 7}})
+    );
+    assert_eq!(
+        policy(DuplicateKeys::LastWins),
+        json!({"id": 2, "user": {"name": "Bob", "id": 7}})
+    );
+    assert_eq!(
+        policy(DuplicateKeys::CollectArray),
+        json!({"id": [1, 2], "user": {"name": ["Ada", "Bob"], "id": 7}})
+    );
+    assert_eq!(
+        decode(
+            "tags[1]: x\ntags[2]: y,z\ntags: w",
+            &DecodeOptions::new().with_duplicate_keys(DuplicateKeys::CollectArray)
+        )
+        .unwrap(),
+        json!({"tags": [["x"], ["y", "z"], "w"]})
+    );
+
+    // Lenient decoding keeps the last value unless a policy is set.
+    let lenient = DecodeOptions::new().with_strict(false);
+    assert_eq!(decode("a: 1\na: 2", &lenient).unwrap(), json!({"a": 2}));
+    assert!(decode(
+        "a: 1\na: 2",
+        &lenient.with_duplicate_keys(DuplicateKeys::Error)
+    )
+    .is_err());
+
+    let err = rtoon::from_toon::<serde_json::Value>("a: 1\na: 2", None).unwrap_err();
+    assert!(matches!(err, ToonError::ParseError { line: 2, .. }));
+}
+
+#[test]
+fn test_duplicate_keys_from_toon() {
+    use std::collections::HashMap;
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Pair {
+        a: i64,
+        b: i64,
+    }
+
+    let input = "a: 1\nb: 3\na: 2";
+    let options = |policy| DecodeOptions::new().with_duplicate_keys(policy);
+
+    assert!(rtoon::from_toon::<Pair>(input, Some(&options(DuplicateKeys::Error))).is_err());
+    assert!(
+        rtoon::from_toon::<HashMap<String, i64>>(input, Some(&options(DuplicateKeys::Error)))
+            .is_err()
+    );
+
+    let first = options(DuplicateKeys::FirstWins);
+    assert_eq!(
+        rtoon::from_toon::<Pair>(input, Some(&first)).unwrap(),
+        Pair { a: 1, b: 3 }
+    );
+    let map: HashMap<String, i64> = rtoon::from_toon(input, Some(&first)).unwrap();
+    assert_eq!(map["a"], 1);
+
+    let last = options(DuplicateKeys::LastWins);
+    assert_eq!(
+        rtoon::from_toon::<Pair>(input, Some(&last)).unwrap(),
+        Pair { a: 2, b: 3 }
+    );
+    let map: HashMap<String, i64> = rtoon::from_toon(input, Some(&last)).unwrap();
+    assert_eq!(map["a"], 2);
+
+    let collect = options(DuplicateKeys::CollectArray);
+    let value: serde_json::Value = rtoon::from_toon(input, Some(&collect)).unwrap();
+    assert_eq!(value, decode(input, &collect).unwrap());
+    assert_eq!(value, json!({"a": [1, 2], "b": 3}));
+    let map: HashMap<String, Vec<i64>> = rtoon::from_toon("a: 1\na: 2", Some(&collect)).unwrap();
+    assert_eq!(map["a"], [1, 2]);
+
+    // Without a policy, lenient decoding streams: maps keep the last value
+    // and structs reject the repeated field with its position.
+    let lenient = DecodeOptions::new().with_strict(false);
+    let map: HashMap<String, i64> = rtoon::from_toon(input, Some(&lenient)).unwrap();
+    assert_eq!(map["a"], 2);
+    let err = rtoon::from_toon::<Pair>(input, Some(&lenient)).unwrap_err();
+    assert!(matches!(err, ToonError::ParseError { line: 3, .. }));
+
+    #[derive(serde::Deserialize, Debug, PartialEq)]
+    struct Borrowed<'a> {
+        name: &'a str,
+        count: i64,
+    }
+
+    let borrowed: Borrowed = rtoon::from_toon_str("name: x\ncount: 1", Some(&lenient)).unwrap();
+    assert_eq!(
+        borrowed,
+        Borrowed {
+            name: "x",
+            count: 1
+        }
+    );
+}
+
+#[test]
+fn test_limits() {
+    let limited = |limits: Limits| DecodeOptions::new().with_limits(limits);
+    let exceeded = |input: &str, limits: Limits| match decode(input, &limited(limits)) {
+        Err(ToonError::LimitExceeded {
+            limit,
+            max,
+            found,
+            line,
+            context,
+        }) => {
+            assert!(context.is_some());
+            (limit, max, found, line)
+        }
+        other => panic!("expected a limit error for {:?}, got {:?}", input, other),
+    };
+
+    // A huge declared length fails at the header, before any rows are read.
+    let arrays = Limits::new().with_max_array_length(1000);
+    assert_eq!(
+        exceeded("ok[2]: 1,2\nids[999999999]: 1", arrays),
+        (Limit::ArrayLength, 1000, 999999999, 2)
+    );
+    assert!(decode("ids[1000]: 1", &limited(arrays).repair()).is_ok());
+
+    let nested = "a:\n  b:\n    c:\n      d: 1";
+    let depth = Limits::new().with_max_depth(2);
+    assert_eq!(exceeded(nested, depth), (Limit::Depth, 2, 3, 4));
+    assert_eq!(
+        exceeded("a:\n  b[1]:\n    - [1]: x", depth.with_max_depth(1)),
+        (Limit::Depth, 1, 2, 3)
+    );
+    assert!(decode(nested, &limited(depth.with_max_depth(3))).is_ok());
+
+    let deep: String = (0..50)
+        .map(|level| format!("{}k:\n", "  ".repeat(level)))
+        .collect();
+    assert_eq!(
+        exceeded(&deep, depth.with_max_depth(20)),
+        (Limit::Depth, 20, 21, 22)
+    );
+
+    let bytes = Limits::new().with_max_document_bytes(8);
+    assert_eq!(
+        exceeded("a: 1\nb: 2", bytes),
+        (Limit::DocumentBytes, 8, 9, 1)
+    );
+    assert!(decode("a: 1\nb: 2", &limited(bytes.with_max_document_bytes(9))).is_ok());
+
+    let strings = Limits::new().with_max_string_length(5);
+    assert_eq!(
+        exceeded("a: short\nb: longer", strings),
+        (Limit::StringLength, 5, 6, 2)
+    );
+    assert_eq!(
+        exceeded("\"quoted key\": 1", strings),
+        (Limit::StringLength, 5, 10, 1)
+    );
+    assert_eq!(
+        exceeded("hello world", strings),
+        (Limit::StringLength, 5, 11, 1)
+    );
+
+    let keys = Limits::new().with_max_object_keys(2);
+    assert_eq!(
+        exceeded("a: 1\nb:\n  c: 2\n  d: 3\n  e: 4", keys),
+        (Limit::ObjectKeys, 2, 3, 5)
+    );
+    assert_eq!(
+        exceeded("rows[1]{a,b,c}:\n  1,2,3", keys),
+        (Limit::ObjectKeys, 2, 3, 1)
+    );
+
+    // The serde and streaming decoders apply the same limits.
+    let options = limited(arrays.with_max_object_keys(2));
+    let err = rtoon::from_toon::<serde_json::Value>("ids[5000]: 1", Some(&options)).unwrap_err();
+    assert!(matches!(
+        err,
+        ToonError::LimitExceeded {
+            limit: Limit::ArrayLength,
+            ..
+        }
+    ));
+    let err =
+        rtoon::from_toon::<serde_json::Value>("a: 1\nb: 2\nc: 3", Some(&options)).unwrap_err();
+    assert!(matches!(
+        err,
+        ToonError::LimitExceeded {
+            limit: Limit::ObjectKeys,
+            line: 3,
+            ..
+        }
+    ));
+
+    let input = "rows[5]{id}:\n  1\n  2\n  3\n  4\n  5\n";
+    let options = limited(Limits::new().with_max_document_bytes(30));
+    let err =
+        rtoon::from_reader::<_, serde_json::Value>(input.as_bytes(), Some(&options)).unwrap_err();
+    assert!(matches!(
+        err,
+        ToonError::LimitExceeded {
+            limit: Limit::DocumentBytes,
+            max: 30,
+            found: 31,
+            ..
+        }
+    ));
+    let rows = rtoon::de::Rows::<_, serde_json::Value>::new(input.as_bytes(), "rows", &options)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>();
+    assert!(matches!(
+        rows,
+        Err(ToonError::LimitExceeded {
+            limit: Limit::DocumentBytes,
+            ..
+        })
+    ));
+}
@@ -1,6 +1,10 @@
 use rtoon::{
     decode_default,
+    encode,
     encode_default,
+    to_toon,
+    EncodeOptions,
+    FloatFormat,
 };
 use serde_json::json;
 
@@ -22,3 +26,136 @@ fn test_numeric_edge_cases() {
     assert_eq!(decoded["zero"], json!(0));
     assert_eq!(decoded["negative"], json!(-42));
 }
+
+#[test]
+fn test_full_integer_range() {
+    let numbers = json!({
+        "max": u64::MAX,
+        "above_i64": i64::MAX as u64 + 1,
+        "min": i64::MIN,
+        "ids": [18446744073709551615u64, 9223372036854775808u64]
+    });
+
+    let encoded = encode_default(&numbers).unwrap();
+    assert!(encoded.contains("max: 18446744073709551615"), "{}", encoded);
+    let decoded = decode_default(&encoded).unwrap();
+    assert_eq!(decoded, numbers);
+    assert_eq!(decoded["max"].as_u64(), Some(u64::MAX));
+
+    let row = decode_default("rows[1]{id}:\n  18446744073709551615").unwrap();
+    assert_eq!(row["rows"][0]["id"].as_u64(), Some(u64::MAX));
+    assert_eq!(
+        decode_default("18446744073709551615").unwrap(),
+        json!(u64::MAX)
+    );
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_arbitrary_precision_keeps_text() {
+    let input = "amount: 1.50\nsnowflake: 123456789012345678901234567890\nrate: \
+                 0.1000000000000000055511151231257827\nitems[2]: 2.000,-0.10";
+
+    let decoded = decode_default(input).unwrap();
+    assert_eq!(decoded["amount"].to_string(), "1.50");
+    assert_eq!(
+        decoded["snowflake"].to_string(),
+        "123456789012345678901234567890"
+    );
+    assert_eq!(decoded["items"][1].to_string(), "-0.10");
+
+    assert_eq!(encode_default(&decoded).unwrap(), input);
+    assert_eq!(rtoon::to_toon(&decoded, None).unwrap(), input);
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_arbitrary_precision_from_toon() {
+    #[derive(serde::Deserialize)]
+    struct Amounts {
+        amount: f64,
+        count: u32,
+        snowflake: serde_json::Number,
+    }
+
+    let input = "amount: 1.50\ncount: 3\nsnowflake: 123456789012345678901234567890\nhuge: 1e400";
+    let value: serde_json::Value = rtoon::from_toon(input, None).unwrap();
+    assert_eq!(value, decode_default(input).unwrap());
+    assert_eq!(value["amount"].to_string(), "1.50");
+    assert_eq!(value["huge"].to_string(), "1e+400");
+
+    let amounts: Amounts = rtoon::from_toon(input, None).unwrap();
+    assert_eq!(amounts.amount, 1.5);
+    assert_eq!(amounts.count, 3);
+    assert_eq!(
+        amounts.snowflake.to_string(),
+        "123456789012345678901234567890"
+    );
+
+    // A number an `f64` cannot hold is an error, not zero.
+    let err = rtoon::from_toon::<f64>("1e400", None).unwrap_err();
+    assert!(err.to_string().contains("out of range"), "{err}");
+}
+
+#[cfg(feature = "arbitrary_precision")]
+#[test]
+fn test_arbitrary_precision_never_writes_exponents() {
+    let decoded = decode_default("x: 1e5\ny: 1.5E-3\nz: -2.50e+1\nw: 12.5e-1").unwrap();
+    assert_eq!(
+        encode_default(&decoded).unwrap(),
+        "x: 100000\ny: 0.0015\nz: -25.0\nw: 1.25"
+    );
+    assert_eq!(
+        rtoon::to_toon(&decoded, None).unwrap(),
+        encode_default(&decoded).unwrap()
+    );
+
+    let value = json!({"a": 1e30, "b": 1e-7});
+    let encoded = encode_default(&value).unwrap();
+    assert_eq!(encoded, "a: 1000000000000000000000000000000\nb: 0.0000001");
+    assert_eq!(rtoon::to_toon(&value, None).unwrap(), encoded);
+}
+
+#[test]
+fn test_float_format_applies_everywhere() {
+    let value = json!({
+        "total": 0.1 + 0.2,
+        "count": 3,
+        "ratios": [0.126, -0.0001, 2.0],
+        "rows": [{"id": 1, "price": 9.999}, {"id": 2, "price": 0.5}],
+        "mixed": [[1.0 / 3.0], 2.0 / 3.0]
+    });
+
+    let fixed = EncodeOptions::new().with_float_format(FloatFormat::FixedDecimals(2));
+    let encoded = encode(&value, &fixed).unwrap();
+    assert_eq!(
+        encoded,
+        "total: 0.30\ncount: 3\nratios[3]: 0.13,0.00,2.00\nrows[2]{id,price}:\n  1,10.00\n  \
+         2,0.50\nmixed[2]:\n  - [1]: 0.33\n  - 0.67"
+    );
+    assert_eq!(to_toon(&value, Some(&fixed)).unwrap(), encoded);
+
+    let significant = EncodeOptions::new().with_float_format(FloatFormat::SignificantDigits(2));
+    let encoded = encode(&value, &significant).unwrap();
+    assert_eq!(
+        encoded,
+        "total: 0.30\ncount: 3\nratios[3]: 0.13,-0.00010,2.0\nrows[2]{id,price}:\n  1,10\n  \
+         2,0.50\nmixed[2]:\n  - [1]: 0.33\n  - 0.67"
+    );
+    assert_eq!(
+        decode_default(&encoded).unwrap()["rows"][0]["price"],
+        json!(10)
+    );
+
+    // Precision beyond what an `f64` needs is capped rather than rejected.
+    for format in [
+        FloatFormat::FixedDecimals(70_000),
+        FloatFormat::SignificantDigits(70_000),
+        FloatFormat::FixedDecimals(usize::MAX),
+    ] {
+        let options = EncodeOptions::new().with_float_format(format);
+        let encoded = encode(&json!(0.5), &options).unwrap();
+        assert_eq!(encoded.len(), 2 + 1100, "{format:?}");
+        assert_eq!(decode_default(&encoded).unwrap().as_f64(), Some(0.5));
+    }
+}
@@ -1,6 +1,13 @@
 use rtoon::{
+    decode,
     decode_default,
+    encode,
     encode_default,
+    from_toon,
+    to_toon,
+    DecodeOptions,
+    EncodeOptions,
+    ToonError,
 };
 use serde_json::json;
 
@@ -42,3 +49,72 @@ fn test_nested_structures() {
     let decoded = decode_default(&encoded).unwrap();
     assert_eq!(nested, decoded);
 }
+
+#[test]
+fn test_key_folding() {
+    let data = json!({
+        "server": {"http": {"port": 8080}},
+        "db": {"pool": {"min": 1, "max": 4}},
+        "paths": {"include": ["src", "tests"]},
+        "a.b": 1,
+        "empty": {"x": {}},
+        "odd": {"not valid": {"c": 1}}
+    });
+
+    let encoded = encode(&data, &EncodeOptions::new().with_key_folding(true)).unwrap();
+    assert_eq!(
+        encoded,
+        "server.http.port: 8080\ndb.pool:\n  min: 1\n  max: 4\npaths.include[2]: \
+         src,tests\n\"a.b\": 1\nempty.x:\nodd:\n  \"not valid\":\n    c: 1"
+    );
+
+    let folding = EncodeOptions::new().with_key_folding(true);
+    assert_eq!(to_toon(&data, Some(&folding)).unwrap(), encoded);
+
+    let options = DecodeOptions::new().with_expand_paths(true);
+    let decoded = decode(&encoded, &options).unwrap();
+    assert_eq!(decoded["server"], json!({"http": {"port": 8080}}));
+    assert_eq!(decoded["db"], data["db"]);
+    assert_eq!(decoded["paths"], data["paths"]);
+    assert_eq!(decoded["a.b"], json!(1));
+    assert_eq!(decoded["odd"], data["odd"]);
+
+    #[derive(serde::Deserialize)]
+    struct Config {
+        server: Server,
+    }
+    #[derive(serde::Deserialize)]
+    struct Server {
+        http: Http,
+    }
+    #[derive(serde::Deserialize)]
+    struct Http {
+        port: u16,
+    }
+    let config: Config = from_toon(&encoded, Some(&options)).unwrap();
+    assert_eq!(config.server.http.port, 8080);
+}
+
+#[test]
+fn test_path_expansion() {
+    let options = DecodeOptions::new().with_expand_paths(true);
+
+    let decoded = decode("a.b: 1\na.c: 2\nx:\n  y.z: 3\n\"q.r\": 4", &options).unwrap();
+    assert_eq!(
+        decoded,
+        json!({"a": {"b": 1, "c": 2}, "x": {"y": {"z": 3}}, "q.r": 4})
+    );
+
+    // Without expansion, dotted keys are literal.
+    assert_eq!(decode_default("a.b: 1").unwrap(), json!({"a.b": 1}));
+
+    for input in ["a.b: 1\na.b: 2", "a: 1\na.b: 2", "a.b: 1\na:\n  b: 2"] {
+        match decode(input, &options) {
+            Err(ToonError::ParseError { line, message, .. }) => {
+                assert_eq!(line, 2);
+                assert!(message.contains("conflicts"), "{}", message);
+            }
+            other => panic!("expected conflict for {:?}, got {:?}", input, other),
+        }
+    }
+}
@@ -1,6 +1,11 @@
 use rtoon::{
+    decode,
     decode_default,
+    encode,
     encode_default,
+    types::Indent,
+    DecodeOptions,
+    EncodeOptions,
 };
 use serde_json::json;
 
@@ -40,3 +45,24 @@ fn test_comprehensive_round_trips() {
         );
     }
 }
+
+#[test]
+fn test_indent_round_trips() {
+    let value = json!({
+        "a": {"b": {"c": 1}, "d": [1, 2]},
+        "rows": [{"x": 1, "y": "p q"}, {"x": 2, "y": "r"}],
+        "e": 2
+    });
+
+    for (encode_indent, decode_indent) in [
+        (Indent::Spaces(4), Indent::Spaces(4)),
+        (Indent::Spaces(3), Indent::Spaces(3)),
+        (Indent::Tabs, Indent::Tabs),
+        (Indent::Spaces(4), Indent::Auto),
+        (Indent::Tabs, Indent::Auto),
+    ] {
+        let encoded = encode(&value, &EncodeOptions::new().with_indent(encode_indent)).unwrap();
+        let options = DecodeOptions::new().with_indent(decode_indent);
+        assert_eq!(decode(&encoded, &options).unwrap(), value, "{}", encoded);
+    }
+}